@@ -0,0 +1,162 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use nih_plug::prelude::{AtomicF32, Buffer};
+
+pub const AB_CLIP_SECONDS: f32 = 4.0;
+const AB_CLIP_FADE_MS: f32 = 5.0;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AbClipStatus {
+    #[default]
+    Idle = 0,
+    Recording = 1,
+    /// Looping the uncorrected input
+    PlayingPre = 2,
+    /// Looping the corrected output
+    PlayingPost = 3,
+}
+
+impl From<u8> for AbClipStatus {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Recording,
+            2 => Self::PlayingPre,
+            3 => Self::PlayingPost,
+            _ => Self::Idle,
+        }
+    }
+}
+
+/// The editor's side of the A/B clip
+#[derive(Default)]
+pub struct AbClipControl {
+    capture_requested: AtomicBool,
+    stop_requested: AtomicBool,
+    status: AtomicU8,
+    progress: AtomicF32,
+}
+
+impl AbClipControl {
+    pub fn request_capture(&self) {
+        self.capture_requested.store(true, Ordering::Relaxed);
+    }
+
+    pub fn request_stop(&self) {
+        self.stop_requested.store(true, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> AbClipStatus {
+        self.status.load(Ordering::Relaxed).into()
+    }
+
+    /// How far into recording or playing back the clip we are, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        self.progress.load(Ordering::Relaxed)
+    }
+}
+
+/// Records a few seconds of input and output, then loops them back alternately in place of the plugin's output so
+/// the exact same passage can be compared with and without correction
+#[derive(Default)]
+pub struct AbClip {
+    pre: Vec<(f32, f32)>,
+    post: Vec<(f32, f32)>,
+    pos: usize,
+    fade_len: usize,
+    status: AbClipStatus,
+}
+
+impl AbClip {
+    pub fn initialize(&mut self, sample_rate: f32) {
+        let len = (sample_rate * AB_CLIP_SECONDS).round() as usize;
+        self.pre.resize(len, (0.0, 0.0));
+        self.post.resize(len, (0.0, 0.0));
+        self.fade_len = (sample_rate * AB_CLIP_FADE_MS / 1000.0).round() as usize;
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.pos = 0;
+        self.status = AbClipStatus::Idle;
+    }
+
+    /// Handles requests from the editor, and records the input if we're capturing. Call this before processing
+    pub fn capture_input(&mut self, buffer: &mut Buffer, control: &AbClipControl) {
+        if control.stop_requested.swap(false, Ordering::Relaxed) {
+            self.reset();
+        }
+        if control.capture_requested.swap(false, Ordering::Relaxed) {
+            self.pos = 0;
+            self.status = AbClipStatus::Recording;
+        }
+
+        if self.status == AbClipStatus::Recording {
+            for (idx, mut channel_samples) in buffer.iter_samples().enumerate() {
+                if let Some(frame) = self.pre.get_mut(self.pos + idx) {
+                    *frame = (
+                        *channel_samples.get_mut(0).unwrap(),
+                        *channel_samples.get_mut(1).unwrap(),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Records the output if we're capturing, or replaces it with the clip if we're playing it back. Call this after
+    /// processing
+    pub fn process_output(&mut self, buffer: &mut Buffer, control: &AbClipControl) {
+        match self.status {
+            AbClipStatus::Idle => {}
+            AbClipStatus::Recording => {
+                for (idx, mut channel_samples) in buffer.iter_samples().enumerate() {
+                    if let Some(frame) = self.post.get_mut(self.pos + idx) {
+                        *frame = (
+                            *channel_samples.get_mut(0).unwrap(),
+                            *channel_samples.get_mut(1).unwrap(),
+                        );
+                    }
+                }
+
+                self.pos += buffer.samples();
+                if self.pos >= self.pre.len() {
+                    self.pos = 0;
+                    self.status = AbClipStatus::PlayingPre;
+                }
+            }
+            AbClipStatus::PlayingPre | AbClipStatus::PlayingPost => {
+                for mut channel_samples in buffer.iter_samples() {
+                    let clip = if self.status == AbClipStatus::PlayingPre {
+                        &self.pre
+                    } else {
+                        &self.post
+                    };
+                    let (left, right) = clip[self.pos];
+
+                    // fade in and out at the loop points so switching between the two doesn't click
+                    let distance_to_edge = self.pos.min(clip.len() - 1 - self.pos);
+                    let fade = (distance_to_edge as f32 / self.fade_len.max(1) as f32).min(1.0);
+
+                    *channel_samples.get_mut(0).unwrap() = left * fade;
+                    *channel_samples.get_mut(1).unwrap() = right * fade;
+
+                    self.pos += 1;
+                    if self.pos >= clip.len() {
+                        self.pos = 0;
+                        self.status = if self.status == AbClipStatus::PlayingPre {
+                            AbClipStatus::PlayingPost
+                        } else {
+                            AbClipStatus::PlayingPre
+                        };
+                    }
+                }
+            }
+        }
+
+        control.status.store(self.status as u8, Ordering::Relaxed);
+        control.progress.store(
+            self.pos as f32 / self.pre.len().max(1) as f32,
+            Ordering::Relaxed,
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use nih_plug::prelude::{AtomicF32, Buffer};
+
+/// Every FFT size the analyzer can do, from fast and coarse to slow and detailed
+pub const FFT_SIZES: [usize; 5] = [1024, 2048, 4096, 8192, 16384];
+pub const DEFAULT_FFT_SIZE: usize = 4096;
+/// How many frames the analyzer keeps around, which is enough for the largest FFT
+pub const ANALYZER_BUFFER_LEN: usize = FFT_SIZES[FFT_SIZES.len() - 1];
+
+/// The most recent (uncorrected) input, for the spectrum analyzer in the editor
+pub struct AnalyzerInput {
+    frames: Box<[(AtomicF32, AtomicF32)]>,
+    write_idx: AtomicUsize,
+    sample_rate: AtomicF32,
+}
+
+impl Default for AnalyzerInput {
+    fn default() -> Self {
+        Self {
+            frames: (0..ANALYZER_BUFFER_LEN)
+                .map(|_| Default::default())
+                .collect(),
+            write_idx: AtomicUsize::new(0),
+            sample_rate: AtomicF32::new(44100.0),
+        }
+    }
+}
+
+impl AnalyzerInput {
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate.load(Ordering::Relaxed)
+    }
+
+    pub fn set_sample_rate(&self, sample_rate: f32) {
+        self.sample_rate.store(sample_rate, Ordering::Relaxed);
+    }
+
+    /// Appends a block of samples. This is called from the audio thread
+    pub fn write(&self, buffer: &mut Buffer) {
+        let mut write_idx = self.write_idx.load(Ordering::Relaxed);
+
+        for mut channel_samples in buffer.iter_samples() {
+            let (left, right) = &self.frames[write_idx];
+            left.store(*channel_samples.get_mut(0).unwrap(), Ordering::Relaxed);
+            right.store(*channel_samples.get_mut(1).unwrap(), Ordering::Relaxed);
+
+            write_idx = (write_idx + 1) % self.frames.len();
+        }
+
+        self.write_idx.store(write_idx, Ordering::Relaxed);
+    }
+
+    /// Fills `left` and `right` with the most recent frames, oldest first
+    pub fn read_latest(&self, left: &mut [f32], right: &mut [f32]) {
+        let len = left.len().min(right.len()).min(self.frames.len());
+        let start = self.write_idx.load(Ordering::Relaxed) + self.frames.len() - len;
+
+        for (idx, (left, right)) in left.iter_mut().zip(right.iter_mut()).take(len).enumerate() {
+            let (frame_left, frame_right) = &self.frames[(start + idx) % self.frames.len()];
+            *left = frame_left.load(Ordering::Relaxed);
+            *right = frame_right.load(Ordering::Relaxed);
+        }
+    }
+}
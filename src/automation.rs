@@ -0,0 +1,137 @@
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use nih_plug::params::persist::PersistentField;
+
+use crate::persist::{CompactCodec, Compressed};
+
+/// Correction angles over time, imported from a CSV file so they can be played back instead of detecting the angle
+/// live. Every point is a time in seconds and an angle in degrees relative to the center, with positive angles
+/// leaning to the right
+#[derive(Default)]
+pub struct AngleAutomation {
+    points: RwLock<Vec<(f64, f32)>>,
+}
+
+impl AngleAutomation {
+    /// Replaces the automation with the contents of a CSV file. Returns how many points were imported
+    pub fn import(&self, path: &Path) -> Result<usize, String> {
+        let csv = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let points = parse_csv(&csv)?;
+        let num_points = points.len();
+
+        *self.points.write().unwrap() = points;
+        Ok(num_points)
+    }
+
+    pub fn clear(&self) {
+        self.points.write().unwrap().clear();
+    }
+
+    /// How many points there are and how long they last in seconds, or `None` if nothing's imported
+    pub fn summary(&self) -> Option<(usize, f64)> {
+        let points = self.points.read().unwrap();
+        let (first, last) = (points.first()?, points.last()?);
+
+        Some((points.len(), last.0 - first.0))
+    }
+
+    /// The angle at `seconds`, interpolated between the surrounding points. This is called from the audio thread, so
+    /// it returns `None` instead of waiting if the editor is busy importing
+    pub fn angle_at(&self, seconds: f64) -> Option<f32> {
+        let points = self.points.try_read().ok()?;
+        let idx = points.partition_point(|&(time, _)| time <= seconds);
+
+        match (idx.checked_sub(1).map(|idx| points[idx]), points.get(idx)) {
+            (Some((start_time, start_angle)), Some(&(end_time, end_angle))) => {
+                let t = ((seconds - start_time) / (end_time - start_time)) as f32;
+                Some((end_angle - start_angle).mul_add(t, start_angle))
+            }
+            (Some((_, angle)), None) | (None, Some(&(_, angle))) => Some(angle),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Reads `time,angle` rows. Commas, semicolons, and tabs all work as separators, and a header row is skipped
+fn parse_csv(csv: &str) -> Result<Vec<(f64, f32)>, String> {
+    let mut points = Vec::new();
+
+    for (line_idx, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split([',', ';', '\t']).map(str::trim);
+        match (
+            fields.next().map(str::parse::<f64>),
+            fields.next().map(str::parse::<f32>),
+        ) {
+            (Some(Ok(time)), Some(Ok(angle))) => points.push((time, angle)),
+            _ if points.is_empty() && line_idx == 0 => {}
+            _ => {
+                return Err(format!(
+                    "Line {}: expected a time and an angle",
+                    line_idx + 1
+                ))
+            }
+        }
+    }
+
+    if points.is_empty() {
+        return Err("The file doesn't contain any points".to_string());
+    }
+
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(points)
+}
+
+pub struct AutomationPoints(Vec<(f64, f32)>);
+
+impl CompactCodec for AutomationPoints {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        for &(time, angle) in &self.0 {
+            bytes.extend_from_slice(&time.to_le_bytes());
+            bytes.extend_from_slice(&angle.to_le_bytes());
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() % 12 != 0 {
+            return None;
+        }
+
+        Some(Self(
+            bytes
+                .chunks_exact(12)
+                .map(|point| {
+                    let (time, angle) = point.split_at(8);
+                    (
+                        f64::from_le_bytes(time.try_into().unwrap()),
+                        f32::from_le_bytes(angle.try_into().unwrap()),
+                    )
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// The automation is saved with the project, so "analyze once, print later" survives reopening it
+impl<'a> PersistentField<'a, Compressed<AutomationPoints>> for Arc<AngleAutomation> {
+    fn set(&self, new_value: Compressed<AutomationPoints>) {
+        *self.points.write().unwrap() = new_value.0 .0;
+    }
+
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&Compressed<AutomationPoints>) -> R,
+    {
+        f(&Compressed(AutomationPoints(
+            self.points.read().unwrap().clone(),
+        )))
+    }
+}
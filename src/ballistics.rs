@@ -0,0 +1,70 @@
+/// One click timing setups. Each one sets the reaction and release times, the analysis window, and the slew limit to
+/// values that work well together, so they don't all have to be tuned by hand
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Ballistics {
+    Fast,
+    Medium,
+    Slow,
+    Mastering,
+}
+
+impl Ballistics {
+    pub const ALL: [Self; 4] = [Self::Fast, Self::Medium, Self::Slow, Self::Mastering];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fast => "FAST",
+            Self::Medium => "MEDIUM",
+            Self::Slow => "SLOW",
+            Self::Mastering => "MASTERING",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Fast => "Follows sources moving around closely, for live use and sound design",
+            Self::Medium => "Keeps up with the mix without chasing every hit",
+            Self::Slow => "Only corrects imbalances that last, with a long release",
+            Self::Mastering => {
+                "Barely moves: a long window and release, and a slew limit slow enough to never be heard"
+            }
+        }
+    }
+
+    pub fn reaction_ms(self) -> f32 {
+        match self {
+            Self::Fast => 2.0,
+            Self::Medium => 8.0,
+            Self::Slow => 20.0,
+            Self::Mastering => 25.0,
+        }
+    }
+
+    /// `None` keeps the release linked to the reaction time
+    pub fn release_ms(self) -> Option<f32> {
+        match self {
+            Self::Fast | Self::Medium => None,
+            Self::Slow => Some(100.0),
+            Self::Mastering => Some(250.0),
+        }
+    }
+
+    pub fn analysis_window_ms(self) -> f32 {
+        match self {
+            Self::Fast => 20.0,
+            Self::Medium => 50.0,
+            Self::Slow => 150.0,
+            Self::Mastering => 500.0,
+        }
+    }
+
+    /// In degrees per second
+    pub fn max_slew_rate(self) -> f32 {
+        match self {
+            Self::Fast => 90.0,
+            Self::Medium => 45.0,
+            Self::Slow => 15.0,
+            Self::Mastering => 5.0,
+        }
+    }
+}
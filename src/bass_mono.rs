@@ -0,0 +1,37 @@
+use nih_plug::prelude::Buffer;
+
+use crate::multiband::{Crossover, CrossoverSlope};
+
+/// Folds everything below the cutoff down to mono, and leaves the width above it alone
+#[derive(Default)]
+pub struct BassMono {
+    sample_rate: f32,
+    crossover: Crossover,
+}
+
+impl BassMono {
+    pub fn initialize(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.crossover.invalidate();
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.crossover.reset();
+    }
+
+    pub fn process(&mut self, buffer: &mut Buffer, frequency: f32, slope: CrossoverSlope) {
+        self.crossover.set(self.sample_rate, frequency, slope);
+
+        for mut channel_samples in buffer.iter_samples() {
+            let left = *channel_samples.get_mut(0).unwrap();
+            let right = *channel_samples.get_mut(1).unwrap();
+
+            let ((low_left, low_right), (high_left, high_right)) =
+                self.crossover.split(left, right);
+            let low_mono = (low_left + low_right) * 0.5;
+            *channel_samples.get_mut(0).unwrap() = high_left + low_mono;
+            *channel_samples.get_mut(1).unwrap() = high_right + low_mono;
+        }
+    }
+}
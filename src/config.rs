@@ -0,0 +1,96 @@
+use std::{fs, path::PathBuf};
+
+use nih_plug::{nih_log, prelude::Enum};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+/// House defaults from `centered.toml` in the user's config directory, loaded once per process. These only apply to
+/// new instances, anything restored from a project or preset still wins
+pub static GLOBAL_CONFIG: Lazy<GlobalConfig> = Lazy::new(GlobalConfig::load);
+
+/// Where Centered keeps its configuration files
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("centered"))
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct GlobalConfig {
+    pub parameters: ParameterDefaults,
+    pub editor: EditorDefaults,
+    pub metering: MeteringDefaults,
+    pub remote: RemoteConfig,
+}
+
+/// Default parameter values, in the same units the parameters show. Enums use their IDs, e.g.
+/// `silence-behavior = "center"`
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ParameterDefaults {
+    pub correction_amount: Option<f32>,
+    pub reaction_time: Option<f32>,
+    pub lookahead: Option<f32>,
+    pub snap: Option<bool>,
+    pub mode: Option<String>,
+    pub silence_behavior: Option<String>,
+    pub estimator: Option<String>,
+    pub detection_decimation: Option<i32>,
+    pub input_gain: Option<f32>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct EditorDefaults {
+    pub show_meters: Option<bool>,
+    pub show_controls: Option<bool>,
+    pub show_history: Option<bool>,
+    pub history_span: Option<f32>,
+    pub knob_drag_distance: Option<f32>,
+    /// The color the scope draws out of phase samples in, as a `#rrggbb` hex code
+    pub anti_phase_color: Option<String>,
+    /// How long the transport needs to play before stopping it suggests a fixed correction
+    pub lean_suggestion_seconds: Option<f32>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct MeteringDefaults {
+    /// Show 4x oversampled true peak instead of sample peak on the post meters
+    pub true_peak: Option<bool>,
+    /// How long the peak meters take to fall
+    pub peak_decay_ms: Option<f64>,
+    /// Where the gain staging advisor tries to put the input's peaks
+    pub target_peak_db: Option<f32>,
+}
+
+/// Only used with the `remote` feature
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RemoteConfig {
+    /// Streams metering frames to WebSocket clients on this port. Off if unset
+    pub websocket_port: Option<u16>,
+    /// Serves the remote UI to the whole network on this port. Off if unset
+    pub http_port: Option<u16>,
+}
+
+impl GlobalConfig {
+    fn load() -> Self {
+        let Some(path) = config_dir().map(|dir| dir.join("centered.toml")) else {
+            return Self::default();
+        };
+        let Ok(config) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        toml::from_str(&config).unwrap_or_else(|err| {
+            nih_log!("Ignoring {}: {err}", path.display());
+            Self::default()
+        })
+    }
+}
+
+/// Looks up an enum parameter's default by its ID, falling back to `fallback` if it's missing or unknown
+pub fn enum_default<T: Enum>(id: Option<&str>, fallback: T) -> T {
+    id.and_then(|id| T::ids()?.iter().position(|candidate| *candidate == id))
+        .map_or(fallback, T::from_index)
+}
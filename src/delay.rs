@@ -0,0 +1,172 @@
+use nih_plug::prelude::Buffer;
+
+/// The largest offset between the channels that gets looked for, plenty for spaced pairs
+pub const MAX_CHANNEL_DELAY_MS: f32 = 5.0;
+/// How long the cross-correlation remembers what it heard
+const CORRELATION_MEMORY_SECONDS: f32 = 1.0;
+/// The estimate only moves while the correlation's peak is at least this strong. Below that there's no clear offset
+/// to go by, so the last one stays
+const MIN_PEAK_CORRELATION: f32 = 0.5;
+/// How fast the alignment follows the estimate, in milliseconds of delay per second. This keeps the pitch shift that
+/// comes with changing a delay well below what can be heard
+const ALIGNMENT_SPEED_MS_PER_SECOND: f32 = 1.0;
+
+/// Finds how far one channel lags behind the other with a running cross-correlation, and delays the leading channel
+/// by that much so they line up again. The lagging channel passes through as is, so this adds no latency. Fractional
+/// delays get linearly interpolated
+#[derive(Default)]
+pub struct ChannelAligner {
+    sample_rate: f32,
+    max_lag: usize,
+    /// The recent input, a ring buffer with the newest frame at `history_idx`
+    history: Vec<(f32, f32)>,
+    history_idx: usize,
+    /// The cross-correlation for every lag from `-max_lag` to `max_lag`, worked out `max_lag` samples in the past so
+    /// every lag has samples to go by
+    correlation: Vec<f32>,
+    left_energy: f32,
+    right_energy: f32,
+    /// How much of the correlation is left after a sample
+    correlation_decay: f32,
+    /// The estimated delay in samples, positive when the right channel lags behind the left one
+    target_delay: f32,
+    /// The delay that's currently applied, gliding towards `target_delay`
+    delay: f32,
+    max_delay_step: f32,
+}
+
+impl ChannelAligner {
+    pub fn initialize(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.max_lag = (MAX_CHANNEL_DELAY_MS / 1000.0 * sample_rate).ceil() as usize;
+        self.history = vec![(0.0, 0.0); self.max_lag * 2 + 1];
+        self.correlation = vec![0.0; self.max_lag * 2 + 1];
+        self.correlation_decay = (-(CORRELATION_MEMORY_SECONDS * sample_rate).recip()).exp();
+        // in samples per sample, which works out the same at every sample rate
+        self.max_delay_step = ALIGNMENT_SPEED_MS_PER_SECOND / 1000.0;
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.history.fill((0.0, 0.0));
+        self.history_idx = 0;
+        self.correlation.fill(0.0);
+        self.left_energy = 0.0;
+        self.right_energy = 0.0;
+        self.target_delay = 0.0;
+        self.delay = 0.0;
+    }
+
+    /// The estimated offset in milliseconds, positive when the right channel lags behind the left one
+    pub fn delay_ms(&self) -> f32 {
+        self.target_delay / self.sample_rate * 1000.0
+    }
+
+    /// Estimates the offset from the block and lines the channels up. With `enabled` off the estimate stops and the
+    /// alignment glides back to nothing
+    pub fn process(&mut self, buffer: &mut Buffer, enabled: bool) {
+        if self.history.is_empty() || (!enabled && self.delay == 0.0) {
+            return;
+        }
+
+        if enabled {
+            let decay = self.correlation_decay.powi(buffer.samples() as i32);
+            for correlation in &mut self.correlation {
+                *correlation *= decay;
+            }
+            self.left_energy *= decay;
+            self.right_energy *= decay;
+        }
+
+        for mut channel_samples in buffer.iter_samples() {
+            self.history_idx = (self.history_idx + 1) % self.history.len();
+            self.history[self.history_idx] = (
+                *channel_samples.get_mut(0).unwrap(),
+                *channel_samples.get_mut(1).unwrap(),
+            );
+
+            if enabled {
+                self.correlate();
+            }
+
+            let target_delay = if enabled { self.target_delay } else { 0.0 };
+            self.delay +=
+                (target_delay - self.delay).clamp(-self.max_delay_step, self.max_delay_step);
+            *channel_samples.get_mut(0).unwrap() = self.delayed(self.delay.max(0.0)).0;
+            *channel_samples.get_mut(1).unwrap() = self.delayed((-self.delay).max(0.0)).1;
+        }
+
+        if enabled {
+            self.update_estimate();
+        }
+    }
+
+    /// Adds the newest frame to the cross-correlation. A peak at lag `d` means the right channel follows the left one
+    /// `d` samples later
+    fn correlate(&mut self) {
+        let (left, right) = self.history_at(self.max_lag);
+        self.left_energy = left.mul_add(left, self.left_energy);
+        self.right_energy = right.mul_add(right, self.right_energy);
+
+        // lag `d` pairs the right channel with the left channel from `d` samples before it, which is `max_lag + d`
+        // samples ago and so at the correlation's own index
+        let len = self.history.len();
+        for (age, correlation) in self.correlation.iter_mut().enumerate() {
+            let (delayed_left, _) = self.history[(self.history_idx + len - age) % len];
+            *correlation = right.mul_add(delayed_left, *correlation);
+        }
+    }
+
+    /// Moves the estimate to the correlation's peak, interpolated between lags
+    fn update_estimate(&mut self) {
+        let Some((peak_idx, &peak)) = self
+            .correlation
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        else {
+            return;
+        };
+
+        let energy = (self.left_energy * self.right_energy).sqrt();
+        if energy == 0.0 || peak / energy < MIN_PEAK_CORRELATION {
+            return;
+        }
+
+        // fits a parabola through the peak and its neighbors
+        let offset = match (
+            peak_idx.checked_sub(1).map(|idx| self.correlation[idx]),
+            self.correlation.get(peak_idx + 1),
+        ) {
+            (Some(before), Some(&after)) => {
+                let curvature = before - 2.0 * peak + after;
+                if curvature < 0.0 {
+                    (0.5 * (before - after) / curvature).clamp(-0.5, 0.5)
+                } else {
+                    0.0
+                }
+            }
+            _ => 0.0,
+        };
+        self.target_delay = peak_idx as f32 - self.max_lag as f32 + offset;
+    }
+
+    /// The frame from `age` samples ago
+    fn history_at(&self, age: usize) -> (f32, f32) {
+        let len = self.history.len();
+        self.history[(self.history_idx + len - age) % len]
+    }
+
+    /// The frame from `age` samples ago, linearly interpolated between samples
+    fn delayed(&self, age: f32) -> (f32, f32) {
+        let whole = (age as usize).min(self.history.len() - 2);
+        let fraction = age - whole as f32;
+        let (newer_left, newer_right) = self.history_at(whole);
+        let (older_left, older_right) = self.history_at(whole + 1);
+
+        (
+            (older_left - newer_left).mul_add(fraction, newer_left),
+            (older_right - newer_right).mul_add(fraction, newer_right),
+        )
+    }
+}
@@ -1,6 +1,11 @@
 use std::{
+    cell::Cell,
     f32::consts::PI,
-    sync::{atomic::Ordering, Arc},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
@@ -11,267 +16,665 @@ use cozy_ui::{
 };
 use form_urlencoded::byte_serialize;
 use nih_plug::{
+    context::gui::{AsyncExecutor, ParamSetter},
     editor::Editor,
-    params::{smoothing::AtomicF32, Param},
-    util::gain_to_db,
+    params::{smoothing::AtomicF32, FloatParam, Param},
+    util::{db_to_gain, gain_to_db},
 };
 use nih_plug_egui::{
     create_egui_editor,
     egui::{
-        include_image, pos2, remap_clamp, vec2, Align2, CentralPanel, Color32, FontData,
-        FontDefinitions, FontFamily, FontId, Frame, Id, Rect, RichText, Rounding, Sense, Stroke,
-        TopBottomPanel, Ui, Vec2, Window,
+        include_image, pos2, remap_clamp, vec2, Align2, Area, Button, CentralPanel, Color32,
+        Context, FontData, FontDefinitions, FontFamily, FontId, Frame, Id, Pos2, Rect, RichText,
+        Rounding, Sense, Slider, Stroke, TextEdit, TopBottomPanel, Ui, Vec2, Window,
     },
 };
 use once_cell::sync::Lazy;
 
+use audio_settings::AudioSettings;
+use charts::HistoryView;
+use file_panel::FilePanel;
+use param_widgets::{param_combo, param_slider, param_toggle, set_param};
+use pixels::PixelGrid;
+use recorder::ScopeRecorder;
+use scope::{draw_scope, GridCache, ScopeHistory, ANTI_PHASE_COLOR};
+use spectrum::SpectrumView;
+use toast::ResetToast;
+
+mod audio_settings;
+mod charts;
+mod file_panel;
+mod param_widgets;
+mod pixels;
+mod recorder;
+mod scope;
+mod spectrum;
+mod toast;
+
+#[cfg(test)]
+mod tests;
+
 static TRANSLATE_SIN_COS: Lazy<(f32, f32)> = Lazy::new(|| (PI / 4.0).sin_cos());
 
-use crate::{CenteredParams, GONIO_NUM_SAMPLES, VERSION};
+use crate::{
+    ab_clip::{AbClipControl, AbClipStatus},
+    analyzer::AnalyzerInput,
+    ballistics::Ballistics,
+    estimator::EstimatorKind,
+    file_analysis::FileAnalyzer,
+    file_player::FilePlayer,
+    input_stats::{target_peak_db, InputStats, MIN_SUGGESTION_DB},
+    integrated_angle::IntegratedAngle,
+    lean_suggestion::LeanSuggestion,
+    learn::LearnResult,
+    limiter::LimiterMeter,
+    mono_compat::{MonoCompatMeter, PASSING_SCORE},
+    multiband::NUM_BANDS,
+    null_test::NullTestControl,
+    param_locks::{
+        ParamLocks, CORRECTION_AMOUNT_ID, HIGH_BAND_AMOUNT_ID, INPUT_GAIN_ID, LOOKAHEAD_ID,
+        LOW_BAND_AMOUNT_ID, MID_BAND_AMOUNT_ID, OUTPUT_GAIN_ID, REACTION_TIME_ID, RELEASE_TIME_ID,
+    },
+    profile::Profile,
+    standalone::is_standalone,
+    Centered, CenteredParams, CenteredTask, CorrectionMode, CorrectionSource, SilenceBehavior,
+    GONIO_NUM_SAMPLES, LONG_MAX_LOOKAHEAD_MS, MAX_ANALYSIS_WINDOW_MS, VERSION,
+};
+
+/// How many pixels cozy-ui's knobs need to be dragged to cover their full range
+pub const KNOB_NATIVE_DRAG_DISTANCE: f32 = 100.0;
+const MIN_KNOB_DRAG_DISTANCE: f32 = 50.0;
+const MAX_KNOB_DRAG_DISTANCE: f32 = 2000.0;
+const KNOB_DIAMETER: f32 = 50.0;
+/// The per band knobs are smaller, so they don't crowd the main controls
+const BAND_KNOB_DIAMETER: f32 = 30.0;
+/// How often the editor redraws while the meters are suspended
+const IDLE_REPAINT_INTERVAL: Duration = Duration::from_millis(250);
 
 #[derive(Default)]
 struct EditorState {
     show_debug: bool,
     show_about: bool,
+    show_settings: bool,
+    show_gain_staging: bool,
+    show_integrated: bool,
+    show_null_test: bool,
+    show_advanced: bool,
+    show_spectrum: bool,
+    show_audio_settings: bool,
+    show_file: bool,
+    #[cfg(feature = "scripting")]
+    show_script: bool,
+    /// Double-clicking the scope hides everything else, for putting it on a second screen
+    fullscreen_scope: bool,
+    pre_scope: ScopeHistory,
+    post_scope: ScopeHistory,
+    scope_recorder: ScopeRecorder,
+    reset_toast: Option<ResetToast>,
+    /// The average lean from the last playback, waiting to be applied or dismissed
+    lean_suggestion: Option<f32>,
+    grid_cache: GridCache,
+    history_view: HistoryView,
+    spectrum_view: SpectrumView,
+    audio_settings: AudioSettings,
+    file_panel: FilePanel,
+    automation_path: String,
+    automation_message: Option<String>,
+    #[cfg(feature = "scripting")]
+    script_source: String,
+    #[cfg(feature = "scripting")]
+    script_message: Option<String>,
 }
 
 // shut up clippy this is an arc
-#[allow(clippy::needless_pass_by_value)]
+#[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
 pub fn editor(
     params: Arc<CenteredParams>,
+    async_executor: AsyncExecutor<Centered>,
     pre_stereo_data: Arc<[(AtomicF32, AtomicF32); GONIO_NUM_SAMPLES]>,
     post_stereo_data: Arc<[(AtomicF32, AtomicF32); GONIO_NUM_SAMPLES]>,
     pre_peak_meter: Arc<(AtomicF32, AtomicF32)>,
     post_peak_meter: Arc<(AtomicF32, AtomicF32)>,
+    pre_mono_compat: Arc<MonoCompatMeter>,
+    post_mono_compat: Arc<MonoCompatMeter>,
+    true_peak_clip: Arc<AtomicBool>,
+    limiter_meter: Arc<LimiterMeter>,
+    ab_clip: Arc<AbClipControl>,
+    null_test: Arc<NullTestControl>,
+    input_stats: Arc<InputStats>,
+    lean_suggestion: Arc<LeanSuggestion>,
+    learn_result: Arc<LearnResult>,
+    integrated_angle: Arc<IntegratedAngle>,
+    analyzer_input: Arc<AnalyzerInput>,
+    file_player: Arc<FilePlayer>,
+    file_analyzer: Arc<FileAnalyzer>,
+    watchdog_tripped: Arc<AtomicBool>,
     correcting_angle: Arc<AtomicF32>,
+    metering_idle: Arc<AtomicBool>,
+    mono_input: Arc<AtomicBool>,
+    channel_delay: Arc<AtomicF32>,
 ) -> Option<Box<dyn Editor>> {
     create_egui_editor(
         params.editor_state.clone(),
         EditorState::default(),
-        |ctx, _| {
-            cozy_ui::setup(ctx);
-            egui_extras::install_image_loaders(ctx);
-
-            let mut fonts = FontDefinitions::default();
-
-            fonts.font_data.insert(
-                "0x".to_string(),
-                FontData::from_static(include_bytes!("../assets/0xProto-Regular.ttf")),
-            );
-
-            fonts
-                .families
-                .entry(nih_plug_egui::egui::FontFamily::Name("0x".into()))
-                .or_default()
-                .insert(0, "0x".to_string());
-            ctx.set_fonts(fonts);
-        },
-        move |ctx, setter, state| {
-            let corr_angle_debug = correcting_angle.load(Ordering::Relaxed);
-            let correcting_angle = if corr_angle_debug == 0.0 {
-                0.0
-            } else {
-                correcting_angle.load(Ordering::Relaxed)
-                    + (90.0_f32.to_radians()
-                        * params.correction_amount.modulated_normalized_value())
-            };
-
-            TopBottomPanel::top("menu").show(ctx, |ui| {
-                ui.horizontal(|ui| {
-                    let button_clicked = ui.button("ABOUT").clicked();
-                    if ui.input(|input| input.modifiers.shift) {
-                        state.show_debug |= button_clicked;
-                    } else {
-                        state.show_about |= button_clicked;
-                    }
-                })
-            });
+        |ctx, _| setup(ctx),
+        update(
+            params,
+            move |task| async_executor.execute_background(task),
+            pre_stereo_data,
+            post_stereo_data,
+            pre_peak_meter,
+            post_peak_meter,
+            pre_mono_compat,
+            post_mono_compat,
+            true_peak_clip,
+            limiter_meter,
+            ab_clip,
+            null_test,
+            input_stats,
+            lean_suggestion,
+            learn_result,
+            integrated_angle,
+            analyzer_input,
+            file_player,
+            file_analyzer,
+            watchdog_tripped,
+            correcting_angle,
+            metering_idle,
+            mono_input,
+            channel_delay,
+        ),
+    )
+}
 
-            TopBottomPanel::bottom("controls").show(ctx, |ui| {
-                ui.horizontal(|ui| {
-                    centered(ctx, ui, |ui| {
-                        ui.add(
-                            knob(
-                                "knob_correct_amount",
-                                50.0,
-                                |v| match v {
-                                    Operation::Get => {
-                                        params.correction_amount.unmodulated_normalized_value()
-                                    }
-                                    Operation::Set(v) => {
-                                        setter
-                                            .set_parameter_normalized(&params.correction_amount, v);
-                                        v
-                                    }
-                                },
-                                || setter.begin_set_parameter(&params.correction_amount),
-                                || setter.end_set_parameter(&params.correction_amount),
-                            )
-                            .label("CORRECTION")
-                            .default_value(params.correction_amount.default_normalized_value())
-                            .modulated_value(params.correction_amount.modulated_normalized_value()),
-                        );
+/// Installs the fonts and image loaders the editor needs
+fn setup(ctx: &Context) {
+    cozy_ui::setup(ctx);
+    egui_extras::install_image_loaders(ctx);
 
-                        ui.add(
-                            knob(
-                                "knob_reaction_time",
-                                50.0,
-                                |v| match v {
-                                    Operation::Get => {
-                                        params.reaction_time.unmodulated_normalized_value()
-                                    }
-                                    Operation::Set(v) => {
-                                        setter.set_parameter_normalized(&params.reaction_time, v);
-                                        v
-                                    }
-                                },
-                                || setter.begin_set_parameter(&params.reaction_time),
-                                || setter.end_set_parameter(&params.reaction_time),
-                            )
-                            .label("REACTION TIME")
-                            .description(params.reaction_time.to_string())
-                            .default_value(params.reaction_time.default_normalized_value())
-                            .modulated_value(params.reaction_time.modulated_normalized_value()),
-                        );
+    let mut fonts = FontDefinitions::default();
 
-                        ui.add(
-                            knob(
-                                "knob_lookahead",
-                                50.0,
-                                |v| match v {
-                                    Operation::Get => {
-                                        params.lookahead.unmodulated_normalized_value()
-                                    }
-                                    Operation::Set(v) => {
-                                        setter.set_parameter_normalized(&params.lookahead, v);
-                                        v
-                                    }
-                                },
-                                || setter.begin_set_parameter(&params.lookahead),
-                                || setter.end_set_parameter(&params.lookahead),
-                            )
-                            .label("LOOKAHEAD")
-                            .description(params.lookahead.to_string())
-                            .default_value(params.lookahead.default_normalized_value())
-                            .modulated_value(params.lookahead.modulated_normalized_value()),
-                        );
+    fonts.font_data.insert(
+        "0x".to_string(),
+        FontData::from_static(include_bytes!("../assets/0xProto-Regular.ttf")),
+    );
+
+    fonts
+        .families
+        .entry(nih_plug_egui::egui::FontFamily::Name("0x".into()))
+        .or_default()
+        .insert(0, "0x".to_string());
+    ctx.set_fonts(fonts);
+}
+
+/// Draws a frame of the editor. This is split out from [`editor`] so it can be driven without a window, which is
+/// what the tests do. `execute_task` runs a [`CenteredTask`] in the background
+#[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
+fn update(
+    params: Arc<CenteredParams>,
+    execute_task: impl Fn(CenteredTask) + Send + Sync + 'static,
+    pre_stereo_data: Arc<[(AtomicF32, AtomicF32); GONIO_NUM_SAMPLES]>,
+    post_stereo_data: Arc<[(AtomicF32, AtomicF32); GONIO_NUM_SAMPLES]>,
+    pre_peak_meter: Arc<(AtomicF32, AtomicF32)>,
+    post_peak_meter: Arc<(AtomicF32, AtomicF32)>,
+    pre_mono_compat: Arc<MonoCompatMeter>,
+    post_mono_compat: Arc<MonoCompatMeter>,
+    true_peak_clip: Arc<AtomicBool>,
+    limiter_meter: Arc<LimiterMeter>,
+    ab_clip: Arc<AbClipControl>,
+    null_test: Arc<NullTestControl>,
+    input_stats: Arc<InputStats>,
+    lean_suggestion: Arc<LeanSuggestion>,
+    learn_result: Arc<LearnResult>,
+    integrated_angle: Arc<IntegratedAngle>,
+    analyzer_input: Arc<AnalyzerInput>,
+    file_player: Arc<FilePlayer>,
+    file_analyzer: Arc<FileAnalyzer>,
+    watchdog_tripped: Arc<AtomicBool>,
+    correcting_angle: Arc<AtomicF32>,
+    metering_idle: Arc<AtomicBool>,
+    mono_input: Arc<AtomicBool>,
+    channel_delay: Arc<AtomicF32>,
+) -> impl Fn(&Context, &ParamSetter, &mut EditorState) + Send + Sync + 'static {
+    move |ctx, setter, state| {
+        // idle instances only need to check in every now and then for things like the lean suggestion
+        if metering_idle.load(Ordering::Relaxed) {
+            ctx.request_repaint_after(IDLE_REPAINT_INTERVAL);
+        } else {
+            ctx.request_repaint();
+        }
+
+        let corr_angle_debug = correcting_angle.load(Ordering::Relaxed);
+        let correcting_angle = if corr_angle_debug == 0.0 {
+            0.0
+        } else {
+            correcting_angle.load(Ordering::Relaxed)
+                + (90.0_f32.to_radians() * params.correction_amount.modulated_normalized_value())
+        };
+
+        let drag_scale =
+            KNOB_NATIVE_DRAG_DISTANCE / params.knob_drag_distance.load(Ordering::Relaxed);
+
+        if state
+            .file_panel
+            .handle_drops(ctx, &execute_task, &file_player, params.estimator.value())
+        {
+            state.show_file = true;
+        }
+
+        let fullscreen_scope = state.fullscreen_scope;
+
+        if let Some(toast) = &state.reset_toast {
+            if !toast.show(ctx, setter, &params) {
+                state.reset_toast = None;
+            }
+        }
+
+        if let Some(lean) = lean_suggestion.take() {
+            state.lean_suggestion = Some(lean);
+        }
+        if let Some(lean) = learn_result.take() {
+            apply_fixed_correction(setter, &params, lean);
+            set_param(setter, &params.learn, false);
+        }
+        if let Some(lean) = state.lean_suggestion {
+            Area::new(Id::new("lean_suggestion"))
+                .anchor(Align2::RIGHT_BOTTOM, vec2(-10.0, -10.0))
+                .show(ctx, |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(format!(
+                            "Average lean: {:.1}° {} — apply as a fixed correction?",
+                            lean.abs(),
+                            if lean >= 0.0 { "right" } else { "left" }
+                        ));
+                        ui.horizontal(|ui| {
+                            if ui.button("APPLY").clicked() {
+                                apply_fixed_correction(setter, &params, lean);
+                                state.lean_suggestion = None;
+                            }
+                            if ui.button("DISMISS").clicked() {
+                                state.lean_suggestion = None;
+                            }
+                        });
                     });
-                })
-            });
-            CentralPanel::default().show(ctx, |ui| {
-                Frame::canvas(ui.style())
-                    .stroke(Stroke::new(2.0, Color32::DARK_GRAY))
-                    .show(ui, |ui| {
-                        let (rect, _) = ui.allocate_at_least(
-                            ui.available_size_before_wrap(),
-                            Sense::focusable_noninteractive(),
-                        );
+                });
+        }
 
-                        let scope_rect =
-                            Rect::from_center_size(rect.center(), Vec2::splat(rect.height()))
-                                .shrink(20.0);
+        TopBottomPanel::top("menu").show_animated(ctx, !fullscreen_scope, |ui| {
+            ui.horizontal(|ui| {
+                let button_clicked = ui.button("ABOUT").clicked();
+                if ui.input(|input| input.modifiers.shift) {
+                    state.show_debug |= button_clicked;
+                } else {
+                    state.show_about |= button_clicked;
+                }
 
-                        let painter = ui.painter_at(rect);
-                        let center = rect.center();
+                state.show_settings |= ui.button("SETTINGS").clicked();
+                state.show_gain_staging |= ui.button("GAIN STAGING").clicked();
+                state.show_integrated |= ui.button("INTEGRATED").clicked();
+                state.show_null_test |= ui.button("NULL TEST").clicked();
+                state.show_advanced |= ui.button("ADVANCED").clicked();
+                state.show_spectrum |= ui.button("SPECTRUM").clicked();
 
-                        painter.line_segment(
-                            [scope_rect.center_top(), scope_rect.center_bottom()],
-                            Stroke::new(1.5, Color32::GRAY.gamma_multiply(0.5)),
-                        );
-                        painter.line_segment(
-                            [scope_rect.left_center(), scope_rect.right_center()],
-                            Stroke::new(1.5, Color32::GRAY.gamma_multiply(0.5)),
-                        );
+                #[cfg(feature = "scripting")]
+                if ui.button("SCRIPT").clicked() {
+                    state.script_source = params.detection_script.source();
+                    state.script_message = None;
+                    state.show_script = true;
+                }
 
-                        painter.line_segment(
-                            [
-                                scope_rect.min + (scope_rect.size() * 0.25),
-                                scope_rect.max - (scope_rect.size() * 0.25),
-                            ],
-                            Stroke::new(1.5, Color32::GRAY.gamma_multiply(0.55)),
-                        );
-                        painter.line_segment(
-                            [
-                                scope_rect.min + (scope_rect.size() * vec2(0.75, 0.25)),
-                                scope_rect.max - (scope_rect.size() * vec2(0.75, 0.25)),
-                            ],
-                            Stroke::new(1.5, Color32::GRAY.gamma_multiply(0.55)),
-                        );
+                if is_standalone() {
+                    if ui.button("AUDIO").clicked() {
+                        state.audio_settings.reload();
+                        state.show_audio_settings = true;
+                    }
+                }
+                state.show_file |= ui.button("FILE").clicked();
 
-                        painter.line_segment(
-                            [scope_rect.center_top(), scope_rect.left_center()],
-                            Stroke::new(1.5, Color32::GRAY),
-                        );
-                        painter.line_segment(
-                            [scope_rect.left_center(), scope_rect.center_bottom()],
-                            Stroke::new(1.5, Color32::GRAY),
-                        );
-                        painter.line_segment(
-                            [scope_rect.center_bottom(), scope_rect.right_center()],
-                            Stroke::new(1.5, Color32::GRAY),
-                        );
-                        painter.line_segment(
-                            [scope_rect.right_center(), scope_rect.center_top()],
-                            Stroke::new(1.5, Color32::GRAY),
-                        );
+                let mut true_peak = params.true_peak_metering.load(Ordering::Relaxed);
+                if ui.toggle_value(&mut true_peak, "TRUE PEAK").changed() {
+                    params
+                        .true_peak_metering
+                        .store(true_peak, Ordering::Relaxed);
+                    true_peak_clip.store(false, Ordering::Relaxed);
+                }
+
+                ui.separator();
+
+                let mut show_meters = params.show_meters.load(Ordering::Relaxed);
+                if ui.toggle_value(&mut show_meters, "METERS").changed() {
+                    params.show_meters.store(show_meters, Ordering::Relaxed);
+                }
+
+                let mut show_controls = params.show_controls.load(Ordering::Relaxed);
+                if ui.toggle_value(&mut show_controls, "CONTROLS").changed() {
+                    params.show_controls.store(show_controls, Ordering::Relaxed);
+                }
+
+                let mut show_history = params.show_history.load(Ordering::Relaxed);
+                if ui.toggle_value(&mut show_history, "HISTORY").changed() {
+                    params.show_history.store(show_history, Ordering::Relaxed);
+                }
+
+                // the minimal view is just the goniometer, clicking it again brings everything back
+                let minimal = !show_meters && !show_controls && !show_history;
+                if ui.selectable_label(minimal, "MINIMAL").clicked() {
+                    params.show_meters.store(minimal, Ordering::Relaxed);
+                    params.show_controls.store(minimal, Ordering::Relaxed);
+                    params.show_history.store(minimal, Ordering::Relaxed);
+                }
+
+                ui.separator();
+
+                let active_profile = Profile::from(params.profile.load(Ordering::Relaxed));
+                for profile in Profile::ALL {
+                    if profile == Profile::Custom {
+                        continue;
+                    }
+
+                    if ui
+                        .selectable_label(active_profile == profile, profile.name())
+                        .on_hover_text(profile.description())
+                        .clicked()
+                    {
+                        // clicking the active profile again goes back to plain parameters
+                        let profile = if active_profile == profile {
+                            Profile::Custom
+                        } else {
+                            profile
+                        };
+                        apply_profile(setter, &params, &input_stats, profile);
+                    }
+                }
+
+                if watchdog_tripped.load(Ordering::Relaxed)
+                    && ui
+                        .button(RichText::new("OUTPUT MUTED").color(Color32::RED))
+                        .on_hover_text(
+                            "The output contained NaN, infinity, or absurdly loud samples and \
+                             was muted. Click to dismiss",
+                        )
+                        .clicked()
+                {
+                    watchdog_tripped.store(false, Ordering::Relaxed);
+                }
+
+                if mono_input.load(Ordering::Relaxed) {
+                    ui.label("MONO INPUT").on_hover_text(
+                        "The input is mono, so the correction is suspended until it isn't anymore",
+                    );
+                }
 
-                        let (translate_sin, translate_cos) = *TRANSLATE_SIN_COS;
+                ui.separator();
 
-                        for (left, right) in pre_stereo_data.iter().map(|(left, right)| {
-                            (
-                                left.load(std::sync::atomic::Ordering::Relaxed)
-                                    .clamp(-1.0, 1.0),
-                                right
-                                    .load(std::sync::atomic::Ordering::Relaxed)
-                                    .clamp(-1.0, 1.0),
+                match ab_clip.status() {
+                    AbClipStatus::Idle => {
+                        if ui
+                            .button("CAPTURE A/B")
+                            .on_hover_text(
+                                "Records a few seconds of audio, then loops it with and \
+                                 without correction",
                             )
-                        }) {
-                            let dot_x = left * translate_cos - right * translate_sin;
-                            let dot_y = left * translate_sin + right * translate_cos;
-                            let offset = vec2(
-                                dot_x * scope_rect.width() / PI,
-                                dot_y * scope_rect.height() / PI,
-                            );
+                            .clicked()
+                        {
+                            ab_clip.request_capture();
+                        }
+                    }
+                    status => {
+                        let label = match status {
+                            AbClipStatus::Recording => "RECORDING",
+                            AbClipStatus::PlayingPre => "A: PRE",
+                            _ => "B: POST",
+                        };
+                        ui.label(format!("{label} {:.0}%", ab_clip.progress() * 100.0));
 
-                            painter.circle_filled(
-                                center + offset,
-                                1.5,
-                                Color32::WHITE.gamma_multiply((left.abs() + right.abs()) / 2.0),
-                            );
+                        if ui.button("STOP").clicked() {
+                            ab_clip.request_stop();
                         }
+                    }
+                }
 
-                        for (left, right) in post_stereo_data.iter().map(|(left, right)| {
-                            (
-                                left.load(std::sync::atomic::Ordering::Relaxed)
-                                    .clamp(-1.0, 1.0),
-                                right
-                                    .load(std::sync::atomic::Ordering::Relaxed)
-                                    .clamp(-1.0, 1.0),
-                            )
-                        }) {
-                            let dot_x = left * translate_cos - right * translate_sin;
-                            let dot_y = left * translate_sin + right * translate_cos;
-                            let offset = vec2(
-                                dot_x * scope_rect.width() / PI,
-                                dot_y * scope_rect.height() / PI,
-                            );
+                ui.separator();
 
-                            painter.circle_filled(
-                                center + offset,
-                                1.5,
-                                cozy_ui::colors::HIGHLIGHT_COL32.gamma_multiply((left.abs() + right.abs()) / 2.0),
-                            );
+                state.scope_recorder.show(ui);
+            })
+        });
+
+        let show_controls = params.show_controls.load(Ordering::Relaxed) && !fullscreen_scope;
+        TopBottomPanel::bottom("controls").show_animated(ctx, show_controls, |ui| {
+            ui.horizontal(|ui| {
+                centered(ctx, ui, |ui| {
+                    if let Some(previous_value) = param_knob(
+                        ui,
+                        setter,
+                        &params.input_gain,
+                        INPUT_GAIN_ID,
+                        "knob_input_gain",
+                        "INPUT",
+                        KNOB_DIAMETER,
+                        drag_scale,
+                        &params.param_locks,
+                    ) {
+                        state.reset_toast = Some(ResetToast::new(
+                            "INPUT",
+                            |params| &params.input_gain,
+                            previous_value,
+                        ));
+                    }
+                    if let Some(previous_value) = param_knob(
+                        ui,
+                        setter,
+                        &params.correction_amount,
+                        CORRECTION_AMOUNT_ID,
+                        "knob_correct_amount",
+                        "CORRECTION",
+                        KNOB_DIAMETER,
+                        drag_scale,
+                        &params.param_locks,
+                    ) {
+                        state.reset_toast = Some(ResetToast::new(
+                            "CORRECTION",
+                            |params| &params.correction_amount,
+                            previous_value,
+                        ));
+                    }
+                    if let Some(previous_value) = param_knob(
+                        ui,
+                        setter,
+                        &params.reaction_time,
+                        REACTION_TIME_ID,
+                        "knob_reaction_time",
+                        "ATTACK",
+                        KNOB_DIAMETER,
+                        drag_scale,
+                        &params.param_locks,
+                    ) {
+                        state.reset_toast = Some(ResetToast::new(
+                            "ATTACK",
+                            |params| &params.reaction_time,
+                            previous_value,
+                        ));
+                    }
+                    ui.add_enabled_ui(!params.release_linked.value(), |ui| {
+                        if let Some(previous_value) = param_knob(
+                            ui,
+                            setter,
+                            &params.release_time,
+                            RELEASE_TIME_ID,
+                            "knob_release_time",
+                            "RELEASE",
+                            KNOB_DIAMETER,
+                            drag_scale,
+                            &params.param_locks,
+                        ) {
+                            state.reset_toast = Some(ResetToast::new(
+                                "RELEASE",
+                                |params| &params.release_time,
+                                previous_value,
+                            ));
+                        }
+                    });
+                    // the long lookahead takes over from this knob, and gets a slider of its own in the extras. A
+                    // locked lookahead wins over the profile, so it stays usable for unlocking it
+                    let profile = Profile::from(params.profile.load(Ordering::Relaxed));
+                    let locked = params
+                        .param_locks
+                        .locked_normalized_value(LOOKAHEAD_ID)
+                        .is_some();
+                    let lookahead_enabled =
+                        !params.long_lookahead.value() && (locked || !profile.disables_lookahead());
+                    ui.add_enabled_ui(lookahead_enabled, |ui| {
+                        if let Some(previous_value) = param_knob(
+                            ui,
+                            setter,
+                            &params.lookahead,
+                            LOOKAHEAD_ID,
+                            "knob_lookahead",
+                            "LOOKAHEAD",
+                            KNOB_DIAMETER,
+                            drag_scale,
+                            &params.param_locks,
+                        ) {
+                            state.reset_toast = Some(ResetToast::new(
+                                "LOOKAHEAD",
+                                |params| &params.lookahead,
+                                previous_value,
+                            ));
+                        }
+                    });
+                    if let Some(previous_value) = param_knob(
+                        ui,
+                        setter,
+                        &params.output_gain,
+                        OUTPUT_GAIN_ID,
+                        "knob_output_gain",
+                        "OUTPUT",
+                        KNOB_DIAMETER,
+                        drag_scale,
+                        &params.param_locks,
+                    ) {
+                        state.reset_toast = Some(ResetToast::new(
+                            "OUTPUT",
+                            |params| &params.output_gain,
+                            previous_value,
+                        ));
+                    }
+                });
+            })
+        });
+
+        // declared after the controls so it sits between them and the scope
+        let show_bands = show_controls && params.mode.value() == CorrectionMode::Multiband;
+        TopBottomPanel::bottom("bands").show_animated(ctx, show_bands, |ui| {
+            ui.horizontal(|ui| {
+                centered(ctx, ui, |ui| {
+                    let bands: [(fn(&CenteredParams) -> &FloatParam, _, _, _); NUM_BANDS] = [
+                        (
+                            |params| &params.band_amounts.low,
+                            LOW_BAND_AMOUNT_ID,
+                            "knob_band_low",
+                            "LOW",
+                        ),
+                        (
+                            |params| &params.band_amounts.mid,
+                            MID_BAND_AMOUNT_ID,
+                            "knob_band_mid",
+                            "MID",
+                        ),
+                        (
+                            |params| &params.band_amounts.high,
+                            HIGH_BAND_AMOUNT_ID,
+                            "knob_band_high",
+                            "HIGH",
+                        ),
+                    ];
+                    for (band_param, param_id, id, label) in bands {
+                        if let Some(previous_value) = param_knob(
+                            ui,
+                            setter,
+                            band_param(&params),
+                            param_id,
+                            id,
+                            label,
+                            BAND_KNOB_DIAMETER,
+                            drag_scale,
+                            &params.param_locks,
+                        ) {
+                            state.reset_toast =
+                                Some(ResetToast::new(label, band_param, previous_value));
                         }
+                    }
+                });
+            })
+        });
 
-                        generate_arc(
-                            &painter,
-                            center,
-                            scope_rect.height() / 4.0,
-                            90.0_f32.to_radians() - correcting_angle,
-                            90.0_f32.to_radians(),
-                            Stroke::new(2.5, cozy_ui::colors::HIGHLIGHT_COL32),
-                        );
+        let show_history = params.show_history.load(Ordering::Relaxed) && !fullscreen_scope;
+        TopBottomPanel::bottom("history").show_animated(ctx, show_history, |ui| {
+            state
+                .history_view
+                .show(ui, &params.history, &params.history_span);
+        });
+
+        CentralPanel::default().show(ctx, |ui| {
+            Frame::canvas(ui.style())
+                .stroke(Stroke::new(2.0, Color32::DARK_GRAY))
+                .show(ui, |ui| {
+                    let (rect, response) =
+                        ui.allocate_at_least(ui.available_size_before_wrap(), Sense::click());
+                    if response.double_clicked() {
+                        state.fullscreen_scope = !state.fullscreen_scope;
+                    }
 
+                    let pixel_grid = PixelGrid::new(ctx);
+                    let scope_rect = pixel_grid.snap_rect(
+                        Rect::from_center_size(rect.center(), Vec2::splat(rect.height()))
+                            .shrink(20.0),
+                    );
+
+                    let painter = ui.painter_at(rect);
+                    let center = scope_rect.center();
+
+                    painter.add(state.grid_cache.shape(ctx, scope_rect, pixel_grid));
+
+                    let now = Instant::now();
+                    state.pre_scope.update(pre_stereo_data.as_slice(), now);
+                    state.post_scope.update(post_stereo_data.as_slice(), now);
+                    state
+                        .scope_recorder
+                        .capture(now, &state.pre_scope, &state.post_scope);
+
+                    let connected = params.scope_connected.load(Ordering::Relaxed);
+                    let anti_phase_color = params
+                        .scope_anti_phase
+                        .load(Ordering::Relaxed)
+                        .then_some(*ANTI_PHASE_COLOR);
+                    draw_scope(
+                        &painter,
+                        scope_rect,
+                        state.pre_scope.interpolated(now),
+                        Color32::WHITE,
+                        anti_phase_color,
+                        connected,
+                    );
+                    draw_scope(
+                        &painter,
+                        scope_rect,
+                        state.post_scope.interpolated(now),
+                        cozy_ui::colors::HIGHLIGHT_COL32,
+                        anti_phase_color,
+                        connected,
+                    );
+
+                    generate_arc(
+                        &painter,
+                        center,
+                        scope_rect.height() / 4.0,
+                        90.0_f32.to_radians() - correcting_angle,
+                        90.0_f32.to_radians(),
+                        Stroke::new(2.5, cozy_ui::colors::HIGHLIGHT_COL32),
+                    );
+
+                    if params.show_meters.load(Ordering::Relaxed) && !fullscreen_scope {
                         let peak_rect_pre = Rect::from_center_size(
                             pos2(rect.left() + (rect.width() * 0.1), rect.center().y),
                             vec2(40.0, rect.height() * 0.8),
@@ -290,6 +693,11 @@ pub fn editor(
                             FontId::new(10.0, FontFamily::Name("0x".into())),
                             Color32::GRAY,
                         );
+                        draw_mono_compat_score(
+                            ui,
+                            peak_rect_pre.center_bottom() + vec2(0.0, 22.0),
+                            &pre_mono_compat,
+                        );
                         let peak_rect_post = Rect::from_center_size(
                             pos2(rect.left() + (rect.width() * 0.9), rect.center().y),
                             vec2(40.0, rect.height() * 0.8),
@@ -312,41 +720,819 @@ pub fn editor(
                             FontId::new(10.0, FontFamily::Name("0x".into())),
                             Color32::GRAY,
                         );
+                        draw_mono_compat_score(
+                            ui,
+                            peak_rect_post.center_bottom() + vec2(0.0, 22.0),
+                            &post_mono_compat,
+                        );
+
+                        if params.true_peak_metering.load(Ordering::Relaxed) {
+                            let clip_rect = Rect::from_center_size(
+                                peak_rect_post.center_top() - vec2(0.0, 10.0),
+                                vec2(peak_rect_post.width(), 12.0),
+                            );
+                            let clipped = true_peak_clip.load(Ordering::Relaxed);
+                            if ui
+                                .interact(clip_rect, Id::new("true_peak_clip"), Sense::click())
+                                .on_hover_text("Click to reset")
+                                .clicked()
+                            {
+                                true_peak_clip.store(false, Ordering::Relaxed);
+                            }
+
+                            ui.painter().rect_filled(
+                                clip_rect,
+                                Rounding::same(2.0),
+                                if clipped {
+                                    Color32::RED
+                                } else {
+                                    Color32::DARK_GRAY
+                                },
+                            );
+                            ui.painter().text(
+                                clip_rect.center(),
+                                Align2::CENTER_CENTER,
+                                "TP",
+                                FontId::new(10.0, FontFamily::Name("0x".into())),
+                                Color32::WHITE,
+                            );
+                        }
+
+                        if params.safety_limiter.value() {
+                            let gr_rect = Rect::from_min_max(
+                                pos2(peak_rect_post.left() - 18.0, peak_rect_post.top()),
+                                pos2(peak_rect_post.left() - 10.0, peak_rect_post.bottom()),
+                            );
+                            draw_gain_reduction_meter(ui, gr_rect, &limiter_meter);
+                        }
+                    }
+                });
+        });
+
+        Window::new("DEBUG")
+            .vscroll(true)
+            .open(&mut state.show_debug)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "pan angle: {} ({} rad pre-offset)",
+                    correcting_angle.to_degrees(),
+                    corr_angle_debug
+                ));
+            });
+
+        Window::new("SETTINGS")
+            .open(&mut state.show_settings)
+            .show(ctx, |ui| {
+                let mut drag_distance = params.knob_drag_distance.load(Ordering::Relaxed);
+                if ui
+                    .add(
+                        Slider::new(
+                            &mut drag_distance,
+                            MIN_KNOB_DRAG_DISTANCE..=MAX_KNOB_DRAG_DISTANCE,
+                        )
+                        .logarithmic(true)
+                        .suffix(" px")
+                        .text("Knob drag distance"),
+                    )
+                    .on_hover_text("How far a knob needs to be dragged to cover its full range")
+                    .changed()
+                {
+                    params
+                        .knob_drag_distance
+                        .store(drag_distance, Ordering::Relaxed);
+                }
+
+                let mut scope_connected = params.scope_connected.load(Ordering::Relaxed);
+                if ui
+                    .checkbox(&mut scope_connected, "Connect scope samples")
+                    .on_hover_text(
+                        "Draws the scope as a trace like an analog vector scope instead of \
+                         separate dots",
+                    )
+                    .changed()
+                {
+                    params
+                        .scope_connected
+                        .store(scope_connected, Ordering::Relaxed);
+                }
+
+                let mut scope_anti_phase = params.scope_anti_phase.load(Ordering::Relaxed);
+                if ui
+                    .checkbox(&mut scope_anti_phase, "Highlight out of phase samples")
+                    .on_hover_text(
+                        "Colors scope samples where the channels have opposite signs, so phase \
+                         problems stand out",
+                    )
+                    .changed()
+                {
+                    params
+                        .scope_anti_phase
+                        .store(scope_anti_phase, Ordering::Relaxed);
+                }
+            });
+
+        Window::new("ADVANCED")
+            .open(&mut state.show_advanced)
+            .show(ctx, |ui| {
+                param_combo(ui, setter, &params.mode, "MODE");
+                if params.mode.value() == CorrectionMode::Multiband {
+                    ui.horizontal(|ui| {
+                        param_slider(ui, setter, &params.multiband_low_crossover, "LOW XOVER");
+                        param_combo(ui, setter, &params.multiband_low_slope, "LOW SLOPE");
+                    });
+                    ui.horizontal(|ui| {
+                        param_slider(
+                            ui,
+                            setter,
+                            &params.multiband_high_crossover,
+                            "HIGH XOVER",
+                        );
+                        param_combo(ui, setter, &params.multiband_high_slope, "HIGH SLOPE");
                     });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("BALLISTICS");
+                    for ballistics in Ballistics::ALL {
+                        if ui
+                            .button(ballistics.name())
+                            .on_hover_text(ballistics.description())
+                            .clicked()
+                        {
+                            apply_ballistics(setter, &params, ballistics);
+                        }
+                    }
+                });
+                param_toggle(ui, setter, &params.snap, "SNAP");
+                param_combo(ui, setter, &params.smoothing_shape, "SMOOTHING");
+                param_toggle(ui, setter, &params.auto_release, "AUTO RELEASE").on_hover_text(
+                    "Lengthens the release while the image jumps around and shortens it while it drifts",
+                );
+                param_toggle(ui, setter, &params.adaptive_reaction, "ADAPTIVE REACTION")
+                    .on_hover_text(
+                        "Reacts slower while the detected angle is noisy and faster to a clear, \
+                         steady offset",
+                    );
+                param_toggle(ui, setter, &params.release_linked, "LINK RELEASE").on_hover_text(
+                    "Uses the attack time for the release too, so the correction moves back towards \
+                     the center as quickly as it moved away",
+                );
+                ui.horizontal(|ui| {
+                    param_toggle(ui, setter, &params.reaction_sync, "TEMPO SYNC")
+                        .on_hover_text(
+                            "Sets the reaction time in note divisions of the host's tempo \
+                             instead of milliseconds",
+                        );
+                    param_combo(ui, setter, &params.reaction_division, "DIVISION");
+                });
+                ui.horizontal(|ui| {
+                    param_toggle(ui, setter, &params.bass_mono, "BASS MONO")
+                        .on_hover_text("Folds everything below the frequency down to mono");
+                    param_slider(ui, setter, &params.bass_mono_frequency, "BELOW");
+                    param_combo(ui, setter, &params.bass_mono_slope, "BASS SLOPE");
+                });
+                ui.horizontal(|ui| {
+                    param_combo(ui, setter, &params.silence_behavior, "SILENCE");
+                    if params.silence_behavior.value() == SilenceBehavior::Return {
+                        param_slider(ui, setter, &params.return_time, "RETURN TIME");
+                    }
+                });
+                ui.horizontal(|ui| {
+                    param_toggle(ui, setter, &params.long_lookahead, "LONG LOOKAHEAD")
+                        .on_hover_text(
+                            "Lets the lookahead reach 100 ms for offline passes. Adds that much latency",
+                        );
+                    if params.long_lookahead.value() {
+                        param_slider(ui, setter, &params.long_lookahead_time, "TIME");
+                    }
+                });
+                param_slider(ui, setter, &params.static_offset, "STATIC OFFSET");
+                param_slider(ui, setter, &params.target_position, "TARGET");
+                param_toggle(ui, setter, &params.inverse, "INVERSE").on_hover_text(
+                    "Pushes the image further the way it already leans instead of centering it",
+                );
+                ui.horizontal(|ui| {
+                    param_slider(ui, setter, &params.deadband, "DEADBAND");
+                    param_slider(ui, setter, &params.knee, "KNEE");
+                    param_slider(ui, setter, &params.max_correction, "MAX CORRECTION");
+                });
+                param_slider(ui, setter, &params.hold_off, "HOLD-OFF");
+                ui.horizontal(|ui| {
+                    param_toggle(ui, setter, &params.slew_limit, "SLEW LIMIT").on_hover_text(
+                        "Caps how fast the correction can turn, whatever the detector does",
+                    );
+                    param_slider(ui, setter, &params.max_slew_rate, "MAX SPEED");
+                });
+                ui.horizontal(|ui| {
+                    param_combo(ui, setter, &params.estimator, "ESTIMATOR");
+                    if params.estimator.value() == EstimatorKind::Percentile {
+                        param_slider(ui, setter, &params.estimator_percentile, "PERCENTILE");
+                    }
+                });
+                ui.horizontal(|ui| {
+                    param_toggle(ui, setter, &params.detection_highpass, "DETECTION HPF")
+                        .on_hover_text(
+                            "Keeps low end rumble out of the detector without filtering the audio",
+                        );
+                    param_slider(ui, setter, &params.detection_highpass_frequency, "CUTOFF");
+                });
+                param_combo(ui, setter, &params.detection_weighting, "WEIGHTING");
+                ui.horizontal(|ui| {
+                    param_slider(ui, setter, &params.detection_weighting_frequency, "CENTER");
+                    param_slider(ui, setter, &params.detection_tilt, "TILT");
+                });
+                param_slider(ui, setter, &params.detection_decimation, "DECIMATION");
+                param_toggle(ui, setter, &params.exclude_transients, "EXCLUDE TRANSIENTS")
+                    .on_hover_text(
+                        "Leaves drum hits out of the detection, so hard panned percussion doesn't \
+                         jerk the correction around",
+                    );
+                ui.horizontal(|ui| {
+                    param_toggle(ui, setter, &params.fixed_analysis_window, "FIXED WINDOW")
+                        .on_hover_text(
+                            "Detects over a set length of audio, so the host's buffer size doesn't \
+                             change how the correction behaves",
+                        );
+                    param_slider(ui, setter, &params.analysis_window, "WINDOW");
+                });
+                ui.horizontal(|ui| {
+                    param_toggle(ui, setter, &params.correlation_gate, "CORRELATION GATE")
+                        .on_hover_text(
+                            "Holds the correction while the channels are less correlated than this, \
+                             so it doesn't chase reverb tails",
+                        );
+                    param_slider(ui, setter, &params.min_correlation, "MIN");
+                });
+                ui.horizontal(|ui| {
+                    param_toggle(ui, setter, &params.level_gate, "LEVEL GATE").on_hover_text(
+                        "Holds the correction while the input is quieter than this, instead of \
+                         letting quiet passages pull the image around",
+                    );
+                    param_slider(ui, setter, &params.level_gate_threshold, "GATE AT");
+                });
+                param_toggle(ui, setter, &params.freeze, "FREEZE")
+                    .on_hover_text("Locks the correction where it is, for as long as this is on");
+                param_toggle(ui, setter, &params.mono_detection, "MONO DETECTION").on_hover_text(
+                    "Leaves mono and identical dual mono input alone, instead of turning it over tiny \
+                     differences between the channels",
+                );
+                ui.horizontal(|ui| {
+                    param_toggle(ui, setter, &params.learn, "LEARN").on_hover_text(
+                        "Averages the detected angle while this is on, or for the set time, and \
+                         then switches to a manual correction by that much",
+                    );
+                    param_slider(ui, setter, &params.learn_duration, "FOR");
+                });
+
+                ui.separator();
+
+                param_toggle(ui, setter, &params.detect_from_sidechain, "DETECT FROM SIDECHAIN")
+                    .on_hover_text(
+                        "Follows the sidechain's balance instead of the input's, for when later \
+                         processing would skew the estimate",
+                    );
+                param_toggle(ui, setter, &params.sidechain_freeze, "SIDECHAIN FREEZE")
+                    .on_hover_text(
+                        "Holds the correction while the sidechain is louder than the threshold",
+                    );
+                param_slider(ui, setter, &params.sidechain_threshold, "THRESHOLD");
+
+                ui.separator();
+
+                param_toggle(ui, setter, &params.safety_limiter, "SAFETY LIMITER")
+                    .on_hover_text(
+                        "Brickwall limits the output so the correction can't clip it",
+                    );
+                param_slider(ui, setter, &params.limiter_ceiling, "CEILING");
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    param_combo(ui, setter, &params.correction_source, "SOURCE");
+                    if params.correction_source.value() == CorrectionSource::Manual {
+                        param_slider(ui, setter, &params.manual_angle, "ANGLE");
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.add(
+                        TextEdit::singleline(&mut state.automation_path)
+                            .hint_text("time,angle CSV file"),
+                    );
+
+                    if ui.button("IMPORT").clicked() {
+                        let path = Path::new(state.automation_path.trim());
+                        state.automation_message =
+                            Some(match params.angle_automation.import(path) {
+                                Ok(num_points) => format!("Imported {num_points} points"),
+                                Err(err) => format!("Couldn't import: {err}"),
+                            });
+                    }
+
+                    if ui.button("CLEAR").clicked() {
+                        params.angle_automation.clear();
+                        state.automation_message = None;
+                    }
+                });
+
+                match params.angle_automation.summary() {
+                    Some((num_points, duration)) => {
+                        ui.label(format!("{num_points} points over {duration:.1} s"));
+                    }
+                    None => {
+                        ui.label("Nothing imported");
+                    }
+                }
+                if let Some(message) = &state.automation_message {
+                    ui.label(message);
+                }
             });
 
-            Window::new("DEBUG")
-                .vscroll(true)
-                .open(&mut state.show_debug)
-                .show(ctx, |ui| {
+        Window::new("SPECTRUM")
+            .open(&mut state.show_spectrum)
+            .show(ctx, |ui| {
+                state.spectrum_view.show(
+                    ui,
+                    &analyzer_input,
+                    &params.analyzer_window,
+                    &params.analyzer_fft_size,
+                );
+            });
+
+        Window::new("AUDIO")
+            .open(&mut state.show_audio_settings)
+            .show(ctx, |ui| {
+                state.audio_settings.show(ui);
+            });
+
+        #[cfg(feature = "scripting")]
+        Window::new("SCRIPT")
+            .open(&mut state.show_script)
+            .show(ctx, |ui| {
+                ui.label(
+                    "A rhai expression for the correction target in degrees, with positive angles \
+                     leaning right. It can use mean_angle, correlation, rms_left, and rms_right, and \
+                     runs when the correction source is set to Script",
+                );
+                ui.add(
+                    TextEdit::multiline(&mut state.script_source)
+                        .code_editor()
+                        .desired_rows(4),
+                );
+
+                if ui.button("APPLY").clicked() {
+                    state.script_message = params
+                        .detection_script
+                        .set_source(&state.script_source)
+                        .err();
+                }
+
+                if let Some(error) = state
+                    .script_message
+                    .clone()
+                    .or_else(|| params.detection_script.error())
+                {
+                    ui.label(RichText::new(error).color(Color32::RED));
+                }
+            });
+
+        Window::new("FILE")
+            .open(&mut state.show_file)
+            .show(ctx, |ui| {
+                state.file_panel.show(
+                    ui,
+                    setter,
+                    &params,
+                    &file_analyzer,
+                    &file_player,
+                    analyzer_input.sample_rate(),
+                );
+            });
+
+        Window::new("INTEGRATED")
+            .open(&mut state.show_integrated)
+            .show(ctx, |ui| {
+                match integrated_angle.get() {
+                    Some((angle, seconds)) => {
+                        let seconds = seconds as u32;
+                        ui.label(format!(
+                            "Integrated angle: {:.1}° {} over {}:{:02}:{:02}",
+                            angle.abs(),
+                            if angle >= 0.0 { "right" } else { "left" },
+                            seconds / 3600,
+                            seconds / 60 % 60,
+                            seconds % 60
+                        ));
+                    }
+                    None => {
+                        ui.label("Waiting for signal...");
+                    }
+                }
+
+                if ui
+                    .button("RESET")
+                    .on_hover_text("Starts integrating the output's balance from scratch")
+                    .clicked()
+                {
+                    integrated_angle.request_reset();
+                }
+            });
+
+        Window::new("NULL TEST")
+            .open(&mut state.show_null_test)
+            .show(ctx, |ui| {
+                ui.label(match null_test.depth_db() {
+                    Some(depth) => format!(
+                        "Residual: {:.1} dBFS, {:.1} dB below the input",
+                        null_test.residual_db(),
+                        -depth
+                    ),
+                    None => "Waiting for input...".to_string(),
+                });
+
+                let mut listening = null_test.listening.load(Ordering::Relaxed);
+                if ui
+                    .toggle_value(&mut listening, "LISTEN")
+                    .on_hover_text(
+                        "Replaces the output with the input minus the output, which is \
+                         everything Centered changes",
+                    )
+                    .changed()
+                {
+                    null_test.listening.store(listening, Ordering::Relaxed);
+                }
+            });
+        // only measure while the window is open, and never leave the output replaced once it's closed
+        null_test
+            .active
+            .store(state.show_null_test, Ordering::Relaxed);
+        if !state.show_null_test {
+            null_test.listening.store(false, Ordering::Relaxed);
+        }
+
+        Window::new("GAIN STAGING")
+            .open(&mut state.show_gain_staging)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Input peak: {:.1} dBFS, RMS: {:.1} dBFS",
+                    input_stats.peak_db(),
+                    input_stats.rms_db()
+                ));
+
+                match input_stats.suggested_gain_db() {
+                    None => {
+                        ui.label("Waiting for input...");
+                    }
+                    Some(suggested_gain)
+                        if (suggested_gain - gain_to_db(params.input_gain.value())).abs()
+                            >= MIN_SUGGESTION_DB =>
+                    {
+                        ui.label(format!(
+                            "Input is peaking at {:.1} dBFS; consider {suggested_gain:+.1} dB \
+                             of input gain to bring peaks to {:.0} dBFS",
+                            input_stats.peak_db(),
+                            target_peak_db()
+                        ));
+
+                        if ui.button("APPLY").clicked() {
+                            setter.begin_set_parameter(&params.input_gain);
+                            setter.set_parameter(&params.input_gain, db_to_gain(suggested_gain));
+                            setter.end_set_parameter(&params.input_gain);
+                        }
+                    }
+                    Some(_) => {
+                        ui.label("Input levels look good");
+                    }
+                }
+
+                ui.separator();
+
+                match input_stats.suggested_trims_db() {
+                    None => {
+                        ui.label("Waiting for signal on both channels...");
+                    }
+                    Some((left_trim, right_trim)) => {
+                        ui.label(format!(
+                            "Evening out the channels needs {left_trim:+.1} dB on the left and \
+                             {right_trim:+.1} dB on the right"
+                        ));
+
+                        if ui
+                            .button("AUTO TRIM")
+                            .on_hover_text(
+                                "Sets the channel trims from the last 30 seconds or so of input",
+                            )
+                            .clicked()
+                        {
+                            for (param, trim) in [
+                                (&params.trim_left, left_trim),
+                                (&params.trim_right, right_trim),
+                            ] {
+                                setter.begin_set_parameter(param);
+                                setter.set_parameter(param, db_to_gain(trim));
+                                setter.end_set_parameter(param);
+                            }
+                        }
+                    }
+                }
+                param_slider(ui, setter, &params.trim_left, "LEFT TRIM");
+                param_slider(ui, setter, &params.trim_right, "RIGHT TRIM");
+
+                ui.separator();
+
+                param_toggle(ui, setter, &params.align_channels, "ALIGN CHANNELS").on_hover_text(
+                    "Delays whichever channel comes first so both line up in time, for spaced \
+                     pairs that sound off center because of timing rather than level",
+                );
+                if params.align_channels.value() {
+                    let delay_ms = channel_delay.load(Ordering::Relaxed);
+                    let lagging = if delay_ms < 0.0 { "left" } else { "right" };
                     ui.label(format!(
-                        "pan angle: {} ({} rad pre-offset)",
-                        correcting_angle.to_degrees(),
-                        corr_angle_debug
+                        "The {lagging} channel lags by {:.2} ms",
+                        delay_ms.abs()
                     ));
-                });
+                }
 
-            Window::new("ABOUT")
-                .vscroll(true)
-                .open(&mut state.show_about)
-                .show(ctx, |ui| {
-                    ui.image(include_image!("../assets/Cozy_logo.png"));
-                    ui.vertical_centered(|ui| {
-                        ui.heading(RichText::new("CENTERED").strong());
-                        ui.label(
-                            RichText::new(format!("Version {}", VERSION))
-                                .italics(),
-                        );
-                        ui.hyperlink_to("Homepage", env!("CARGO_PKG_HOMEPAGE"));
-                        ui.hyperlink_to("Report a Bug", format!("{}/issues/new?template=.gitea%2fISSUE_TEMPLATE%2fbug-report.yaml&version={}", env!("CARGO_PKG_REPOSITORY"), byte_serialize(VERSION.as_bytes()).collect::<String>()));
-                        ui.separator();
-                        ui.heading(RichText::new("Credits"));
-                        ui.label("Plugin by joe sorensen");
-                        ui.label("cozy dsp branding and design by gordo");
-                    });
+                ui.separator();
+
+                if ui.button("RESET").clicked() {
+                    input_stats.request_reset();
+                }
+            });
+
+        Window::new("ABOUT")
+            .vscroll(true)
+            .open(&mut state.show_about)
+            .show(ctx, |ui| {
+                ui.image(include_image!("../assets/Cozy_logo.png"));
+                ui.vertical_centered(|ui| {
+                    ui.heading(RichText::new("CENTERED").strong());
+                    ui.label(
+                        RichText::new(format!("Version {}", VERSION))
+                            .italics(),
+                    );
+                    ui.hyperlink_to("Homepage", env!("CARGO_PKG_HOMEPAGE"));
+                    ui.hyperlink_to("Report a Bug", format!("{}/issues/new?template=.gitea%2fISSUE_TEMPLATE%2fbug-report.yaml&version={}", env!("CARGO_PKG_REPOSITORY"), byte_serialize(VERSION.as_bytes()).collect::<String>()));
+                    ui.separator();
+                    ui.heading(RichText::new("Credits"));
+                    ui.label("Plugin by joe sorensen");
+                    ui.label("cozy dsp branding and design by gordo");
                 });
-        },
+            });
+    }
+}
+
+/// Adds a knob for `param`, with a context menu for resetting and locking it. `param_id` is the parameter's ID, which
+/// the lock goes by. If the knob got reset to its default, this returns the normalized value it had before that
+#[allow(clippy::too_many_arguments)]
+fn param_knob<P: Param>(
+    ui: &mut Ui,
+    setter: &ParamSetter,
+    param: &P,
+    param_id: &'static str,
+    id: &'static str,
+    label: &'static str,
+    diameter: f32,
+    drag_scale: f32,
+    param_locks: &ParamLocks,
+) -> Option<f32> {
+    let locked_value = param_locks.locked_normalized_value(param_id);
+    let locked = locked_value.is_some();
+    // a locked knob shows the value that's actually being processed, whatever the parameter got set to since
+    let shown_value = || locked_value.unwrap_or_else(|| param.unmodulated_normalized_value());
+    let default_value = param.default_normalized_value();
+    let reset_from = Cell::new(None);
+    let description = if let Some(locked_value) = locked_value {
+        format!(
+            "{} (LOCKED)",
+            param.normalized_value_to_string(locked_value, true)
+        )
+    } else {
+        param.to_string()
+    };
+
+    ui.add(
+        knob(
+            id,
+            diameter,
+            |v| match v {
+                Operation::Get => shown_value(),
+                Operation::Set(_) if locked => shown_value(),
+                Operation::Set(v) => {
+                    let current = param.unmodulated_normalized_value();
+                    let v = scale_drag(current, v, default_value, drag_scale);
+                    if v == default_value && current != default_value {
+                        reset_from.set(Some(current));
+                    }
+                    setter.set_parameter_normalized(param, v);
+                    v
+                }
+            },
+            || {
+                if !locked {
+                    setter.begin_set_parameter(param);
+                }
+            },
+            || {
+                if !locked {
+                    setter.end_set_parameter(param);
+                }
+            },
+        )
+        .label(label)
+        .description(description)
+        .default_value(default_value)
+        .modulated_value(param.modulated_normalized_value()),
     )
+    .context_menu(|ui| {
+        let current = param.unmodulated_normalized_value();
+        if ui
+            .add_enabled(
+                !locked && current != default_value,
+                Button::new("Reset to default"),
+            )
+            .clicked()
+        {
+            setter.begin_set_parameter(param);
+            setter.set_parameter_normalized(param, default_value);
+            setter.end_set_parameter(param);
+            reset_from.set(Some(current));
+            ui.close_menu();
+        }
+
+        if locked {
+            if ui.button("Unlock").clicked() {
+                param_locks.unlock(param_id);
+                ui.close_menu();
+            }
+        } else if ui.button("Lock").clicked() {
+            param_locks.lock(param_id, param);
+            ui.close_menu();
+        }
+    });
+
+    reset_from.get()
+}
+
+/// Switches to a profile, setting whichever parameters it cares about
+fn apply_profile(
+    setter: &ParamSetter,
+    params: &CenteredParams,
+    input_stats: &InputStats,
+    profile: Profile,
+) {
+    match profile {
+        Profile::Custom => {}
+        Profile::Live => {
+            set_param(setter, &params.lookahead, 0.0);
+            set_param(setter, &params.reaction_time, 10.0);
+            set_param(setter, &params.snap, false);
+            set_param(setter, &params.detection_decimation, 1);
+            set_param(setter, &params.fixed_analysis_window, true);
+            set_param(setter, &params.analysis_window, 20.0);
+            set_param(setter, &params.slew_limit, true);
+            set_param(setter, &params.max_slew_rate, 20.0);
+        }
+        Profile::Mastering => {
+            set_param(setter, &params.long_lookahead, true);
+            set_param(setter, &params.long_lookahead_time, LONG_MAX_LOOKAHEAD_MS);
+            set_param(setter, &params.fixed_analysis_window, true);
+            set_param(setter, &params.analysis_window, MAX_ANALYSIS_WINDOW_MS);
+            set_param(setter, &params.reaction_time, 25.0);
+            set_param(setter, &params.snap, false);
+            set_param(setter, &params.estimator, EstimatorKind::Median);
+            set_param(setter, &params.detection_decimation, 1);
+            if let Some(suggested_gain) = input_stats.suggested_gain_db() {
+                set_param(setter, &params.input_gain, db_to_gain(suggested_gain));
+            }
+            params.true_peak_metering.store(true, Ordering::Relaxed);
+        }
+    }
+
+    params.profile.store(profile as u8, Ordering::Relaxed);
+}
+
+/// Takes out a measured `lean` (in degrees, positive is right) with a fixed correction instead of the detector, so the
+/// result stays the same over time. Going through the manual source instead of the static offset keeps the live
+/// correction from taking the same lean out a second time. The manual source turns the image, so it turns it back by
+/// the lean
+fn apply_fixed_correction(setter: &ParamSetter, params: &CenteredParams, lean: f32) {
+    set_param(setter, &params.correction_source, CorrectionSource::Manual);
+    set_param(setter, &params.manual_angle, (-lean).clamp(-45.0, 45.0));
+}
+
+/// Sets every timing parameter the ballistics preset covers
+fn apply_ballistics(setter: &ParamSetter, params: &CenteredParams, ballistics: Ballistics) {
+    set_param(setter, &params.reaction_sync, false);
+    set_param(setter, &params.reaction_time, ballistics.reaction_ms());
+    match ballistics.release_ms() {
+        Some(release_ms) => {
+            set_param(setter, &params.release_linked, false);
+            set_param(setter, &params.release_time, release_ms);
+        }
+        None => set_param(setter, &params.release_linked, true),
+    }
+    set_param(setter, &params.fixed_analysis_window, true);
+    set_param(
+        setter,
+        &params.analysis_window,
+        ballistics.analysis_window_ms(),
+    );
+    set_param(setter, &params.slew_limit, true);
+    set_param(setter, &params.max_slew_rate, ballistics.max_slew_rate());
+}
+
+/// Rescales the change a knob made this frame according to the drag distance setting. Resetting to the default value
+/// jumps straight there, so that doesn't get scaled
+fn scale_drag(current: f32, new: f32, default: f32, drag_scale: f32) -> f32 {
+    if new == default {
+        return new;
+    }
+
+    (new - current).mul_add(drag_scale, current).clamp(0.0, 1.0)
+}
+
+/// The mono compatibility score as text centered on `pos`, red when it's failing
+fn draw_mono_compat_score(ui: &Ui, pos: Pos2, meter: &MonoCompatMeter) {
+    let (text, color) = match meter.score() {
+        Some(score) if score < PASSING_SCORE => (format!("MONO {score:.0}%"), Color32::RED),
+        Some(score) => (format!("MONO {score:.0}%"), Color32::GRAY),
+        None => ("MONO --".to_string(), Color32::DARK_GRAY),
+    };
+
+    ui.painter().text(
+        pos,
+        Align2::CENTER_CENTER,
+        text,
+        FontId::new(10.0, FontFamily::Name("0x".into())),
+        color,
+    );
+}
+
+/// A bar that grows down from the top as the safety limiter reduces gain, with a line at the held maximum. The held
+/// value is shown above it, and clicking that resets it
+fn draw_gain_reduction_meter(ui: &Ui, bounds: Rect, meter: &LimiterMeter) {
+    const MAX_REDUCTION_DB: f32 = 12.0;
+
+    let reduction_db = meter.reduction_db.load(Ordering::Relaxed);
+    let held_reduction_db = meter.held_reduction_db.load(Ordering::Relaxed);
+
+    let pixel_grid = PixelGrid::new(ui.ctx());
+    let bounds = pixel_grid.snap_rect(bounds);
+    let font = FontId::new(10.0, FontFamily::Name("0x".into()));
+
+    ui.painter()
+        .rect_filled(bounds, Rounding::ZERO, Color32::from_gray(30));
+    ui.painter().rect_filled(
+        Rect::from_two_pos(
+            bounds.left_top(),
+            pos2(
+                bounds.right(),
+                pixel_grid.snap_edge(remap_clamp(
+                    reduction_db,
+                    0.0..=MAX_REDUCTION_DB,
+                    bounds.y_range(),
+                )),
+            ),
+        ),
+        Rounding::ZERO,
+        Color32::from_rgb(230, 140, 40),
+    );
+    ui.painter().hline(
+        bounds.x_range(),
+        pixel_grid
+            .snap_pos(
+                pos2(
+                    bounds.left(),
+                    remap_clamp(held_reduction_db, 0.0..=MAX_REDUCTION_DB, bounds.y_range()),
+                ),
+                1.0,
+            )
+            .y,
+        pixel_grid.stroke(1.0, Color32::GRAY),
+    );
+
+    ui.painter().text(
+        bounds.center_bottom() + vec2(0.0, 10.0),
+        Align2::CENTER_CENTER,
+        "GR",
+        font.clone(),
+        Color32::GRAY,
+    );
+
+    let held_rect = Rect::from_center_size(bounds.center_top() - vec2(0.0, 10.0), vec2(30.0, 12.0));
+    if ui
+        .interact(held_rect, Id::new("limiter_held_reduction"), Sense::click())
+        .on_hover_text("The most gain reduction so far. Click to reset")
+        .clicked()
+    {
+        meter.held_reduction_db.store(0.0, Ordering::Relaxed);
+    }
+    ui.painter().text(
+        held_rect.center(),
+        Align2::CENTER_CENTER,
+        format!("{held_reduction_db:.1}"),
+        font,
+        Color32::GRAY,
+    );
 }
 
 fn draw_peak_meters(
@@ -418,17 +1604,24 @@ fn draw_peak_meters(
     let held_peak_value_db_l = ui.memory_mut(|r| *r.data.get_temp_mut_or(held_l_id, f32::MIN));
     let held_peak_value_db_r = ui.memory_mut(|r| *r.data.get_temp_mut_or(held_r_id, f32::MIN));
 
+    let pixel_grid = PixelGrid::new(ui.ctx());
+    let bounds = pixel_grid.snap_rect(bounds);
     let peak_width = (bounds.width() - 10.0) / 2.0;
 
-    let (l_bounds, temp) = bounds.split_left_right_at_x(bounds.left() + peak_width);
-    let (_, r_bounds) = temp.split_left_right_at_x(temp.left() + 10.0);
+    let (l_bounds, temp) =
+        bounds.split_left_right_at_x(pixel_grid.snap_edge(bounds.left() + peak_width));
+    let (_, r_bounds) = temp.split_left_right_at_x(pixel_grid.snap_edge(temp.left() + 10.0));
 
     ui.painter().rect_filled(
         Rect::from_two_pos(
             l_bounds.left_bottom(),
             pos2(
                 l_bounds.right(),
-                remap_clamp(level_l_dbfs, MIN_DB..=MAX_DB, l_bounds.bottom_up_range()),
+                pixel_grid.snap_edge(remap_clamp(
+                    level_l_dbfs,
+                    MIN_DB..=MAX_DB,
+                    l_bounds.bottom_up_range(),
+                )),
             ),
         ),
         Rounding::ZERO,
@@ -436,19 +1629,31 @@ fn draw_peak_meters(
     );
     ui.painter().hline(
         l_bounds.x_range(),
-        remap_clamp(
-            held_peak_value_db_l,
-            MIN_DB..=MAX_DB,
-            l_bounds.bottom_up_range(),
-        ),
-        Stroke::new(1.0, Color32::GRAY),
+        pixel_grid
+            .snap_pos(
+                pos2(
+                    l_bounds.left(),
+                    remap_clamp(
+                        held_peak_value_db_l,
+                        MIN_DB..=MAX_DB,
+                        l_bounds.bottom_up_range(),
+                    ),
+                ),
+                1.0,
+            )
+            .y,
+        pixel_grid.stroke(1.0, Color32::GRAY),
     );
     ui.painter().rect_filled(
         Rect::from_two_pos(
             r_bounds.left_bottom(),
             pos2(
                 r_bounds.right(),
-                remap_clamp(level_r_dbfs, MIN_DB..=MAX_DB, r_bounds.bottom_up_range()),
+                pixel_grid.snap_edge(remap_clamp(
+                    level_r_dbfs,
+                    MIN_DB..=MAX_DB,
+                    r_bounds.bottom_up_range(),
+                )),
             ),
         ),
         Rounding::ZERO,
@@ -456,11 +1661,19 @@ fn draw_peak_meters(
     );
     ui.painter().hline(
         r_bounds.x_range(),
-        remap_clamp(
-            held_peak_value_db_r,
-            MIN_DB..=MAX_DB,
-            r_bounds.bottom_up_range(),
-        ),
-        Stroke::new(1.0, Color32::GRAY),
+        pixel_grid
+            .snap_pos(
+                pos2(
+                    r_bounds.left(),
+                    remap_clamp(
+                        held_peak_value_db_r,
+                        MIN_DB..=MAX_DB,
+                        r_bounds.bottom_up_range(),
+                    ),
+                ),
+                1.0,
+            )
+            .y,
+        pixel_grid.stroke(1.0, Color32::GRAY),
     );
 }
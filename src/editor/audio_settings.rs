@@ -0,0 +1,105 @@
+use nih_plug_egui::egui::{ComboBox, Response, TextEdit, Ui};
+
+use crate::standalone::{StandaloneConfig, BACKENDS, PERIOD_SIZES, SAMPLE_RATES};
+
+/// The standalone build's audio device settings. These only take effect after restarting
+#[derive(Default)]
+pub struct AudioSettings {
+    config: StandaloneConfig,
+    status: Option<String>,
+}
+
+impl AudioSettings {
+    /// Reloads the saved settings, so reopening the window throws away anything that wasn't saved
+    pub fn reload(&mut self) {
+        self.config = StandaloneConfig::load();
+        self.status = None;
+    }
+
+    pub fn show(&mut self, ui: &mut Ui) {
+        option_combo(
+            ui,
+            "BACKEND",
+            &mut self.config.backend,
+            BACKENDS.iter().map(|backend| backend.to_string()),
+        );
+        option_text(ui, "INPUT DEVICE", &mut self.config.input_device);
+        option_text(ui, "OUTPUT DEVICE", &mut self.config.output_device);
+        option_combo(
+            ui,
+            "SAMPLE RATE",
+            &mut self.config.sample_rate,
+            SAMPLE_RATES.iter().copied(),
+        );
+        option_combo(
+            ui,
+            "BUFFER SIZE",
+            &mut self.config.period_size,
+            PERIOD_SIZES.iter().copied(),
+        );
+        option_text(ui, "JACK INPUTS", &mut self.config.jack_inputs).on_hover_text(
+            "Comma separated JACK ports to connect to the inputs, e.g. \
+             system:capture_3,system:capture_4",
+        );
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button("SAVE").clicked() {
+                self.status = Some(match self.config.save() {
+                    Ok(()) => "Saved, restart Centered to apply".to_string(),
+                    Err(err) => format!("Couldn't save the settings: {err}"),
+                });
+            }
+
+            if ui.button("REVERT").clicked() {
+                self.reload();
+            }
+        });
+
+        if let Some(status) = &self.status {
+            ui.label(status);
+        }
+    }
+}
+
+/// A combo box where the first entry leaves the setting up to the wrapper
+fn option_combo<T: Clone + PartialEq + ToString>(
+    ui: &mut Ui,
+    label: &str,
+    value: &mut Option<T>,
+    choices: impl Iterator<Item = T>,
+) {
+    let selected = value
+        .as_ref()
+        .map_or_else(|| "Default".to_string(), ToString::to_string);
+
+    ComboBox::from_label(label)
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(value, None, "Default");
+            for choice in choices {
+                let text = choice.to_string();
+                ui.selectable_value(value, Some(choice), text);
+            }
+        });
+}
+
+/// A text field where leaving it empty leaves the setting up to the wrapper
+fn option_text(ui: &mut Ui, label: &str, value: &mut Option<String>) -> Response {
+    let mut text = value.clone().unwrap_or_default();
+
+    let response = ui
+        .horizontal(|ui| {
+            let response = ui.add(TextEdit::singleline(&mut text).hint_text("Default"));
+            ui.label(label);
+            response
+        })
+        .inner;
+
+    if response.changed() {
+        *value = Some(text).filter(|text| !text.trim().is_empty());
+    }
+
+    response
+}
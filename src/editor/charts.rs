@@ -0,0 +1,170 @@
+use std::sync::atomic::Ordering;
+
+use nih_plug::prelude::AtomicF32;
+use nih_plug_egui::egui::{
+    pos2, remap_clamp, vec2, Align2, Button, Color32, FontFamily, FontId, Rect, Sense, Shape,
+    Slider, Stroke, Ui,
+};
+
+use super::pixels::PixelGrid;
+use crate::history::{History, TransportMarker, HISTORY_LEN, HISTORY_RATE_HZ, HISTORY_SECONDS};
+
+const ANGLE_RANGE_DEG: f32 = 45.0;
+const LOUDNESS_MIN_DB: f32 = -60.0;
+const LOUDNESS_MAX_DB: f32 = 0.0;
+const MIN_HISTORY_SPAN_SECONDS: f32 = 5.0;
+
+/// The editor's view into the [`History`]. While paused it works off of a snapshot, so the data can be scrolled
+/// through without it moving
+#[derive(Default)]
+pub struct HistoryView {
+    paused: bool,
+    snapshot: Vec<(f32, f32, TransportMarker)>,
+    /// How many points back from the newest one the right edge of the chart is
+    offset: usize,
+}
+
+impl HistoryView {
+    pub fn show(&mut self, ui: &mut Ui, history: &History, span_seconds: &AtomicF32) {
+        let mut span = span_seconds.load(Ordering::Relaxed);
+
+        ui.horizontal(|ui| {
+            if ui.toggle_value(&mut self.paused, "PAUSE").changed() && !self.paused {
+                self.offset = 0;
+            }
+
+            if ui
+                .add_enabled(self.offset > 0, Button::new("LIVE"))
+                .clicked()
+            {
+                self.paused = false;
+                self.offset = 0;
+            }
+
+            if ui
+                .add(
+                    Slider::new(&mut span, MIN_HISTORY_SPAN_SECONDS..=HISTORY_SECONDS as f32)
+                        .logarithmic(true)
+                        .suffix(" s")
+                        .text("SPAN"),
+                )
+                .changed()
+            {
+                span_seconds.store(span, Ordering::Relaxed);
+            }
+        });
+
+        if !self.paused {
+            self.snapshot.clear();
+            self.snapshot.extend(history.iter());
+        }
+
+        let (rect, response) =
+            ui.allocate_exact_size(vec2(ui.available_width(), 80.0), Sense::drag());
+
+        let span_points = ((span * HISTORY_RATE_HZ as f32).round() as usize).clamp(2, HISTORY_LEN);
+        let x_step = rect.width() / (span_points - 1) as f32;
+
+        // dragging to the right goes back in time, and freezes the chart so it doesn't move from under the cursor
+        if response.dragged() {
+            self.paused = true;
+            let delta_points = (response.drag_delta().x / x_step).round() as isize;
+            self.offset = self
+                .offset
+                .saturating_add_signed(delta_points)
+                .min(self.snapshot.len().saturating_sub(span_points));
+        }
+
+        let end = self.snapshot.len().saturating_sub(self.offset);
+        let start = end.saturating_sub(span_points);
+        draw_history(ui, rect, &self.snapshot[start..end], span_points);
+    }
+}
+
+/// Draws the correction angle and loudness history, with the host's bars and loop points marked. `span_points` is
+/// how many points fit in `bounds`, and the points are right aligned
+fn draw_history(ui: &Ui, bounds: Rect, points: &[(f32, f32, TransportMarker)], span_points: usize) {
+    let pixel_grid = PixelGrid::new(ui.ctx());
+    let bounds = pixel_grid.snap_rect(bounds);
+    let painter = ui.painter_at(bounds);
+
+    painter.rect_stroke(bounds, 0.0, pixel_grid.stroke(1.0, Color32::DARK_GRAY));
+    painter.hline(
+        bounds.x_range(),
+        pixel_grid.snap_pos(bounds.center(), 1.0).y,
+        pixel_grid.stroke(1.0, Color32::GRAY.gamma_multiply(0.5)),
+    );
+
+    let x_step = bounds.width() / (span_points - 1) as f32;
+    let x_start = bounds.right() - (points.len().saturating_sub(1)) as f32 * x_step;
+    // no point in drawing more than one point per pixel on long spans
+    let stride = ((1.0 / (x_step * pixel_grid.pixels_per_point())).floor() as usize).max(1);
+
+    let mut angle_points = Vec::with_capacity(points.len() / stride + 1);
+    let mut loudness_points = Vec::with_capacity(points.len() / stride + 1);
+
+    for (idx, &(angle, loudness, marker)) in points.iter().enumerate() {
+        let x = (idx as f32).mul_add(x_step, x_start);
+
+        let marker_stroke = match marker {
+            TransportMarker::None => None,
+            TransportMarker::Bar => Some(pixel_grid.stroke(1.0, Color32::GRAY.gamma_multiply(0.3))),
+            TransportMarker::Loop => {
+                Some(pixel_grid.stroke(1.0, cozy_ui::colors::HIGHLIGHT_COL32.gamma_multiply(0.6)))
+            }
+        };
+        if let Some(stroke) = marker_stroke {
+            painter.vline(
+                pixel_grid.snap_pos(pos2(x, bounds.top()), 1.0).x,
+                bounds.y_range(),
+                stroke,
+            );
+        }
+
+        if idx % stride != 0 {
+            continue;
+        }
+
+        angle_points.push(pos2(
+            x,
+            remap_clamp(
+                angle,
+                -ANGLE_RANGE_DEG..=ANGLE_RANGE_DEG,
+                bounds.bottom_up_range(),
+            ),
+        ));
+        loudness_points.push(pos2(
+            x,
+            remap_clamp(
+                loudness,
+                LOUDNESS_MIN_DB..=LOUDNESS_MAX_DB,
+                bounds.bottom_up_range(),
+            ),
+        ));
+    }
+
+    painter.add(Shape::line(
+        loudness_points,
+        Stroke::new(1.0, Color32::GRAY.gamma_multiply(0.6)),
+    ));
+    painter.add(Shape::line(
+        angle_points,
+        Stroke::new(1.5, cozy_ui::colors::HIGHLIGHT_COL32),
+    ));
+
+    let font = FontId::new(10.0, FontFamily::Name("0x".into()));
+    painter.text(
+        bounds.left_top() + vec2(4.0, 2.0),
+        Align2::LEFT_TOP,
+        "ANGLE",
+        font.clone(),
+        cozy_ui::colors::HIGHLIGHT_COL32,
+    );
+    painter.text(
+        bounds.left_bottom() + vec2(4.0, -2.0),
+        Align2::LEFT_BOTTOM,
+        "LOUDNESS",
+        font,
+        Color32::GRAY,
+    );
+}
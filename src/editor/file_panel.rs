@@ -0,0 +1,219 @@
+use std::path::{Path, PathBuf};
+
+use nih_plug::prelude::{Enum, ParamSetter};
+use nih_plug_egui::egui::{Context, ProgressBar, Ui};
+
+use super::param_widgets::set_param;
+use crate::{
+    estimator::EstimatorKind,
+    file_analysis::{FileAnalysis, FileAnalyzer},
+    file_player::{write_wav, FilePlayer, FilePlayerStatus, LoadedFile},
+    standalone::is_standalone,
+    CenteredParams, CenteredTask, CorrectionMode, CorrectionSource,
+};
+
+/// Drop a file onto the editor to analyze it and get suggested settings. The standalone app can also preview the file
+/// with correction and export the corrected version
+#[derive(Default)]
+pub struct FilePanel {
+    analysis: Option<FileAnalysis>,
+    /// Waiting on the background task to finish analyzing a file
+    analyzing: bool,
+    message: Option<String>,
+}
+
+impl FilePanel {
+    /// Starts analyzing the first file dropped onto the window, handing the work to `execute_task`. Returns whether
+    /// anything was dropped
+    pub fn handle_drops(
+        &mut self,
+        ctx: &Context,
+        execute_task: &dyn Fn(CenteredTask),
+        player: &FilePlayer,
+        estimator_kind: EstimatorKind,
+    ) -> bool {
+        let Some(path) = ctx.input(|input| {
+            input
+                .raw
+                .dropped_files
+                .iter()
+                .find_map(|file| file.path.clone())
+        }) else {
+            return false;
+        };
+
+        self.analysis = None;
+        self.message = None;
+        // the plugin has no way to play a file, only the standalone app gets to preview and export
+        if is_standalone() {
+            match LoadedFile::open(&path) {
+                Ok(file) => player.load(file),
+                Err(err) => {
+                    self.message = Some(format!("Couldn't open {}: {err}", path.display()));
+                    return true;
+                }
+            }
+        }
+
+        self.analyzing = true;
+        execute_task(CenteredTask::AnalyzeFile(path, estimator_kind));
+
+        true
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        setter: &ParamSetter,
+        params: &CenteredParams,
+        analyzer: &FileAnalyzer,
+        player: &FilePlayer,
+        device_sample_rate: f32,
+    ) {
+        if let Some(result) = analyzer.take_result() {
+            self.analyzing = false;
+            match result {
+                Ok(analysis) => self.analysis = Some(analysis),
+                Err(err) => self.message = Some(err),
+            }
+        }
+
+        if let Some((path, frames, sample_rate)) = player.take_export() {
+            let export_path = export_path(&path);
+            self.message = Some(match write_wav(&export_path, &frames, sample_rate) {
+                Ok(()) => format!("Exported to {}", export_path.display()),
+                Err(err) => format!("Couldn't export: {err}"),
+            });
+        }
+
+        if self.analyzing {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Analyzing...");
+            });
+        } else if let Some(analysis) = &self.analysis {
+            ui.heading(&analysis.name);
+            ui.label(format!(
+                "{:.1} s at {} Hz, peaking at {:.1} dBFS",
+                analysis.duration_seconds, analysis.sample_rate, analysis.peak_db
+            ));
+            ui.label(match analysis.angle {
+                Some(angle) if angle >= 0.0 => format!(
+                    "Leans {angle:.1}° to the right, wandering by {:.1}°",
+                    analysis.angle_spread
+                ),
+                Some(angle) => format!(
+                    "Leans {:.1}° to the left, wandering by {:.1}°",
+                    -angle, analysis.angle_spread
+                ),
+                None => "The file is silent".to_string(),
+            });
+
+            let suggestions = &analysis.suggestions;
+            ui.separator();
+            ui.label(match suggestions.static_angle {
+                Some(angle) => format!(
+                    "Suggested: a fixed correction turning the image {:.1}° to the {}",
+                    angle.abs(),
+                    if angle >= 0.0 { "right" } else { "left" }
+                ),
+                None => format!(
+                    "Suggested: {} mode with a {:.0} ms reaction time",
+                    CorrectionMode::variants()[suggestions.mode.to_index()],
+                    suggestions.reaction_time_ms
+                ),
+            });
+            if let Some(frequency) = suggestions.detection_highpass_hz {
+                ui.label(format!(
+                    "The low end leans somewhere else, so the detector should only listen above \
+                     {frequency:.0} Hz"
+                ));
+            }
+            if ui.button("APPLY").clicked() {
+                set_param(setter, &params.mode, suggestions.mode);
+                set_param(setter, &params.reaction_time, suggestions.reaction_time_ms);
+                match suggestions.static_angle {
+                    Some(angle) => {
+                        set_param(setter, &params.correction_source, CorrectionSource::Manual);
+                        set_param(setter, &params.manual_angle, angle);
+                    }
+                    None => set_param(setter, &params.correction_source, CorrectionSource::Live),
+                }
+                set_param(
+                    setter,
+                    &params.detection_highpass,
+                    suggestions.detection_highpass_hz.is_some(),
+                );
+                if let Some(frequency) = suggestions.detection_highpass_hz {
+                    set_param(setter, &params.detection_highpass_frequency, frequency);
+                }
+            }
+
+            if is_standalone() {
+                ui.separator();
+                show_playback(ui, player, analysis, device_sample_rate);
+            }
+        } else {
+            ui.label("Drop a WAV or FLAC file onto the window to analyze it");
+        }
+
+        if let Some(message) = &self.message {
+            ui.label(message);
+        }
+    }
+}
+
+fn show_playback(
+    ui: &mut Ui,
+    player: &FilePlayer,
+    analysis: &FileAnalysis,
+    device_sample_rate: f32,
+) {
+    if analysis.sample_rate as f32 != device_sample_rate {
+        ui.label(format!(
+            "The audio device runs at {device_sample_rate} Hz, so the file will play back at the \
+             wrong speed"
+        ));
+    }
+
+    ui.horizontal(|ui| match player.status() {
+        FilePlayerStatus::Idle => {
+            if ui.button("PREVIEW").clicked() {
+                player.preview();
+            }
+            if ui
+                .button("EXPORT")
+                .on_hover_text(
+                    "Plays the file through Centered once and saves the corrected output next to it",
+                )
+                .clicked()
+            {
+                player.export();
+            }
+        }
+        FilePlayerStatus::Previewing => {
+            ui.add(ProgressBar::new(player.progress()).desired_width(150.0));
+            if ui.button("STOP").clicked() {
+                player.stop();
+            }
+        }
+        FilePlayerStatus::Exporting | FilePlayerStatus::ExportDone => {
+            ui.add(
+                ProgressBar::new(player.progress())
+                    .desired_width(150.0)
+                    .text("EXPORTING"),
+            );
+            if ui.button("CANCEL").clicked() {
+                player.stop();
+            }
+        }
+    });
+}
+
+fn export_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+
+    path.with_file_name(format!("{stem}-centered.wav"))
+}
@@ -0,0 +1,53 @@
+use nih_plug::prelude::{BoolParam, Enum, EnumParam, Param, ParamSetter};
+use nih_plug_egui::egui::{ComboBox, Response, Slider, Ui};
+
+/// Sets a parameter as a single gesture, for widgets that don't have a drag to wrap the gesture around
+pub fn set_param<P: Param>(setter: &ParamSetter, param: &P, value: P::Plain) {
+    setter.begin_set_parameter(param);
+    setter.set_parameter(param, value);
+    setter.end_set_parameter(param);
+}
+
+pub fn param_toggle(ui: &mut Ui, setter: &ParamSetter, param: &BoolParam, label: &str) -> Response {
+    let mut value = param.value();
+    let response = ui.toggle_value(&mut value, label);
+    if response.changed() {
+        set_param(setter, param, value);
+    }
+
+    response
+}
+
+pub fn param_combo<T: Enum + PartialEq + 'static>(
+    ui: &mut Ui,
+    setter: &ParamSetter,
+    param: &EnumParam<T>,
+    label: &str,
+) {
+    let current = param.value().to_index();
+
+    ComboBox::from_label(label)
+        .selected_text(param.to_string())
+        .show_ui(ui, |ui| {
+            for (idx, name) in T::variants().iter().enumerate() {
+                if ui.selectable_label(idx == current, *name).clicked() && idx != current {
+                    set_param(setter, param, T::from_index(idx));
+                }
+            }
+        });
+}
+
+pub fn param_slider<P: Param>(ui: &mut Ui, setter: &ParamSetter, param: &P, label: &str) {
+    let mut value = param.unmodulated_normalized_value();
+    ui.horizontal(|ui| {
+        if ui
+            .add(Slider::new(&mut value, 0.0..=1.0).show_value(false))
+            .changed()
+        {
+            setter.begin_set_parameter(param);
+            setter.set_parameter_normalized(param, value);
+            setter.end_set_parameter(param);
+        }
+        ui.label(format!("{label}: {param}"));
+    });
+}
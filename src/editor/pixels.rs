@@ -0,0 +1,57 @@
+use nih_plug_egui::egui::{pos2, Color32, Context, Pos2, Rect, Stroke};
+
+/// Helpers for lining shapes up with physical pixels. Without this, lines on fractional scale factors land between
+/// pixels and get smeared across two of them
+#[derive(Clone, Copy)]
+pub struct PixelGrid {
+    pixels_per_point: f32,
+}
+
+impl PixelGrid {
+    pub fn new(ctx: &Context) -> Self {
+        Self {
+            pixels_per_point: ctx.pixels_per_point(),
+        }
+    }
+
+    pub fn pixels_per_point(&self) -> f32 {
+        self.pixels_per_point
+    }
+
+    /// Rounds a stroke width (in points) to a whole number of physical pixels
+    pub fn stroke_width(&self, width: f32) -> f32 {
+        (width * self.pixels_per_point).round().max(1.0) / self.pixels_per_point
+    }
+
+    pub fn stroke(&self, width: f32, color: impl Into<Color32>) -> Stroke {
+        Stroke::new(self.stroke_width(width), color)
+    }
+
+    /// Snaps a point so that a line of `width` points drawn through it covers whole pixels
+    pub fn snap_pos(&self, pos: Pos2, width: f32) -> Pos2 {
+        let width_px = (width * self.pixels_per_point).round().max(1.0);
+        // odd widths need to sit on a pixel center, even widths on a pixel edge
+        let offset = if width_px % 2.0 == 1.0 { 0.5 } else { 0.0 };
+
+        pos2(
+            ((pos.x * self.pixels_per_point).floor() + offset) / self.pixels_per_point,
+            ((pos.y * self.pixels_per_point).floor() + offset) / self.pixels_per_point,
+        )
+    }
+
+    pub fn snap_line(&self, points: [Pos2; 2], width: f32) -> [Pos2; 2] {
+        points.map(|point| self.snap_pos(point, width))
+    }
+
+    /// Snaps a coordinate to the nearest pixel edge, for filled shapes
+    pub fn snap_edge(&self, coord: f32) -> f32 {
+        (coord * self.pixels_per_point).round() / self.pixels_per_point
+    }
+
+    pub fn snap_rect(&self, rect: Rect) -> Rect {
+        Rect::from_min_max(
+            pos2(self.snap_edge(rect.min.x), self.snap_edge(rect.min.y)),
+            pos2(self.snap_edge(rect.max.x), self.snap_edge(rect.max.y)),
+        )
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    f32::consts::PI,
+    fs::File,
+    io::BufWriter,
+    path::PathBuf,
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, Rgba, RgbaImage,
+};
+use nih_plug_egui::egui::{Color32, RichText, Ui};
+
+use super::{scope::ScopeHistory, TRANSLATE_SIN_COS};
+
+const RECORDING_FPS: u32 = 30;
+const MAX_RECORDING_LENGTH: Duration = Duration::from_secs(30);
+/// The recording is square, this is both its width and height in pixels
+const RECORDING_SIZE: u32 = 360;
+
+/// The dots on screen during one frame of the recording
+struct ScopeFrame {
+    pre: Vec<(f32, f32)>,
+    post: Vec<(f32, f32)>,
+}
+
+#[derive(Default)]
+enum RecorderStatus {
+    #[default]
+    Idle,
+    Recording {
+        started: Instant,
+        last_frame: Option<Instant>,
+    },
+    Encoding(JoinHandle<Result<PathBuf, String>>),
+}
+
+/// Records the goniometer into an animated GIF, for sharing what a mix is doing. The dots get recorded while the
+/// scope is drawn, and turned into an image on a background thread once the recording stops
+#[derive(Default)]
+pub struct ScopeRecorder {
+    status: RecorderStatus,
+    frames: Vec<ScopeFrame>,
+    message: Option<String>,
+}
+
+impl ScopeRecorder {
+    /// Records a frame if one is due. Call this every time the scope is drawn
+    pub fn capture(&mut self, now: Instant, pre: &ScopeHistory, post: &ScopeHistory) {
+        let RecorderStatus::Recording {
+            started,
+            last_frame,
+        } = &mut self.status
+        else {
+            return;
+        };
+
+        if last_frame
+            .is_some_and(|last_frame| now - last_frame < Duration::from_secs(1) / RECORDING_FPS)
+        {
+            return;
+        }
+        *last_frame = Some(now);
+
+        let clamp = |(left, right): (f32, f32)| (left.clamp(-1.0, 1.0), right.clamp(-1.0, 1.0));
+        self.frames.push(ScopeFrame {
+            pre: pre.interpolated(now).map(clamp).collect(),
+            post: post.interpolated(now).map(clamp).collect(),
+        });
+
+        if now - *started >= MAX_RECORDING_LENGTH {
+            self.stop();
+        }
+    }
+
+    /// The record button, along with the recording's progress and where it ended up
+    pub fn show(&mut self, ui: &mut Ui) {
+        match &self.status {
+            RecorderStatus::Idle => {
+                if ui
+                    .button("RECORD")
+                    .on_hover_text("Records up to 30 seconds of the scope as an animated GIF")
+                    .clicked()
+                {
+                    self.frames.clear();
+                    self.message = None;
+                    self.status = RecorderStatus::Recording {
+                        started: Instant::now(),
+                        last_frame: None,
+                    };
+                }
+            }
+            RecorderStatus::Recording { started, .. } => {
+                ui.label(
+                    RichText::new(format!("REC {:.1} s", started.elapsed().as_secs_f32()))
+                        .color(Color32::RED),
+                );
+                if ui.button("STOP").clicked() {
+                    self.stop();
+                }
+            }
+            RecorderStatus::Encoding(handle) => {
+                if handle.is_finished() {
+                    let RecorderStatus::Encoding(handle) =
+                        std::mem::replace(&mut self.status, RecorderStatus::Idle)
+                    else {
+                        unreachable!()
+                    };
+
+                    self.message = Some(match handle.join() {
+                        Ok(Ok(path)) => format!("Saved to {}", path.display()),
+                        Ok(Err(err)) => format!("Couldn't save the recording: {err}"),
+                        Err(_) => "Couldn't save the recording".to_string(),
+                    });
+                } else {
+                    ui.label("SAVING GIF");
+                }
+            }
+        }
+
+        if let Some(message) = &self.message {
+            if ui
+                .button(message)
+                .on_hover_text("Click to dismiss")
+                .clicked()
+            {
+                self.message = None;
+            }
+        }
+    }
+
+    fn stop(&mut self) {
+        let frames = std::mem::take(&mut self.frames);
+        if frames.is_empty() {
+            self.status = RecorderStatus::Idle;
+            return;
+        }
+
+        self.status = RecorderStatus::Encoding(std::thread::spawn(move || encode_gif(&frames)));
+    }
+}
+
+fn encode_gif(frames: &[ScopeFrame]) -> Result<PathBuf, String> {
+    let dir = dirs::picture_dir()
+        .or_else(dirs::home_dir)
+        .ok_or("there's no pictures or home folder to save it in")?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = dir.join(format!("centered-scope-{timestamp}.gif"));
+
+    let file = File::create(&path).map_err(|err| err.to_string())?;
+    let mut encoder = GifEncoder::new(BufWriter::new(file));
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(|err| err.to_string())?;
+
+    let delay = Delay::from_numer_denom_ms(1000, RECORDING_FPS);
+    let [pre_r, pre_g, pre_b, _] = Color32::WHITE.to_array();
+    let [post_r, post_g, post_b, _] = cozy_ui::colors::HIGHLIGHT_COL32.to_array();
+
+    encoder
+        .encode_frames(frames.iter().map(|frame| {
+            let mut image =
+                RgbaImage::from_pixel(RECORDING_SIZE, RECORDING_SIZE, Rgba([0, 0, 0, 255]));
+            draw_dots(&mut image, &frame.pre, [pre_r, pre_g, pre_b]);
+            draw_dots(&mut image, &frame.post, [post_r, post_g, post_b]);
+
+            Frame::from_parts(image, 0, 0, delay)
+        }))
+        .map_err(|err| err.to_string())?;
+
+    Ok(path)
+}
+
+/// Plots the dots the same way the editor does, fading them out the quieter they are
+fn draw_dots(image: &mut RgbaImage, dots: &[(f32, f32)], color: [u8; 3]) {
+    let (translate_sin, translate_cos) = *TRANSLATE_SIN_COS;
+    let size = RECORDING_SIZE as f32;
+
+    for &(left, right) in dots {
+        let dot_x = left * translate_cos - right * translate_sin;
+        let dot_y = left * translate_sin + right * translate_cos;
+        let x = dot_x.mul_add(size / PI, size / 2.0);
+        let y = dot_y.mul_add(size / PI, size / 2.0);
+        let alpha = (left.abs() + right.abs()) / 2.0;
+
+        for (x, y) in [(x, y), (x + 1.0, y), (x, y + 1.0), (x + 1.0, y + 1.0)] {
+            if !(0.0..size).contains(&x) || !(0.0..size).contains(&y) {
+                continue;
+            }
+
+            let pixel = image.get_pixel_mut(x as u32, y as u32);
+            for (channel, target) in pixel.0.iter_mut().zip(color) {
+                *channel = (f32::from(target) - f32::from(*channel))
+                    .mul_add(alpha, f32::from(*channel)) as u8;
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    f32::consts::PI,
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use nih_plug::{params::smoothing::AtomicF32, util::db_to_gain};
+use nih_plug_egui::egui::{
+    epaint::{Mesh, TessellationOptions, Tessellator},
+    vec2, Color32, Context, Painter, Pos2, Rect, Shape, Stroke,
+};
+
+use once_cell::sync::Lazy;
+
+use super::{pixels::PixelGrid, TRANSLATE_SIN_COS};
+use crate::config::GLOBAL_CONFIG;
+
+/// The color out of phase samples get drawn in, from the config if it has a valid one
+pub static ANTI_PHASE_COLOR: Lazy<Color32> = Lazy::new(|| {
+    GLOBAL_CONFIG
+        .editor
+        .anti_phase_color
+        .as_deref()
+        .and_then(|hex| Color32::from_hex(hex).ok())
+        .unwrap_or(Color32::RED)
+});
+/// Both channels need to be at least this loud for a sample to count as out of phase, so noise around zero doesn't
+/// flicker
+static ANTI_PHASE_MIN_LEVEL: Lazy<f32> = Lazy::new(|| db_to_gain(-30.0));
+
+/// Keeps the last two distinct snapshots of a goniometer buffer, so dots can be interpolated between audio blocks
+/// instead of jumping once per block on high refresh rate displays
+#[derive(Default)]
+pub struct ScopeHistory {
+    previous: Vec<(f32, f32)>,
+    current: Vec<(f32, f32)>,
+    incoming: Vec<(f32, f32)>,
+    last_update: Option<Instant>,
+    update_interval: Duration,
+}
+
+impl ScopeHistory {
+    pub fn update(&mut self, data: &[(AtomicF32, AtomicF32)], now: Instant) {
+        self.incoming.clear();
+        self.incoming
+            .extend(data.iter().map(|(left, right)| {
+                (left.load(Ordering::Relaxed), right.load(Ordering::Relaxed))
+            }));
+
+        if self.incoming == self.current {
+            return;
+        }
+
+        if let Some(last_update) = self.last_update {
+            self.update_interval = now - last_update;
+        }
+        self.last_update = Some(now);
+
+        std::mem::swap(&mut self.previous, &mut self.current);
+        std::mem::swap(&mut self.current, &mut self.incoming);
+    }
+
+    pub fn interpolated(&self, now: Instant) -> impl Iterator<Item = (f32, f32)> + '_ {
+        let t = match self.last_update {
+            Some(last_update) if !self.update_interval.is_zero() => {
+                ((now - last_update).as_secs_f32() / self.update_interval.as_secs_f32()).min(1.0)
+            }
+            _ => 1.0,
+        };
+
+        self.current
+            .iter()
+            .enumerate()
+            .map(move |(idx, &(left, right))| match self.previous.get(idx) {
+                Some(&(prev_left, prev_right)) => (
+                    (left - prev_left).mul_add(t, prev_left),
+                    (right - prev_right).mul_add(t, prev_right),
+                ),
+                None => (left, right),
+            })
+    }
+}
+
+/// Plots goniometer samples, louder ones brighter. With `connected`, consecutive samples are joined with lines like
+/// an analog vector scope, which shows the phase rotation patterns a dot cloud hides. Samples where the channels have
+/// opposite signs get drawn in `anti_phase_color` instead, if there is one
+pub fn draw_scope(
+    painter: &Painter,
+    scope_rect: Rect,
+    samples: impl Iterator<Item = (f32, f32)>,
+    color: Color32,
+    anti_phase_color: Option<Color32>,
+    connected: bool,
+) {
+    let (translate_sin, translate_cos) = *TRANSLATE_SIN_COS;
+    let center = scope_rect.center();
+    let mut previous: Option<(Pos2, f32)> = None;
+
+    for (left, right) in
+        samples.map(|(left, right)| (left.clamp(-1.0, 1.0), right.clamp(-1.0, 1.0)))
+    {
+        let dot_x = left * translate_cos - right * translate_sin;
+        let dot_y = left * translate_sin + right * translate_cos;
+        let pos = center
+            + vec2(
+                dot_x * scope_rect.width() / PI,
+                dot_y * scope_rect.height() / PI,
+            );
+        let intensity = (left.abs() + right.abs()) / 2.0;
+        let color = match anti_phase_color {
+            Some(anti_phase_color)
+                if left * right < 0.0 && left.abs().min(right.abs()) >= *ANTI_PHASE_MIN_LEVEL =>
+            {
+                anti_phase_color
+            }
+            _ => color,
+        };
+
+        if connected {
+            if let Some((previous_pos, previous_intensity)) = previous {
+                painter.line_segment(
+                    [previous_pos, pos],
+                    Stroke::new(
+                        1.0,
+                        color.gamma_multiply((intensity + previous_intensity) / 2.0),
+                    ),
+                );
+            }
+            previous = Some((pos, intensity));
+        } else {
+            painter.circle_filled(pos, 1.5, color.gamma_multiply(intensity));
+        }
+    }
+}
+
+/// The goniometer's axes and the diamond around it only change when the editor is resized, so they get tessellated
+/// once and reused as a mesh
+#[derive(Default)]
+pub struct GridCache {
+    key: Option<(Rect, f32)>,
+    mesh: Mesh,
+}
+
+impl GridCache {
+    pub fn shape(&mut self, ctx: &Context, scope_rect: Rect, pixel_grid: PixelGrid) -> Shape {
+        let key = (scope_rect, pixel_grid.pixels_per_point());
+
+        if self.key != Some(key) {
+            let mut tessellator = Tessellator::new(
+                pixel_grid.pixels_per_point(),
+                TessellationOptions::default(),
+                ctx.fonts(|fonts| fonts.font_image_size()),
+                Vec::new(),
+            );
+
+            self.mesh.clear();
+            for shape in grid_shapes(scope_rect, pixel_grid) {
+                tessellator.tessellate_shape(shape, &mut self.mesh);
+            }
+            self.key = Some(key);
+        }
+
+        Shape::mesh(self.mesh.clone())
+    }
+}
+
+fn grid_shapes(scope_rect: Rect, pixel_grid: PixelGrid) -> impl Iterator<Item = Shape> {
+    let axis_stroke = pixel_grid.stroke(1.5, Color32::GRAY.gamma_multiply(0.5));
+    let diagonal_stroke = pixel_grid.stroke(1.5, Color32::GRAY.gamma_multiply(0.55));
+    let diamond_stroke = pixel_grid.stroke(1.5, Color32::GRAY);
+
+    let lines = [
+        (
+            [scope_rect.center_top(), scope_rect.center_bottom()],
+            axis_stroke,
+        ),
+        (
+            [scope_rect.left_center(), scope_rect.right_center()],
+            axis_stroke,
+        ),
+        (
+            [
+                scope_rect.min + (scope_rect.size() * 0.25),
+                scope_rect.max - (scope_rect.size() * 0.25),
+            ],
+            diagonal_stroke,
+        ),
+        (
+            [
+                scope_rect.min + (scope_rect.size() * vec2(0.75, 0.25)),
+                scope_rect.max - (scope_rect.size() * vec2(0.75, 0.25)),
+            ],
+            diagonal_stroke,
+        ),
+        (
+            [scope_rect.center_top(), scope_rect.left_center()],
+            diamond_stroke,
+        ),
+        (
+            [scope_rect.left_center(), scope_rect.center_bottom()],
+            diamond_stroke,
+        ),
+        (
+            [scope_rect.center_bottom(), scope_rect.right_center()],
+            diamond_stroke,
+        ),
+        (
+            [scope_rect.right_center(), scope_rect.center_top()],
+            diamond_stroke,
+        ),
+    ];
+
+    lines
+        .into_iter()
+        .map(move |(points, stroke)| Shape::line_segment(pixel_grid.snap_line(points, 1.5), stroke))
+}
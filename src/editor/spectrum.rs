@@ -0,0 +1,302 @@
+use std::{
+    f32::consts::TAU,
+    sync::{
+        atomic::{AtomicU32, AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+use nih_plug::util::db_to_gain;
+use nih_plug_egui::egui::{
+    pos2, remap_clamp, vec2, Align2, Color32, ComboBox, FontFamily, FontId, Rect, Sense, Shape,
+    Stroke, Ui,
+};
+use realfft::{num_complex::Complex, RealFftPlanner, RealToComplex};
+
+use super::pixels::PixelGrid;
+use crate::analyzer::{AnalyzerInput, DEFAULT_FFT_SIZE, FFT_SIZES};
+
+const ANGLE_RANGE_DEG: f32 = 45.0;
+const MIN_FREQUENCY: f32 = 20.0;
+/// Bins quieter than this don't have a balance worth showing
+const MIN_BIN_DB: f32 = -80.0;
+/// How much of the previous reading every new one keeps, so the display doesn't flicker
+const BALANCE_SMOOTHING: f32 = 0.8;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WindowFunction {
+    #[default]
+    Hann = 0,
+    /// Much less leakage than Hann, so quiet bins next to loud ones keep their own balance
+    BlackmanHarris = 1,
+    /// Barely any scalloping, so the level of a tone reads the same no matter which bin it lands in
+    FlatTop = 2,
+}
+
+impl From<u8> for WindowFunction {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::BlackmanHarris,
+            2 => Self::FlatTop,
+            _ => Self::Hann,
+        }
+    }
+}
+
+impl WindowFunction {
+    pub const ALL: [Self; 3] = [Self::Hann, Self::BlackmanHarris, Self::FlatTop];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Hann => "Hann",
+            Self::BlackmanHarris => "Blackman-Harris",
+            Self::FlatTop => "Flat Top",
+        }
+    }
+
+    /// Every one of these is a sum of cosines, so they only differ by their coefficients
+    fn coefficients(self) -> &'static [f32] {
+        match self {
+            Self::Hann => &[0.5, 0.5],
+            Self::BlackmanHarris => &[0.358_75, 0.488_29, 0.141_28, 0.011_68],
+            Self::FlatTop => &[
+                0.215_578_95,
+                0.416_631_58,
+                0.277_263_16,
+                0.083_578_95,
+                0.006_947_37,
+            ],
+        }
+    }
+
+    /// Fills `window` with a periodic window
+    fn generate(self, window: &mut [f32]) {
+        let len = window.len() as f32;
+
+        for (n, sample) in window.iter_mut().enumerate() {
+            let phase = TAU * n as f32 / len;
+            *sample = self
+                .coefficients()
+                .iter()
+                .enumerate()
+                .map(|(k, coefficient)| {
+                    let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+                    sign * coefficient * (k as f32 * phase).cos()
+                })
+                .sum();
+        }
+    }
+}
+
+/// Shows how the input's balance is spread across the spectrum
+pub struct SpectrumView {
+    fft_size: usize,
+    fft: Arc<dyn RealToComplex<f32>>,
+    /// What `window` was last generated for
+    window_function: Option<WindowFunction>,
+    window: Vec<f32>,
+    left: Vec<f32>,
+    right: Vec<f32>,
+    left_spectrum: Vec<Complex<f32>>,
+    right_spectrum: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    /// The smoothed angle of every bin in degrees, or NaN if the bin is too quiet
+    balance: Vec<f32>,
+}
+
+impl Default for SpectrumView {
+    fn default() -> Self {
+        Self::new(DEFAULT_FFT_SIZE)
+    }
+}
+
+impl SpectrumView {
+    fn new(fft_size: usize) -> Self {
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(fft_size);
+
+        Self {
+            fft_size,
+            window_function: None,
+            window: vec![0.0; fft_size],
+            left: fft.make_input_vec(),
+            right: fft.make_input_vec(),
+            left_spectrum: fft.make_output_vec(),
+            right_spectrum: fft.make_output_vec(),
+            scratch: fft.make_scratch_vec(),
+            balance: vec![f32::NAN; fft_size / 2 + 1],
+            fft,
+        }
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        input: &AnalyzerInput,
+        window_setting: &AtomicU8,
+        fft_size_setting: &AtomicU32,
+    ) {
+        let mut window_function = WindowFunction::from(window_setting.load(Ordering::Relaxed));
+        let mut fft_size = fft_size_setting.load(Ordering::Relaxed) as usize;
+        if !FFT_SIZES.contains(&fft_size) {
+            fft_size = DEFAULT_FFT_SIZE;
+        }
+
+        ui.horizontal(|ui| {
+            ComboBox::from_label("WINDOW")
+                .selected_text(window_function.name())
+                .show_ui(ui, |ui| {
+                    for candidate in WindowFunction::ALL {
+                        ui.selectable_value(&mut window_function, candidate, candidate.name());
+                    }
+                });
+
+            ComboBox::from_label("FFT SIZE")
+                .selected_text(fft_size.to_string())
+                .show_ui(ui, |ui| {
+                    for candidate in FFT_SIZES {
+                        ui.selectable_value(&mut fft_size, candidate, candidate.to_string());
+                    }
+                });
+
+            // bigger FFTs resolve the low end better, but take longer to notice when something changes
+            let sample_rate = input.sample_rate();
+            ui.label(format!(
+                "{:.1} Hz / {:.0} ms",
+                sample_rate / fft_size as f32,
+                fft_size as f32 / sample_rate * 1000.0
+            ));
+        });
+        window_setting.store(window_function as u8, Ordering::Relaxed);
+        fft_size_setting.store(fft_size as u32, Ordering::Relaxed);
+
+        if fft_size != self.fft_size {
+            *self = Self::new(fft_size);
+        }
+
+        self.analyze(input, window_function);
+
+        let (rect, _) = ui.allocate_exact_size(vec2(ui.available_width(), 150.0), Sense::hover());
+        self.draw(ui, rect, input.sample_rate());
+    }
+
+    fn analyze(&mut self, input: &AnalyzerInput, window_function: WindowFunction) {
+        if self.window_function != Some(window_function) {
+            window_function.generate(&mut self.window);
+            self.window_function = Some(window_function);
+        }
+
+        input.read_latest(&mut self.left, &mut self.right);
+        for ((left, right), window) in self
+            .left
+            .iter_mut()
+            .zip(self.right.iter_mut())
+            .zip(self.window.iter())
+        {
+            *left *= window;
+            *right *= window;
+        }
+
+        // this only fails if the buffers are the wrong length, and they all came from the plan
+        let _ = self.fft.process_with_scratch(
+            &mut self.left,
+            &mut self.left_spectrum,
+            &mut self.scratch,
+        );
+        let _ = self.fft.process_with_scratch(
+            &mut self.right,
+            &mut self.right_spectrum,
+            &mut self.scratch,
+        );
+
+        // turns bin magnitudes back into the amplitude of the sine that would've produced them
+        let amplitude_scale = 2.0 / (window_function.coefficients()[0] * self.fft_size as f32);
+        let min_amplitude = db_to_gain(MIN_BIN_DB);
+
+        for ((balance, left), right) in self
+            .balance
+            .iter_mut()
+            .zip(self.left_spectrum.iter())
+            .zip(self.right_spectrum.iter())
+        {
+            let left = left.norm() * amplitude_scale;
+            let right = right.norm() * amplitude_scale;
+
+            let angle = if left.max(right) < min_amplitude {
+                f32::NAN
+            } else {
+                right.atan2(left).to_degrees() - 45.0
+            };
+
+            *balance = if balance.is_nan() || angle.is_nan() {
+                angle
+            } else {
+                balance.mul_add(BALANCE_SMOOTHING, angle * (1.0 - BALANCE_SMOOTHING))
+            };
+        }
+    }
+
+    fn draw(&self, ui: &Ui, bounds: Rect, sample_rate: f32) {
+        let pixel_grid = PixelGrid::new(ui.ctx());
+        let bounds = pixel_grid.snap_rect(bounds);
+        let painter = ui.painter_at(bounds);
+
+        painter.rect_stroke(bounds, 0.0, pixel_grid.stroke(1.0, Color32::DARK_GRAY));
+        painter.hline(
+            bounds.x_range(),
+            pixel_grid.snap_pos(bounds.center(), 1.0).y,
+            pixel_grid.stroke(1.0, Color32::GRAY.gamma_multiply(0.5)),
+        );
+
+        let nyquist = sample_rate / 2.0;
+        let log_range = MIN_FREQUENCY.log10()..=nyquist.log10();
+        let bin_width = sample_rate / self.fft_size as f32;
+
+        // quiet bins break the line up, so it's drawn as a run of segments
+        let mut segment = Vec::new();
+        for (bin, &balance) in self.balance.iter().enumerate().skip(1) {
+            let frequency = bin as f32 * bin_width;
+            if frequency < MIN_FREQUENCY || balance.is_nan() {
+                if segment.len() > 1 {
+                    painter.add(Shape::line(
+                        std::mem::take(&mut segment),
+                        Stroke::new(1.5, cozy_ui::colors::HIGHLIGHT_COL32),
+                    ));
+                }
+                segment.clear();
+                continue;
+            }
+
+            segment.push(pos2(
+                remap_clamp(frequency.log10(), log_range.clone(), bounds.x_range()),
+                remap_clamp(
+                    balance,
+                    -ANGLE_RANGE_DEG..=ANGLE_RANGE_DEG,
+                    bounds.bottom_up_range(),
+                ),
+            ));
+        }
+        if segment.len() > 1 {
+            painter.add(Shape::line(
+                segment,
+                Stroke::new(1.5, cozy_ui::colors::HIGHLIGHT_COL32),
+            ));
+        }
+
+        let font = FontId::new(10.0, FontFamily::Name("0x".into()));
+        painter.text(
+            bounds.left_top() + vec2(4.0, 2.0),
+            Align2::LEFT_TOP,
+            "R",
+            font.clone(),
+            Color32::GRAY,
+        );
+        painter.text(
+            bounds.left_bottom() + vec2(4.0, -2.0),
+            Align2::LEFT_BOTTOM,
+            "L",
+            font,
+            Color32::GRAY,
+        );
+    }
+}
@@ -0,0 +1,358 @@
+use std::{cell::Cell, sync::Mutex};
+
+use nih_plug::{
+    prelude::{GuiContext, Param, ParamPtr, ParamSetter, PluginApi},
+    wrapper::state::PluginState,
+};
+use nih_plug_egui::egui::{
+    epaint::ClippedShape, Area, Context, Event, FullOutput, Id, Modifiers, PointerButton, Pos2,
+    RawInput, Rect, Shape, Vec2,
+};
+
+use super::{param_knob, setup, update, EditorState, KNOB_DIAMETER};
+use crate::{param_locks::CORRECTION_AMOUNT_ID, Centered, CenteredParams};
+
+const SCREEN_SIZE: Vec2 = Vec2::new(1200.0, 900.0);
+/// How far the knob tests drag, comfortably past egui's click distance
+const DRAG_DISTANCE: f32 = 40.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SetterCall {
+    Begin(ParamPtr),
+    Set(ParamPtr, f32),
+    End(ParamPtr),
+}
+
+/// Records every parameter change the editor makes, instead of passing them on to a host
+#[derive(Default)]
+struct RecordingContext {
+    calls: Mutex<Vec<SetterCall>>,
+}
+
+impl RecordingContext {
+    fn take_calls(&self) -> Vec<SetterCall> {
+        std::mem::take(&mut self.calls.lock().unwrap())
+    }
+}
+
+impl GuiContext for RecordingContext {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Standalone
+    }
+
+    fn request_resize(&self) -> bool {
+        false
+    }
+
+    unsafe fn raw_begin_set_parameter(&self, param: ParamPtr) {
+        self.calls.lock().unwrap().push(SetterCall::Begin(param));
+    }
+
+    unsafe fn raw_set_parameter_normalized(&self, param: ParamPtr, normalized: f32) {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(SetterCall::Set(param, normalized));
+    }
+
+    unsafe fn raw_end_set_parameter(&self, param: ParamPtr) {
+        self.calls.lock().unwrap().push(SetterCall::End(param));
+    }
+
+    fn get_state(&self) -> PluginState {
+        panic!("the editor never asks for the plugin's state, so the tests don't keep one")
+    }
+
+    fn set_state(&self, _state: PluginState) {
+        panic!("the editor never restores the plugin's state, so the tests don't keep one")
+    }
+}
+
+/// Drives the editor without a window, one frame at a time
+struct Harness {
+    ctx: Context,
+    time: f64,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let ctx = Context::default();
+        setup(&ctx);
+
+        Self { ctx, time: 0.0 }
+    }
+
+    /// Runs a frame with `events` as its input, and returns every piece of text that got drawn along with where
+    fn run(
+        &mut self,
+        events: Vec<Event>,
+        modifiers: Modifiers,
+        ui: impl FnOnce(&Context),
+    ) -> Vec<(String, Rect)> {
+        self.time += 1.0 / 60.0;
+        let input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, SCREEN_SIZE)),
+            time: Some(self.time),
+            modifiers,
+            events,
+            ..RawInput::default()
+        };
+
+        drawn_text(&self.ctx.run(input, ui))
+    }
+
+    /// Clicks at `pos`, and returns the text drawn on the frame after
+    fn click(
+        &mut self,
+        pos: Pos2,
+        modifiers: Modifiers,
+        ui: impl Fn(&Context),
+    ) -> Vec<(String, Rect)> {
+        self.run(
+            vec![Event::PointerMoved(pos), button_event(pos, true, modifiers)],
+            modifiers,
+            &ui,
+        );
+        self.run(vec![button_event(pos, false, modifiers)], modifiers, &ui);
+
+        self.run(Vec::new(), Modifiers::default(), &ui)
+    }
+
+    /// Presses at `pos`, drags up by [`DRAG_DISTANCE`] over a couple of frames, and lets go
+    fn drag_up(&mut self, pos: Pos2, ui: impl Fn(&Context)) {
+        let modifiers = Modifiers::default();
+        self.run(
+            vec![Event::PointerMoved(pos), button_event(pos, true, modifiers)],
+            modifiers,
+            &ui,
+        );
+        for step in 1..=4 {
+            let pos = pos - Vec2::new(0.0, DRAG_DISTANCE * step as f32 / 4.0);
+            self.run(vec![Event::PointerMoved(pos)], modifiers, &ui);
+        }
+        let pos = pos - Vec2::new(0.0, DRAG_DISTANCE);
+        self.run(vec![button_event(pos, false, modifiers)], modifiers, &ui);
+    }
+}
+
+fn button_event(pos: Pos2, pressed: bool, modifiers: Modifiers) -> Event {
+    Event::PointerButton {
+        pos,
+        button: PointerButton::Primary,
+        pressed,
+        modifiers,
+    }
+}
+
+fn drawn_text(output: &FullOutput) -> Vec<(String, Rect)> {
+    fn collect(shape: &Shape, text: &mut Vec<(String, Rect)>) {
+        match shape {
+            Shape::Text(shape) => text.push((
+                shape.galley.text().to_owned(),
+                shape.galley.rect.translate(shape.pos.to_vec2()),
+            )),
+            Shape::Vec(shapes) => {
+                for shape in shapes {
+                    collect(shape, text);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut text = Vec::new();
+    for ClippedShape { shape, .. } in &output.shapes {
+        collect(shape, &mut text);
+    }
+    text
+}
+
+fn find_text<'a>(text: &'a [(String, Rect)], needle: &str) -> Option<&'a Rect> {
+    text.iter()
+        .find(|(drawn, _)| drawn == needle)
+        .map(|(_, rect)| rect)
+}
+
+/// The whole editor, hooked up to a default plugin instance
+fn editor_ui<'a>(plugin: &Centered, setter: &'a ParamSetter) -> impl Fn(&Context) + 'a {
+    let draw = update(
+        plugin.params.clone(),
+        |_| {},
+        plugin.pre_stereo_data.clone(),
+        plugin.post_stereo_data.clone(),
+        plugin.pre_peak_meter.clone(),
+        plugin.post_peak_meter.clone(),
+        plugin.pre_mono_compat.clone(),
+        plugin.post_mono_compat.clone(),
+        plugin.true_peak_clip.clone(),
+        plugin.limiter_meter.clone(),
+        plugin.ab_clip_control.clone(),
+        plugin.null_test_control.clone(),
+        plugin.input_stats.clone(),
+        plugin.lean_suggestion.clone(),
+        plugin.learn_result.clone(),
+        plugin.integrated_angle.clone(),
+        plugin.analyzer_input.clone(),
+        plugin.file_player.clone(),
+        plugin.file_analyzer.clone(),
+        plugin.watchdog_tripped.clone(),
+        plugin.correcting_angle.clone(),
+        plugin.metering_idle.clone(),
+        plugin.mono_input.clone(),
+        plugin.channel_delay.clone(),
+    );
+    let state = Mutex::new(EditorState::default());
+
+    move |ctx| draw(ctx, setter, &mut state.lock().unwrap())
+}
+
+/// Just the correction knob, at the top left of the screen. `rect` gets set to where it ended up
+fn correction_knob<'a>(
+    params: &'a CenteredParams,
+    setter: &'a ParamSetter,
+    rect: &'a Cell<Rect>,
+) -> impl Fn(&Context) + 'a {
+    move |ctx| {
+        Area::new(Id::new("knob"))
+            .fixed_pos(Pos2::ZERO)
+            .show(ctx, |ui| {
+                param_knob(
+                    ui,
+                    setter,
+                    &params.correction_amount,
+                    CORRECTION_AMOUNT_ID,
+                    "knob_correct_amount",
+                    "CORRECTION",
+                    KNOB_DIAMETER,
+                    1.0,
+                    &params.param_locks,
+                );
+                rect.set(ui.min_rect());
+            });
+    }
+}
+
+#[test]
+fn shows_the_main_knobs() {
+    let plugin = Centered::default();
+    let context = RecordingContext::default();
+    let setter = ParamSetter::new(&context);
+    let mut harness = Harness::new();
+
+    let text = harness.run(
+        Vec::new(),
+        Modifiers::default(),
+        editor_ui(&plugin, &setter),
+    );
+    for label in [
+        "INPUT",
+        "CORRECTION",
+        "ATTACK",
+        "RELEASE",
+        "LOOKAHEAD",
+        "OUTPUT",
+    ] {
+        assert!(
+            find_text(&text, label).is_some(),
+            "the {label} knob is missing"
+        );
+    }
+    assert!(
+        context.take_calls().is_empty(),
+        "just drawing the editor shouldn't set anything"
+    );
+}
+
+#[test]
+fn knob_drags_are_one_gesture() {
+    let params = CenteredParams::default();
+    let context = RecordingContext::default();
+    let setter = ParamSetter::new(&context);
+    let rect = Cell::new(Rect::NOTHING);
+    let mut harness = Harness::new();
+
+    harness.run(
+        Vec::new(),
+        Modifiers::default(),
+        correction_knob(&params, &setter, &rect),
+    );
+    harness.drag_up(
+        rect.get().center(),
+        correction_knob(&params, &setter, &rect),
+    );
+
+    let param = params.correction_amount.as_ptr();
+    let calls = context.take_calls();
+    assert!(calls.len() > 2, "expected a whole gesture, got {calls:?}");
+    assert_eq!(calls.first(), Some(&SetterCall::Begin(param)));
+    assert_eq!(calls.last(), Some(&SetterCall::End(param)));
+    let current = params.correction_amount.unmodulated_normalized_value();
+    for call in &calls[1..calls.len() - 1] {
+        let changes_correction =
+            matches!(call, SetterCall::Set(set, value) if *set == param && *value != current);
+        assert!(
+            changes_correction,
+            "expected only changes to the correction amount in between, got {call:?}"
+        );
+    }
+}
+
+#[test]
+fn locked_knobs_ignore_drags() {
+    let params = CenteredParams::default();
+    let context = RecordingContext::default();
+    let setter = ParamSetter::new(&context);
+    let rect = Cell::new(Rect::NOTHING);
+    let mut harness = Harness::new();
+
+    params
+        .param_locks
+        .lock(CORRECTION_AMOUNT_ID, &params.correction_amount);
+    harness.run(
+        Vec::new(),
+        Modifiers::default(),
+        correction_knob(&params, &setter, &rect),
+    );
+    harness.drag_up(
+        rect.get().center(),
+        correction_knob(&params, &setter, &rect),
+    );
+
+    assert_eq!(context.take_calls(), Vec::new());
+}
+
+#[test]
+fn about_and_debug_windows_open() {
+    let plugin = Centered::default();
+    let context = RecordingContext::default();
+    let setter = ParamSetter::new(&context);
+    let mut harness = Harness::new();
+
+    let text = harness.run(
+        Vec::new(),
+        Modifiers::default(),
+        editor_ui(&plugin, &setter),
+    );
+    let about_button = *find_text(&text, "ABOUT").expect("the ABOUT button is missing");
+    assert!(find_text(&text, "DEBUG").is_none());
+
+    let text = harness.click(
+        about_button.center(),
+        Modifiers::default(),
+        editor_ui(&plugin, &setter),
+    );
+    assert!(
+        text.iter().any(|(drawn, _)| drawn.starts_with("Version ")),
+        "clicking ABOUT should open the about window"
+    );
+
+    let text = harness.click(
+        about_button.center(),
+        Modifiers::SHIFT,
+        editor_ui(&plugin, &setter),
+    );
+    assert!(
+        find_text(&text, "DEBUG").is_some(),
+        "shift clicking ABOUT should open the debug window"
+    );
+}
@@ -0,0 +1,56 @@
+use std::time::{Duration, Instant};
+
+use nih_plug::prelude::{FloatParam, ParamSetter};
+use nih_plug_egui::egui::{vec2, Align2, Area, Context, Frame, Id};
+
+use crate::CenteredParams;
+
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+
+/// Shown for a few seconds after a knob gets reset to its default, so an accidental reset can be undone
+pub struct ResetToast {
+    label: &'static str,
+    param: fn(&CenteredParams) -> &FloatParam,
+    previous_value: f32,
+    shown_at: Instant,
+}
+
+impl ResetToast {
+    /// `previous_value` is the normalized value from before the reset
+    pub fn new(
+        label: &'static str,
+        param: fn(&CenteredParams) -> &FloatParam,
+        previous_value: f32,
+    ) -> Self {
+        Self {
+            label,
+            param,
+            previous_value,
+            shown_at: Instant::now(),
+        }
+    }
+
+    /// Shows the toast. Returns `false` once it has timed out or the reset was undone
+    pub fn show(&self, ctx: &Context, setter: &ParamSetter, params: &CenteredParams) -> bool {
+        let mut undone = false;
+
+        Area::new(Id::new("reset_toast"))
+            .anchor(Align2::CENTER_BOTTOM, vec2(0.0, -20.0))
+            .show(ctx, |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} reset to default", self.label));
+                        if ui.button("UNDO").clicked() {
+                            let param = (self.param)(params);
+                            setter.begin_set_parameter(param);
+                            setter.set_parameter_normalized(param, self.previous_value);
+                            setter.end_set_parameter(param);
+                            undone = true;
+                        }
+                    });
+                });
+            });
+
+        !undone && self.shown_at.elapsed() < TOAST_DURATION
+    }
+}
@@ -0,0 +1,595 @@
+use nih_plug::prelude::Enum;
+
+/// Estimates the angle of the stereo image over an analysis window. Angles are in degrees, where 0 is hard left, 45 is
+/// centered, and 90 is hard right
+pub trait AngleEstimator {
+    /// Starts a new analysis window
+    fn clear(&mut self);
+
+    /// Adds a frame to the analysis window. `weight` is how many frames this one stands in for
+    fn push(&mut self, left: f32, right: f32, weight: f32);
+
+    /// Returns the estimated angle, or `None` if nothing usable was pushed since the last [`clear`](Self::clear)
+    fn estimate(&mut self) -> Option<f32>;
+
+    /// Whether the last [`estimate`](Self::estimate) found an axis on the anti-phase side, which got folded back into
+    /// the 0 to 90 degree range where it looks like in-phase content. Only the estimators that keep the samples' signs
+    /// can tell
+    fn anti_phase(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EstimatorKind {
+    /// The average of every frame's angle
+    #[id = "mean"]
+    #[name = "Mean"]
+    Mean,
+    /// The average of every frame's angle, weighted by how loud the frame is
+    #[id = "weighted"]
+    #[name = "Weighted"]
+    Weighted,
+    /// The median of every frame's angle, which ignores outliers
+    #[id = "median"]
+    #[name = "Median"]
+    Median,
+    /// The average of every frame's angle, leaving out the lowest and highest tenth. Like the median this ignores
+    /// outliers, but it still averages over most of the window
+    #[id = "trimmed-mean"]
+    #[name = "Trimmed Mean"]
+    TrimmedMean,
+    /// Like the median, but at a configurable percentile of every frame's angle instead of the 50th
+    #[id = "percentile"]
+    #[name = "Percentile"]
+    Percentile,
+    /// The principal axis of the left/right covariance, which takes the correlation between the channels into account.
+    /// An axis on the anti-phase side gets flagged, see [`AngleEstimator::anti_phase`]
+    #[id = "correlation"]
+    #[name = "Correlation"]
+    Correlation,
+    /// The ratio between the channels' RMS levels over the whole window, instead of looking at every frame on its own.
+    /// This is a lot calmer on percussive material
+    #[id = "rms"]
+    #[name = "RMS"]
+    Rms,
+    /// The circular mean of every frame's signed angle. Unlike the mean, this keeps the samples' signs while averaging,
+    /// so anti-phase content pulls against in-phase content instead of passing for it. When the anti-phase content wins
+    /// out, the estimate gets flagged, see [`AngleEstimator::anti_phase`]
+    #[id = "circular"]
+    #[name = "Circular Mean"]
+    Circular,
+}
+
+/// The angle of a single frame. This throws away the signs of the samples, so anti-phase content looks the same as
+/// in-phase content
+pub fn frame_angle(left: f32, right: f32) -> f32 {
+    (right.abs() / left.abs()).atan().to_degrees()
+}
+
+/// How close to hard left or hard right the doubled angle sums can be, relative to each other, and still count as in
+/// phase. Both sit right on the seam between the in-phase and anti-phase sides, where rounding would otherwise tip a
+/// hard panned source over to the anti-phase side
+const ANTI_PHASE_SEAM_TOLERANCE: f32 = 1e-4;
+
+/// Whether a doubled axis angle with the sine and cosine sums `sin_sum` and `cos_sum` is clearly on the anti-phase
+/// side, see [`ANTI_PHASE_SEAM_TOLERANCE`]
+fn is_anti_phase(sin_sum: f32, cos_sum: f32) -> bool {
+    sin_sum < 0.0 && sin_sum.abs() > ANTI_PHASE_SEAM_TOLERANCE * cos_sum.abs()
+}
+
+/// Holds every estimator, so switching between them doesn't need to allocate
+#[derive(Default)]
+pub struct Estimators {
+    mean: MeanEstimator,
+    weighted: WeightedEstimator,
+    median: MedianEstimator,
+    trimmed_mean: TrimmedMeanEstimator,
+    percentile: PercentileEstimator,
+    correlation: CorrelationEstimator,
+    rms: RmsEstimator,
+    circular: CircularEstimator,
+}
+
+impl Estimators {
+    /// Preallocates enough room for analysis windows of up to `max_window` frames
+    pub fn initialize(&mut self, max_window: usize) {
+        self.median.initialize(max_window);
+        self.trimmed_mean.median.initialize(max_window);
+        self.percentile.median.initialize(max_window);
+    }
+
+    /// Like [`Self::initialize`], but only for `kind`. Only the estimators that keep every frame need the room, so this
+    /// skips allocating it for the others when only one of them is ever used
+    pub fn initialize_kind(&mut self, kind: EstimatorKind, max_window: usize) {
+        match kind {
+            EstimatorKind::Median => self.median.initialize(max_window),
+            EstimatorKind::TrimmedMean => self.trimmed_mean.median.initialize(max_window),
+            EstimatorKind::Percentile => self.percentile.median.initialize(max_window),
+            _ => {}
+        }
+    }
+
+    /// Sets the percentile, from 0 to 100, that [`EstimatorKind::Percentile`] picks
+    pub fn set_percentile(&mut self, percentile: f32) {
+        self.percentile.fraction = percentile / 100.0;
+    }
+
+    pub fn get(&mut self, kind: EstimatorKind) -> &mut dyn AngleEstimator {
+        match kind {
+            EstimatorKind::Mean => &mut self.mean,
+            EstimatorKind::Weighted => &mut self.weighted,
+            EstimatorKind::Median => &mut self.median,
+            EstimatorKind::TrimmedMean => &mut self.trimmed_mean,
+            EstimatorKind::Percentile => &mut self.percentile,
+            EstimatorKind::Correlation => &mut self.correlation,
+            EstimatorKind::Rms => &mut self.rms,
+            EstimatorKind::Circular => &mut self.circular,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MeanEstimator {
+    sum: f32,
+    total_weight: f32,
+}
+
+impl AngleEstimator for MeanEstimator {
+    fn clear(&mut self) {
+        self.sum = 0.0;
+        self.total_weight = 0.0;
+    }
+
+    fn push(&mut self, left: f32, right: f32, weight: f32) {
+        let angle = frame_angle(left, right);
+        if angle.is_nan() {
+            return;
+        }
+
+        self.sum = angle.mul_add(weight, self.sum);
+        self.total_weight += weight;
+    }
+
+    fn estimate(&mut self) -> Option<f32> {
+        (self.total_weight > 0.0).then(|| self.sum / self.total_weight)
+    }
+}
+
+#[derive(Default)]
+pub struct WeightedEstimator {
+    mean: MeanEstimator,
+}
+
+impl AngleEstimator for WeightedEstimator {
+    fn clear(&mut self) {
+        self.mean.clear();
+    }
+
+    fn push(&mut self, left: f32, right: f32, weight: f32) {
+        // near silent frames between transients would otherwise have as much say as the transients themselves
+        self.mean.push(left, right, weight * left.hypot(right));
+    }
+
+    fn estimate(&mut self) -> Option<f32> {
+        self.mean.estimate()
+    }
+}
+
+#[derive(Default)]
+pub struct MedianEstimator {
+    angles: Vec<(f32, f32)>,
+    total_weight: f32,
+}
+
+impl MedianEstimator {
+    fn initialize(&mut self, max_window: usize) {
+        self.angles.clear();
+        self.angles.reserve_exact(max_window);
+    }
+
+    /// The angle that `fraction` of the window's weight sits below
+    fn weighted_percentile(&mut self, fraction: f32) -> Option<f32> {
+        if self.total_weight <= 0.0 {
+            return None;
+        }
+
+        self.angles.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+        let target_weight = self.total_weight * fraction;
+        let mut cumulative_weight = 0.0;
+        self.angles
+            .iter()
+            .find_map(|&(angle, weight)| {
+                cumulative_weight += weight;
+                (cumulative_weight >= target_weight).then_some(angle)
+            })
+            // rounding can leave the last frame just short of a fraction of 1
+            .or_else(|| self.angles.last().map(|&(angle, _)| angle))
+    }
+}
+
+impl AngleEstimator for MedianEstimator {
+    fn clear(&mut self) {
+        self.angles.clear();
+        self.total_weight = 0.0;
+    }
+
+    fn push(&mut self, left: f32, right: f32, weight: f32) {
+        let angle = frame_angle(left, right);
+        // never allocate on the audio thread, if the window somehow got bigger than expected the rest just gets ignored
+        if angle.is_nan() || self.angles.len() == self.angles.capacity() {
+            return;
+        }
+
+        self.angles.push((angle, weight));
+        self.total_weight += weight;
+    }
+
+    fn estimate(&mut self) -> Option<f32> {
+        self.weighted_percentile(0.5)
+    }
+}
+
+/// Works like the median with its own [`MedianEstimator`], it only differs in which point of the sorted angles it picks
+#[derive(Default)]
+pub struct PercentileEstimator {
+    median: MedianEstimator,
+    fraction: f32,
+}
+
+impl AngleEstimator for PercentileEstimator {
+    fn clear(&mut self) {
+        self.median.clear();
+    }
+
+    fn push(&mut self, left: f32, right: f32, weight: f32) {
+        self.median.push(left, right, weight);
+    }
+
+    fn estimate(&mut self) -> Option<f32> {
+        self.median.weighted_percentile(self.fraction)
+    }
+}
+
+/// The trimmed mean needs the same sorted angles as the median, so it keeps its own [`MedianEstimator`] to collect them
+#[derive(Default)]
+pub struct TrimmedMeanEstimator {
+    median: MedianEstimator,
+}
+
+/// How much of the window's weight gets left out on either end
+const TRIM_FRACTION: f32 = 0.1;
+
+impl AngleEstimator for TrimmedMeanEstimator {
+    fn clear(&mut self) {
+        self.median.clear();
+    }
+
+    fn push(&mut self, left: f32, right: f32, weight: f32) {
+        self.median.push(left, right, weight);
+    }
+
+    fn estimate(&mut self) -> Option<f32> {
+        let total_weight = self.median.total_weight;
+        if total_weight <= 0.0 {
+            return None;
+        }
+
+        self.median
+            .angles
+            .sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+        let (low, high) = (
+            total_weight * TRIM_FRACTION,
+            total_weight * (1.0 - TRIM_FRACTION),
+        );
+        let mut cumulative_weight = 0.0;
+        let mut sum = 0.0;
+        let mut kept_weight = 0.0;
+        for &(angle, weight) in &self.median.angles {
+            // only the part of this frame's weight that falls inside the kept range counts
+            let kept = (cumulative_weight + weight).min(high) - cumulative_weight.max(low);
+            if kept > 0.0 {
+                sum = angle.mul_add(kept, sum);
+                kept_weight += kept;
+            }
+            cumulative_weight += weight;
+        }
+
+        (kept_weight > 0.0).then(|| sum / kept_weight)
+    }
+}
+
+#[derive(Default)]
+pub struct CorrelationEstimator {
+    left_energy: f32,
+    right_energy: f32,
+    cross_energy: f32,
+    anti_phase: bool,
+}
+
+impl AngleEstimator for CorrelationEstimator {
+    fn clear(&mut self) {
+        self.left_energy = 0.0;
+        self.right_energy = 0.0;
+        self.cross_energy = 0.0;
+        self.anti_phase = false;
+    }
+
+    fn push(&mut self, left: f32, right: f32, weight: f32) {
+        self.left_energy = (left * left).mul_add(weight, self.left_energy);
+        self.right_energy = (right * right).mul_add(weight, self.right_energy);
+        self.cross_energy = (left * right).mul_add(weight, self.cross_energy);
+    }
+
+    fn estimate(&mut self) -> Option<f32> {
+        self.anti_phase = false;
+        if self.left_energy + self.right_energy <= 0.0 {
+            return None;
+        }
+
+        // the principal axis ends up between -90 and 90 degrees, with anti-phase content on the negative side. folding
+        // that back keeps it in the same range as the other estimators, and the flag keeps track of which side it was
+        let (sin_sum, cos_sum) = (
+            2.0 * self.cross_energy,
+            self.left_energy - self.right_energy,
+        );
+        let axis = 0.5 * sin_sum.atan2(cos_sum).to_degrees();
+        self.anti_phase = is_anti_phase(sin_sum, cos_sum);
+
+        Some(axis.abs())
+    }
+
+    fn anti_phase(&self) -> bool {
+        self.anti_phase
+    }
+}
+
+#[derive(Default)]
+pub struct RmsEstimator {
+    left_energy: f32,
+    right_energy: f32,
+}
+
+impl AngleEstimator for RmsEstimator {
+    fn clear(&mut self) {
+        self.left_energy = 0.0;
+        self.right_energy = 0.0;
+    }
+
+    fn push(&mut self, left: f32, right: f32, weight: f32) {
+        self.left_energy = (left * left).mul_add(weight, self.left_energy);
+        self.right_energy = (right * right).mul_add(weight, self.right_energy);
+    }
+
+    fn estimate(&mut self) -> Option<f32> {
+        if self.left_energy + self.right_energy <= 0.0 {
+            return None;
+        }
+
+        // the total weight cancels out of the ratio, so there's no need to keep track of it
+        Some(
+            self.right_energy
+                .sqrt()
+                .atan2(self.left_energy.sqrt())
+                .to_degrees(),
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct CircularEstimator {
+    sin_sum: f32,
+    cos_sum: f32,
+    anti_phase: bool,
+}
+
+impl AngleEstimator for CircularEstimator {
+    fn clear(&mut self) {
+        self.sin_sum = 0.0;
+        self.cos_sum = 0.0;
+        self.anti_phase = false;
+    }
+
+    fn push(&mut self, left: f32, right: f32, weight: f32) {
+        if left == 0.0 && right == 0.0 {
+            return;
+        }
+
+        // a frame and its polarity flipped twin sit on the same axis, so the angles get doubled before averaging. that
+        // maps both of them onto the same point on the circle
+        let (sin, cos) = (2.0 * right.atan2(left)).sin_cos();
+        self.sin_sum = sin.mul_add(weight, self.sin_sum);
+        self.cos_sum = cos.mul_add(weight, self.cos_sum);
+    }
+
+    fn estimate(&mut self) -> Option<f32> {
+        self.anti_phase = false;
+        if self.sin_sum == 0.0 && self.cos_sum == 0.0 {
+            return None;
+        }
+
+        // this ends up between -90 and 90 degrees with anti-phase content on the negative side, and gets folded back
+        // into the other estimators' range and flagged the same way the correlation estimator does
+        let axis = 0.5 * self.sin_sum.atan2(self.cos_sum).to_degrees();
+        self.anti_phase = is_anti_phase(self.sin_sum, self.cos_sum);
+
+        Some(axis.abs())
+    }
+
+    fn anti_phase(&self) -> bool {
+        self.anti_phase
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [EstimatorKind; 8] = [
+        EstimatorKind::Mean,
+        EstimatorKind::Weighted,
+        EstimatorKind::Median,
+        EstimatorKind::TrimmedMean,
+        EstimatorKind::Percentile,
+        EstimatorKind::Correlation,
+        EstimatorKind::Rms,
+        EstimatorKind::Circular,
+    ];
+    /// The estimators that look at every frame's angle on its own, and so have an angle to skip
+    const FRAME_KINDS: [EstimatorKind; 5] = [
+        EstimatorKind::Mean,
+        EstimatorKind::Weighted,
+        EstimatorKind::Median,
+        EstimatorKind::TrimmedMean,
+        EstimatorKind::Percentile,
+    ];
+
+    fn estimate(kind: EstimatorKind, frames: &[(f32, f32)]) -> Option<f32> {
+        let mut estimators = Estimators::default();
+        estimators.initialize(64);
+        estimators.set_percentile(50.0);
+
+        let estimator = estimators.get(kind);
+        estimator.clear();
+        for &(left, right) in frames {
+            estimator.push(left, right, 1.0);
+        }
+        estimator.estimate()
+    }
+
+    fn assert_angle(kind: EstimatorKind, frames: &[(f32, f32)], expected: f32) {
+        let angle = estimate(kind, frames).unwrap_or_else(|| panic!("{kind:?} gave no estimate"));
+        assert!(
+            (angle - expected).abs() < 1e-3,
+            "{kind:?} estimated {angle} instead of {expected}"
+        );
+    }
+
+    #[test]
+    fn hard_left_is_0() {
+        for kind in KINDS {
+            assert_angle(kind, &[(1.0, 0.0), (0.5, 0.0), (-0.25, 0.0)], 0.0);
+        }
+    }
+
+    #[test]
+    fn centered_is_45() {
+        for kind in KINDS {
+            assert_angle(kind, &[(1.0, 1.0), (0.5, 0.5), (-0.25, -0.25)], 45.0);
+        }
+    }
+
+    #[test]
+    fn hard_right_is_90() {
+        for kind in KINDS {
+            assert_angle(kind, &[(0.0, 1.0), (0.0, 0.5), (0.0, -0.25)], 90.0);
+        }
+    }
+
+    /// Runs `frames` through `kind`, and returns the estimate along with whether it got flagged as anti-phase
+    fn estimate_with_flag(kind: EstimatorKind, frames: &[(f32, f32)]) -> (Option<f32>, bool) {
+        let mut estimators = Estimators::default();
+        let estimator = estimators.get(kind);
+        estimator.clear();
+        for &(left, right) in frames {
+            estimator.push(left, right, 1.0);
+        }
+        (estimator.estimate(), estimator.anti_phase())
+    }
+
+    #[test]
+    fn hard_panned_content_is_not_anti_phase() {
+        for kind in [EstimatorKind::Correlation, EstimatorKind::Circular] {
+            for (frames, expected) in [
+                ([(1.0, 0.0), (-0.5, 0.0), (0.25, 0.0)], 0.0),
+                ([(0.0, 1.0), (0.0, -0.5), (0.0, 0.25)], 90.0),
+            ] {
+                let (angle, anti_phase) = estimate_with_flag(kind, &frames);
+                assert!(
+                    angle.is_some_and(|angle| (angle - expected).abs() < 1e-3),
+                    "{kind:?} estimated {angle:?} instead of {expected}"
+                );
+                assert!(
+                    !anti_phase,
+                    "{kind:?} flagged content hard panned to {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn true_anti_phase_gets_flagged() {
+        for kind in [EstimatorKind::Correlation, EstimatorKind::Circular] {
+            let (angle, anti_phase) = estimate_with_flag(kind, &[(1.0, -1.0), (-0.5, 0.5)]);
+            assert!(
+                angle.is_some_and(|angle| (angle - 45.0).abs() < 1e-3),
+                "{kind:?} estimated {angle:?}"
+            );
+            assert!(anti_phase, "{kind:?} missed l = -r");
+        }
+    }
+
+    #[test]
+    fn anti_phase_gets_flagged() {
+        let mut estimators = Estimators::default();
+        for kind in [EstimatorKind::Correlation, EstimatorKind::Circular] {
+            let estimator = estimators.get(kind);
+            estimator.clear();
+            for (left, right) in [(1.0, -1.0), (-0.5, 0.5), (1.0, -0.9)] {
+                estimator.push(left, right, 1.0);
+            }
+            let angle = estimator.estimate();
+            assert!(
+                angle.is_some_and(|angle| (0.0..=90.0).contains(&angle)),
+                "{kind:?} estimated {angle:?}"
+            );
+            assert!(
+                estimator.anti_phase(),
+                "{kind:?} missed the anti-phase content"
+            );
+
+            estimator.clear();
+            estimator.push(1.0, 1.0, 1.0);
+            estimator.estimate();
+            assert!(!estimator.anti_phase(), "{kind:?} flagged in-phase content");
+        }
+    }
+
+    #[test]
+    fn empty_window_has_no_estimate() {
+        for kind in KINDS {
+            assert_eq!(estimate(kind, &[]), None, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn silent_window_has_no_estimate() {
+        for kind in KINDS {
+            assert_eq!(estimate(kind, &[(0.0, 0.0); 4]), None, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn silent_frames_are_skipped() {
+        for kind in KINDS {
+            assert_angle(
+                kind,
+                &[(0.0, 0.0), (1.0, 1.0), (0.0, 0.0), (0.5, 0.5)],
+                45.0,
+            );
+        }
+    }
+
+    #[test]
+    fn nan_frames_are_skipped() {
+        for kind in FRAME_KINDS {
+            assert_angle(
+                kind,
+                &[(f32::NAN, 1.0), (1.0, 1.0), (1.0, f32::NAN), (0.5, 0.5)],
+                45.0,
+            );
+        }
+    }
+}
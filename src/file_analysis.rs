@@ -0,0 +1,203 @@
+use std::{f32::consts::FRAC_1_SQRT_2, path::Path, sync::Mutex};
+
+use nih_plug::util::gain_to_db;
+
+use crate::{
+    estimator::{EstimatorKind, Estimators},
+    file_player::LoadedFile,
+    filter::Biquad,
+    CorrectionMode,
+};
+
+/// How long each of the windows the file gets split into for tracking how much the image moves is
+const WINDOW_SECONDS: f32 = 0.05;
+/// Channels that correlate at least this well are treated as the same source recorded twice
+const DUAL_MONO_CORRELATION: f32 = 0.8;
+/// Dual mono channels that differ by less than this don't need balancing
+const DUAL_MONO_MIN_DIFFERENCE_DB: f32 = 1.0;
+/// The whole file estimate looks at no more than this many frames, skipping evenly between them for longer files. The
+/// estimators that sort every frame would otherwise need hundreds of megabytes for a long file
+const MAX_ESTIMATE_FRAMES: usize = 1 << 20;
+/// An image that wanders less than this many degrees gets a fixed correction suggested, instead of the detector
+const MAX_STATIC_SPREAD: f32 = 2.0;
+/// Everything below this counts as rumble, which the suggested detection highpass cuts off
+const RUMBLE_HZ: f32 = 120.0;
+/// The highpass gets suggested when the rumble leans at least this many degrees differently from the rest of the file
+const MIN_RUMBLE_LEAN_DIFFERENCE: f32 = 3.0;
+/// Rumble quieter than this share of the file's energy doesn't pull the detector around enough to bother with
+const MIN_RUMBLE_ENERGY_SHARE: f64 = 0.01;
+
+/// Settings that suit the analyzed file
+pub struct Suggestions {
+    pub mode: CorrectionMode,
+    pub reaction_time_ms: f32,
+    /// A fixed correction in degrees that takes out the file's lean, positive turns the image to the right. Only
+    /// suggested when the image holds still enough for one angle to fit all of it, otherwise the detector follows it
+    pub static_angle: Option<f32>,
+    /// The frequency the detection highpass should cut at, so the detector listens to the band above it. Only
+    /// suggested when the low end leans somewhere else than the rest of the file
+    pub detection_highpass_hz: Option<f32>,
+}
+
+pub struct FileAnalysis {
+    pub name: String,
+    pub sample_rate: u32,
+    pub duration_seconds: f32,
+    pub peak_db: f32,
+    /// The angle of the whole file, relative to the center. `None` if it's silent
+    pub angle: Option<f32>,
+    /// How far the angle strays from [`Self::angle`] over time, as a standard deviation in degrees
+    pub angle_spread: f32,
+    pub suggestions: Suggestions,
+}
+
+impl FileAnalysis {
+    pub fn new(file: &LoadedFile, estimator_kind: EstimatorKind) -> Self {
+        let window_len = ((file.sample_rate as f32 * WINDOW_SECONDS) as usize).max(1);
+        // every frame that does get looked at stands in for the frames that were skipped after it
+        let decimation = file.frames.len().div_ceil(MAX_ESTIMATE_FRAMES).max(1);
+        let mut estimators = Estimators::default();
+        estimators.initialize_kind(
+            estimator_kind,
+            file.frames.len().div_ceil(decimation).max(window_len),
+        );
+
+        let sample_rate = file.sample_rate as f32;
+        let mut rumble_filters = [Biquad::default(); 2];
+        let mut rest_filters = [Biquad::default(); 2];
+        for (rumble_filter, rest_filter) in rumble_filters.iter_mut().zip(&mut rest_filters) {
+            rumble_filter.set_lowpass(sample_rate, RUMBLE_HZ, FRAC_1_SQRT_2);
+            rest_filter.set_highpass(sample_rate, RUMBLE_HZ, FRAC_1_SQRT_2);
+        }
+
+        let mut peak: f32 = 0.0;
+        let (mut left_energy, mut right_energy, mut cross_energy) = (0.0_f64, 0.0_f64, 0.0_f64);
+        let mut rumble_energy = (0.0_f64, 0.0_f64);
+        let mut rest_energy = (0.0_f64, 0.0_f64);
+        for &(left, right) in &file.frames {
+            peak = peak.max(left.abs()).max(right.abs());
+            left_energy += f64::from(left * left);
+            right_energy += f64::from(right * right);
+            cross_energy += f64::from(left * right);
+
+            let (rumble_left, rumble_right) = (
+                rumble_filters[0].process(left),
+                rumble_filters[1].process(right),
+            );
+            rumble_energy.0 += f64::from(rumble_left * rumble_left);
+            rumble_energy.1 += f64::from(rumble_right * rumble_right);
+            let (rest_left, rest_right) = (
+                rest_filters[0].process(left),
+                rest_filters[1].process(right),
+            );
+            rest_energy.0 += f64::from(rest_left * rest_left);
+            rest_energy.1 += f64::from(rest_right * rest_right);
+        }
+
+        let estimator = estimators.get(estimator_kind);
+        estimator.clear();
+        for &(left, right) in file.frames.iter().step_by(decimation) {
+            if left != 0.0 || right != 0.0 {
+                estimator.push(left, right, decimation as f32);
+            }
+        }
+        let angle = estimator.estimate().map(|angle| angle - 45.0);
+
+        let window_angles: Vec<f32> = file
+            .frames
+            .chunks(window_len)
+            .filter_map(|window| {
+                let estimator = estimators.get(estimator_kind);
+                estimator.clear();
+                for &(left, right) in window {
+                    if left != 0.0 || right != 0.0 {
+                        estimator.push(left, right, 1.0);
+                    }
+                }
+                estimator.estimate().map(|angle| angle - 45.0)
+            })
+            .collect();
+        let angle_spread = angle.map_or(0.0, |angle| {
+            let variance = window_angles
+                .iter()
+                .map(|window_angle| (window_angle - angle).powi(2))
+                .sum::<f32>()
+                / window_angles.len().max(1) as f32;
+            variance.sqrt()
+        });
+
+        let correlation = if left_energy > 0.0 && right_energy > 0.0 {
+            (cross_energy / (left_energy * right_energy).sqrt()) as f32
+        } else {
+            0.0
+        };
+        let difference_db =
+            gain_to_db((left_energy / right_energy.max(f64::MIN_POSITIVE)).sqrt() as f32);
+        let dual_mono = correlation >= DUAL_MONO_CORRELATION
+            && difference_db.abs() >= DUAL_MONO_MIN_DIFFERENCE_DB;
+
+        let static_angle = angle
+            .filter(|_| angle_spread < MAX_STATIC_SPREAD)
+            .map(|angle| (-angle).clamp(-45.0, 45.0));
+        let rumble_leans_apart = match (energy_angle(rumble_energy), energy_angle(rest_energy)) {
+            (Some(rumble_angle), Some(rest_angle)) => {
+                (rumble_angle - rest_angle).abs() >= MIN_RUMBLE_LEAN_DIFFERENCE
+            }
+            _ => false,
+        };
+        let rumble_share = (rumble_energy.0 + rumble_energy.1)
+            / (left_energy + right_energy).max(f64::MIN_POSITIVE);
+        let detection_highpass_hz =
+            (rumble_leans_apart && rumble_share >= MIN_RUMBLE_ENERGY_SHARE).then_some(RUMBLE_HZ);
+
+        Self {
+            name: file
+                .path
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+            sample_rate: file.sample_rate,
+            duration_seconds: file.frames.len() as f32 / file.sample_rate as f32,
+            peak_db: gain_to_db(peak),
+            angle,
+            angle_spread,
+            suggestions: Suggestions {
+                mode: if dual_mono {
+                    CorrectionMode::Dialogue
+                } else {
+                    CorrectionMode::Rotation
+                },
+                // an image that wanders needs a quick reaction to keep up with it, a steady one is better off with a
+                // slow reaction that won't chase every transient
+                reaction_time_ms: (25.0 - angle_spread).clamp(2.0, 25.0).round(),
+                static_angle,
+                detection_highpass_hz,
+            },
+        }
+    }
+}
+
+/// The angle in degrees of a pair of channel energies, `None` if both are silent
+fn energy_angle((left_energy, right_energy): (f64, f64)) -> Option<f32> {
+    (left_energy + right_energy > 0.0)
+        .then(|| right_energy.sqrt().atan2(left_energy.sqrt()).to_degrees() as f32)
+}
+
+/// Runs file analysis on the plugin's background task thread, and holds on to the result until the editor picks it up
+#[derive(Default)]
+pub struct FileAnalyzer {
+    result: Mutex<Option<Result<FileAnalysis, String>>>,
+}
+
+impl FileAnalyzer {
+    /// Decodes and analyzes a file. This blocks, so it only ever runs as a background task
+    pub fn run(&self, path: &Path, estimator_kind: EstimatorKind) {
+        let result = LoadedFile::open(path)
+            .map(|file| FileAnalysis::new(&file, estimator_kind))
+            .map_err(|err| format!("Couldn't open {}: {err}", path.display()));
+        *self.result.lock().unwrap() = Some(result);
+    }
+
+    pub fn take_result(&self) -> Option<Result<FileAnalysis, String>> {
+        self.result.lock().unwrap().take()
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Mutex,
+    },
+};
+
+use nih_plug::prelude::{AtomicF32, Buffer};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FilePlayerStatus {
+    #[default]
+    Idle = 0,
+    /// Looping the file through the plugin
+    Previewing = 1,
+    /// Playing the file through the plugin once while recording the output
+    Exporting = 2,
+    /// The export is done and waiting for the editor to write it to disk
+    ExportDone = 3,
+}
+
+impl From<u8> for FilePlayerStatus {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Previewing,
+            2 => Self::Exporting,
+            3 => Self::ExportDone,
+            _ => Self::Idle,
+        }
+    }
+}
+
+/// A decoded audio file, along with its corrected version while it's being exported
+pub struct LoadedFile {
+    pub path: PathBuf,
+    pub sample_rate: u32,
+    pub frames: Vec<(f32, f32)>,
+    rendered: Vec<(f32, f32)>,
+    pos: usize,
+}
+
+impl LoadedFile {
+    /// Decodes a WAV or FLAC file. Mono files get copied to both channels, and anything past the first two channels
+    /// is ignored
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+
+        let (samples, channels, sample_rate) = match extension.as_deref() {
+            Some("wav") => decode_wav(path)?,
+            Some("flac") => decode_flac(path)?,
+            _ => return Err("Only WAV and FLAC files are supported".to_string()),
+        };
+
+        let frames = samples
+            .chunks_exact(channels.max(1))
+            .map(|frame| (frame[0], *frame.get(1).unwrap_or(&frame[0])))
+            .collect();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            sample_rate,
+            frames,
+            rendered: Vec::new(),
+            pos: 0,
+        })
+    }
+}
+
+fn decode_wav(path: &Path) -> Result<(Vec<f32>, usize, u32), String> {
+    let mut reader = hound::WavReader::open(path).map_err(|err| err.to_string())?;
+    let spec = reader.spec();
+
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = ((1_i64 << (spec.bits_per_sample - 1)) as f32).recip();
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 * scale))
+                .collect()
+        }
+    }
+    .map_err(|err| err.to_string())?;
+
+    Ok((samples, spec.channels as usize, spec.sample_rate))
+}
+
+fn decode_flac(path: &Path) -> Result<(Vec<f32>, usize, u32), String> {
+    let mut reader = claxon::FlacReader::open(path).map_err(|err| err.to_string())?;
+    let info = reader.streaminfo();
+    let scale = ((1_i64 << (info.bits_per_sample - 1)) as f32).recip();
+
+    let samples = reader
+        .samples()
+        .map(|sample| sample.map(|sample| sample as f32 * scale))
+        .collect::<Result<_, _>>()
+        .map_err(|err| err.to_string())?;
+
+    Ok((samples, info.channels as usize, info.sample_rate))
+}
+
+/// Writes a corrected file as 32 bit float WAV
+pub fn write_wav(path: &Path, frames: &[(f32, f32)], sample_rate: u32) -> Result<(), String> {
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+
+    let mut writer = hound::WavWriter::create(path, spec).map_err(|err| err.to_string())?;
+    for &(left, right) in frames {
+        writer.write_sample(left).map_err(|err| err.to_string())?;
+        writer.write_sample(right).map_err(|err| err.to_string())?;
+    }
+
+    writer.finalize().map_err(|err| err.to_string())
+}
+
+/// Plays a file in place of the input, so it can be previewed and exported with correction without a DAW
+#[derive(Default)]
+pub struct FilePlayer {
+    file: Mutex<Option<LoadedFile>>,
+    status: AtomicU8,
+    progress: AtomicF32,
+}
+
+impl FilePlayer {
+    pub fn status(&self) -> FilePlayerStatus {
+        self.status.load(Ordering::Relaxed).into()
+    }
+
+    /// How far into the file playback is, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        self.progress.load(Ordering::Relaxed)
+    }
+
+    /// Replaces the loaded file, which stops whatever was playing
+    pub fn load(&self, file: LoadedFile) {
+        *self.file.lock().unwrap() = Some(file);
+        self.set_status(FilePlayerStatus::Idle);
+    }
+
+    pub fn preview(&self) {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            file.pos = 0;
+            self.set_status(FilePlayerStatus::Previewing);
+        }
+    }
+
+    pub fn export(&self) {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            file.pos = 0;
+            // the audio thread can't allocate, so the whole output needs to fit up front
+            file.rendered.clear();
+            file.rendered.reserve_exact(file.frames.len());
+            self.set_status(FilePlayerStatus::Exporting);
+        }
+    }
+
+    pub fn stop(&self) {
+        self.set_status(FilePlayerStatus::Idle);
+    }
+
+    /// Takes the corrected output and the file it came from once an export is done
+    pub fn take_export(&self) -> Option<(PathBuf, Vec<(f32, f32)>, u32)> {
+        if self.status() != FilePlayerStatus::ExportDone {
+            return None;
+        }
+
+        self.set_status(FilePlayerStatus::Idle);
+        self.file.lock().unwrap().as_mut().map(|file| {
+            (
+                file.path.clone(),
+                std::mem::take(&mut file.rendered),
+                file.sample_rate,
+            )
+        })
+    }
+
+    fn set_status(&self, status: FilePlayerStatus) {
+        self.status.store(status as u8, Ordering::Relaxed);
+    }
+
+    /// Replaces the input with the file. Call this before processing
+    pub fn read_input(&self, buffer: &mut Buffer) {
+        if !matches!(
+            self.status(),
+            FilePlayerStatus::Previewing | FilePlayerStatus::Exporting
+        ) {
+            return;
+        }
+
+        // never wait on the editor from the audio thread. if it's busy loading a file, the live input plays instead
+        let Ok(mut file) = self.file.try_lock() else {
+            return;
+        };
+        let Some(file) = file.as_mut() else {
+            return;
+        };
+
+        for (idx, mut channel_samples) in buffer.iter_samples().enumerate() {
+            let (left, right) = file.frames.get(file.pos + idx).copied().unwrap_or_default();
+            *channel_samples.get_mut(0).unwrap() = left;
+            *channel_samples.get_mut(1).unwrap() = right;
+        }
+    }
+
+    /// Records the output if we're exporting, and moves the playhead along. Call this after processing
+    pub fn write_output(&self, buffer: &mut Buffer) {
+        let status = self.status();
+        if !matches!(
+            status,
+            FilePlayerStatus::Previewing | FilePlayerStatus::Exporting
+        ) {
+            return;
+        }
+
+        let Ok(mut file) = self.file.try_lock() else {
+            return;
+        };
+        let Some(file) = file.as_mut() else {
+            return;
+        };
+
+        if status == FilePlayerStatus::Exporting {
+            for mut channel_samples in buffer.iter_samples() {
+                if file.rendered.len() < file.frames.len() {
+                    file.rendered.push((
+                        *channel_samples.get_mut(0).unwrap(),
+                        *channel_samples.get_mut(1).unwrap(),
+                    ));
+                }
+            }
+        }
+
+        file.pos += buffer.samples();
+        if file.pos >= file.frames.len() {
+            file.pos = 0;
+            if status == FilePlayerStatus::Exporting {
+                self.set_status(FilePlayerStatus::ExportDone);
+            }
+        }
+
+        self.progress.store(
+            file.pos as f32 / file.frames.len().max(1) as f32,
+            Ordering::Relaxed,
+        );
+    }
+}
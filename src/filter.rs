@@ -0,0 +1,362 @@
+use std::f32::consts::{FRAC_1_SQRT_2, TAU};
+
+use nih_plug::prelude::Enum;
+
+/// A single biquad in transposed direct form II, with coefficients from the RBJ audio EQ cookbook
+#[derive(Clone, Copy)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    s1: f32,
+    s2: f32,
+}
+
+impl Default for Biquad {
+    fn default() -> Self {
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            s1: 0.0,
+            s2: 0.0,
+        }
+    }
+}
+
+impl Biquad {
+    pub fn set_highpass(&mut self, sample_rate: f32, frequency: f32, q: f32) {
+        let (sin, cos) = (TAU * frequency / sample_rate).sin_cos();
+        let alpha = sin / (2.0 * q);
+        let a0 = 1.0 + alpha;
+
+        self.b0 = (1.0 + cos) / 2.0 / a0;
+        self.b1 = -(1.0 + cos) / a0;
+        self.b2 = self.b0;
+        self.a1 = -2.0 * cos / a0;
+        self.a2 = (1.0 - alpha) / a0;
+    }
+
+    pub fn set_lowpass(&mut self, sample_rate: f32, frequency: f32, q: f32) {
+        let (sin, cos) = (TAU * frequency / sample_rate).sin_cos();
+        let alpha = sin / (2.0 * q);
+        let a0 = 1.0 + alpha;
+
+        self.b0 = (1.0 - cos) / 2.0 / a0;
+        self.b1 = (1.0 - cos) / a0;
+        self.b2 = self.b0;
+        self.a1 = -2.0 * cos / a0;
+        self.a2 = (1.0 - alpha) / a0;
+    }
+
+    /// A bandpass with 0 dB of gain at `frequency`
+    pub fn set_bandpass(&mut self, sample_rate: f32, frequency: f32, q: f32) {
+        let (sin, cos) = (TAU * frequency / sample_rate).sin_cos();
+        let alpha = sin / (2.0 * q);
+        let a0 = 1.0 + alpha;
+
+        self.b0 = alpha / a0;
+        self.b1 = 0.0;
+        self.b2 = -alpha / a0;
+        self.a1 = -2.0 * cos / a0;
+        self.a2 = (1.0 - alpha) / a0;
+    }
+
+    pub fn set_low_shelf(&mut self, sample_rate: f32, frequency: f32, gain_db: f32) {
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let (sin, cos) = (TAU * frequency / sample_rate).sin_cos();
+        // the shelf slope is fixed at 1, which works out to a Q of 1/sqrt(2)
+        let alpha = sin / (2.0 * FRAC_1_SQRT_2);
+        let alpha_term = 2.0 * a.sqrt() * alpha;
+        let a0 = (a + 1.0) + (a - 1.0) * cos + alpha_term;
+
+        self.b0 = a * ((a + 1.0) - (a - 1.0) * cos + alpha_term) / a0;
+        self.b1 = 2.0 * a * ((a - 1.0) - (a + 1.0) * cos) / a0;
+        self.b2 = a * ((a + 1.0) - (a - 1.0) * cos - alpha_term) / a0;
+        self.a1 = -2.0 * ((a - 1.0) + (a + 1.0) * cos) / a0;
+        self.a2 = ((a + 1.0) + (a - 1.0) * cos - alpha_term) / a0;
+    }
+
+    pub fn set_high_shelf(&mut self, sample_rate: f32, frequency: f32, gain_db: f32) {
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let (sin, cos) = (TAU * frequency / sample_rate).sin_cos();
+        // the shelf slope is fixed at 1, which works out to a Q of 1/sqrt(2)
+        let alpha = sin / (2.0 * FRAC_1_SQRT_2);
+        let alpha_term = 2.0 * a.sqrt() * alpha;
+        let a0 = (a + 1.0) - (a - 1.0) * cos + alpha_term;
+
+        self.b0 = a * ((a + 1.0) + (a - 1.0) * cos + alpha_term) / a0;
+        self.b1 = -2.0 * a * ((a - 1.0) + (a + 1.0) * cos) / a0;
+        self.b2 = a * ((a + 1.0) + (a - 1.0) * cos - alpha_term) / a0;
+        self.a1 = 2.0 * ((a - 1.0) - (a + 1.0) * cos) / a0;
+        self.a2 = ((a + 1.0) - (a - 1.0) * cos - alpha_term) / a0;
+    }
+
+    /// The first stage of the BS.1770 K-weighting filter, a high shelf modelling the head's acoustic effect. The
+    /// constants are the analog prototype behind the spec's 48 kHz coefficients, so this works at any sample rate
+    pub fn set_k_weighting_shelf(&mut self, sample_rate: f32) {
+        const GAIN_DB: f32 = 3.999_843_8;
+        const FREQUENCY: f32 = 1_681.974_5;
+        const Q: f32 = 0.707_175_24;
+
+        let k = (TAU / 2.0 * FREQUENCY / sample_rate).tan();
+        let high_gain = 10.0f32.powf(GAIN_DB / 20.0);
+        let band_gain = high_gain.powf(0.499_666_78);
+        let a0 = 1.0 + k / Q + k * k;
+
+        self.b0 = (high_gain + band_gain * k / Q + k * k) / a0;
+        self.b1 = 2.0 * (k * k - high_gain) / a0;
+        self.b2 = (high_gain - band_gain * k / Q + k * k) / a0;
+        self.a1 = 2.0 * (k * k - 1.0) / a0;
+        self.a2 = (1.0 - k / Q + k * k) / a0;
+    }
+
+    /// The second stage of the BS.1770 K-weighting filter, the RLB highpass
+    pub fn set_k_weighting_highpass(&mut self, sample_rate: f32) {
+        const FREQUENCY: f32 = 38.135_47;
+        const Q: f32 = 0.500_327_04;
+
+        let k = (TAU / 2.0 * FREQUENCY / sample_rate).tan();
+        let a0 = 1.0 + k / Q + k * k;
+
+        self.b0 = 1.0;
+        self.b1 = -2.0;
+        self.b2 = 1.0;
+        self.a1 = 2.0 * (k * k - 1.0) / a0;
+        self.a2 = (1.0 - k / Q + k * k) / a0;
+    }
+
+    /// A 6 dB per octave lowpass, from the bilinear transform
+    pub fn set_first_order_lowpass(&mut self, sample_rate: f32, frequency: f32) {
+        let k = (TAU / 2.0 * frequency / sample_rate).tan();
+
+        self.b0 = k / (1.0 + k);
+        self.b1 = self.b0;
+        self.b2 = 0.0;
+        self.a1 = (k - 1.0) / (k + 1.0);
+        self.a2 = 0.0;
+    }
+
+    /// Turns the filter into a plain wire
+    pub fn set_passthrough(&mut self) {
+        *self = Self {
+            s1: self.s1,
+            s2: self.s2,
+            ..Self::default()
+        };
+    }
+
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let output = self.b0.mul_add(sample, self.s1);
+        self.s1 = self.b1.mul_add(sample, self.a1.mul_add(-output, self.s2));
+        self.s2 = self.b2.mul_add(sample, -self.a2 * output);
+
+        output
+    }
+
+    pub fn reset(&mut self) {
+        self.s1 = 0.0;
+        self.s2 = 0.0;
+    }
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectionWeighting {
+    #[id = "off"]
+    #[name = "Off"]
+    Off,
+    /// Shelves the lows and highs in opposite directions around the center frequency
+    #[id = "tilt"]
+    #[name = "Tilt"]
+    Tilt,
+    /// Only listens to an octave or so around the center frequency
+    #[id = "band-pass"]
+    #[name = "Band-pass"]
+    BandPass,
+    /// The BS.1770 loudness weighting, so the center matches the perceived loudness balance a LUFS meter would see
+    #[id = "k-weighting"]
+    #[name = "K-Weighting"]
+    KWeighting,
+}
+
+/// The shape of the detection weighting, with everything needed to calculate its coefficients
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Weighting {
+    /// Positive `tilt_db` favors the highs
+    Tilt {
+        center: f32,
+        tilt_db: f32,
+    },
+    BandPass {
+        center: f32,
+    },
+    KWeighting,
+}
+
+impl Weighting {
+    pub fn new(kind: DetectionWeighting, center: f32, tilt_db: f32) -> Option<Self> {
+        match kind {
+            DetectionWeighting::Off => None,
+            DetectionWeighting::Tilt => Some(Self::Tilt { center, tilt_db }),
+            DetectionWeighting::BandPass => Some(Self::BandPass { center }),
+            DetectionWeighting::KWeighting => Some(Self::KWeighting),
+        }
+    }
+}
+
+/// Band limits the signal feeding the angle detector. This never touches the audio itself, it only decides what the
+/// detector gets to listen to
+#[derive(Default)]
+pub struct DetectionFilter {
+    sample_rate: f32,
+    /// The current `(low, high)` cutoffs in Hz, `None` if everything passes through
+    band: Option<(f32, f32)>,
+    highpass: [Biquad; 2],
+    lowpass: [Biquad; 2],
+    /// The user's rumble filter cutoff in Hz, on top of the band. `None` if it's off
+    rumble_cutoff: Option<f32>,
+    rumble_filter: [Biquad; 2],
+    weighting: Option<Weighting>,
+    /// Two stages for each channel
+    weighting_filters: [[Biquad; 2]; 2],
+}
+
+impl DetectionFilter {
+    pub fn initialize(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        // force the coefficients to be recalculated for the new sample rate
+        let band = self.band.take();
+        self.set_band(band);
+        let rumble_cutoff = self.rumble_cutoff.take();
+        self.set_rumble_cutoff(rumble_cutoff);
+        let weighting = self.weighting.take();
+        self.set_weighting(weighting);
+        self.reset();
+    }
+
+    /// Sets the band the detector listens to. This is cheap to call every block, the coefficients only get
+    /// recalculated when the band actually changes
+    pub fn set_band(&mut self, band: Option<(f32, f32)>) {
+        if band == self.band {
+            return;
+        }
+
+        for (highpass, lowpass) in self.highpass.iter_mut().zip(self.lowpass.iter_mut()) {
+            match band {
+                Some((low, high)) => {
+                    highpass.set_highpass(self.sample_rate, low, FRAC_1_SQRT_2);
+                    // keep the lowpass below nyquist at low sample rates
+                    lowpass.set_lowpass(
+                        self.sample_rate,
+                        high.min(self.sample_rate * 0.45),
+                        FRAC_1_SQRT_2,
+                    );
+                }
+                None => {
+                    highpass.set_passthrough();
+                    lowpass.set_passthrough();
+                }
+            }
+        }
+        self.band = band;
+    }
+
+    /// Sets the highpass that keeps low end rumble out of the detector, `None` to turn it off. Like
+    /// [`Self::set_band`], this only recalculates anything when the cutoff actually changes
+    pub fn set_rumble_cutoff(&mut self, cutoff: Option<f32>) {
+        if cutoff == self.rumble_cutoff {
+            return;
+        }
+
+        for filter in &mut self.rumble_filter {
+            match cutoff {
+                Some(cutoff) => filter.set_highpass(self.sample_rate, cutoff, FRAC_1_SQRT_2),
+                None => filter.set_passthrough(),
+            }
+        }
+        self.rumble_cutoff = cutoff;
+    }
+
+    /// Shapes what the detector hears on top of the band, `None` to leave it flat. Like [`Self::set_band`], this only
+    /// recalculates anything when the weighting actually changes
+    pub fn set_weighting(&mut self, weighting: Option<Weighting>) {
+        if weighting == self.weighting {
+            return;
+        }
+
+        let nyquist_limit = self.sample_rate * 0.45;
+        for [first, second] in &mut self.weighting_filters {
+            match weighting {
+                Some(Weighting::Tilt { center, tilt_db }) => {
+                    first.set_low_shelf(
+                        self.sample_rate,
+                        center.min(nyquist_limit),
+                        -tilt_db / 2.0,
+                    );
+                    second.set_high_shelf(
+                        self.sample_rate,
+                        center.min(nyquist_limit),
+                        tilt_db / 2.0,
+                    );
+                }
+                Some(Weighting::BandPass { center }) => {
+                    first.set_bandpass(self.sample_rate, center.min(nyquist_limit), FRAC_1_SQRT_2);
+                    second.set_passthrough();
+                }
+                Some(Weighting::KWeighting) => {
+                    first.set_k_weighting_shelf(self.sample_rate);
+                    second.set_k_weighting_highpass(self.sample_rate);
+                }
+                None => {
+                    first.set_passthrough();
+                    second.set_passthrough();
+                }
+            }
+        }
+        self.weighting = weighting;
+    }
+
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let (left, right) = if self.rumble_cutoff.is_some() {
+            (
+                self.rumble_filter[0].process(left),
+                self.rumble_filter[1].process(right),
+            )
+        } else {
+            (left, right)
+        };
+        let (left, right) = if self.weighting.is_some() {
+            let [left_filters, right_filters] = &mut self.weighting_filters;
+            (
+                left_filters[1].process(left_filters[0].process(left)),
+                right_filters[1].process(right_filters[0].process(right)),
+            )
+        } else {
+            (left, right)
+        };
+        if self.band.is_none() {
+            return (left, right);
+        }
+
+        (
+            self.lowpass[0].process(self.highpass[0].process(left)),
+            self.lowpass[1].process(self.highpass[1].process(right)),
+        )
+    }
+
+    pub fn reset(&mut self) {
+        for filter in self
+            .highpass
+            .iter_mut()
+            .chain(self.lowpass.iter_mut())
+            .chain(self.rumble_filter.iter_mut())
+            .chain(self.weighting_filters.iter_mut().flatten())
+        {
+            filter.reset();
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::sync::{
+    atomic::{AtomicU8, AtomicUsize, Ordering},
+    Arc,
+};
+
+use nih_plug::{
+    params::persist::PersistentField,
+    prelude::{AtomicF32, Transport},
+    util::gain_to_db,
+};
+
+use crate::persist::{CompactCodec, Compressed};
+
+/// How many history points get recorded per second
+pub const HISTORY_RATE_HZ: usize = 50;
+pub const HISTORY_SECONDS: usize = 300;
+pub const HISTORY_LEN: usize = HISTORY_RATE_HZ * HISTORY_SECONDS;
+/// How many seconds of history the editor shows by default
+pub const DEFAULT_HISTORY_SPAN_SECONDS: f32 = 10.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TransportMarker {
+    None = 0,
+    /// The host started a new bar
+    Bar = 1,
+    /// The host jumped backwards, usually because it wrapped around a loop
+    Loop = 2,
+}
+
+impl From<u8> for TransportMarker {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Bar,
+            2 => Self::Loop,
+            _ => Self::None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct HistoryPoint {
+    angle: AtomicF32,
+    loudness: AtomicF32,
+    marker: AtomicU8,
+}
+
+/// Ring buffer of correction angle and loudness over time, shared with the editor
+pub struct History {
+    points: Vec<HistoryPoint>,
+    write_idx: AtomicUsize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            points: (0..HISTORY_LEN).map(|_| HistoryPoint::default()).collect(),
+            write_idx: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl History {
+    fn push(&self, angle: f32, loudness: f32, marker: TransportMarker) {
+        let idx = self.write_idx.load(Ordering::Relaxed);
+        let point = &self.points[idx];
+
+        point.angle.store(angle, Ordering::Relaxed);
+        point.loudness.store(loudness, Ordering::Relaxed);
+        point.marker.store(marker as u8, Ordering::Relaxed);
+
+        self.write_idx
+            .store((idx + 1) % HISTORY_LEN, Ordering::Relaxed);
+    }
+
+    /// Returns the angle (in degrees), loudness (in dBFS), and transport marker of every point, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = (f32, f32, TransportMarker)> + '_ {
+        let write_idx = self.write_idx.load(Ordering::Relaxed);
+
+        self.points[write_idx..]
+            .iter()
+            .chain(self.points[..write_idx].iter())
+            .map(|point| {
+                (
+                    point.angle.load(Ordering::Relaxed),
+                    point.loudness.load(Ordering::Relaxed),
+                    point.marker.load(Ordering::Relaxed).into(),
+                )
+            })
+    }
+}
+
+/// Every point in the [`History`] when it was saved, oldest first
+pub struct HistorySnapshot(Vec<(f32, f32, TransportMarker)>);
+
+impl CompactCodec for HistorySnapshot {
+    /// Angles and loudness are stored as deltas in hundredths of a degree or decibel, which are mostly tiny numbers
+    /// that compress really well
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&(self.0.len() as u32).to_le_bytes());
+
+        let quantize = |value: f32| (value * 100.0).round().clamp(-32768.0, 32767.0) as i16;
+        let mut last = (0, 0);
+        for &(angle, loudness, _) in &self.0 {
+            let (angle, loudness) = (quantize(angle), quantize(loudness));
+            bytes.extend_from_slice(&angle.wrapping_sub(last.0).to_le_bytes());
+            bytes.extend_from_slice(&loudness.wrapping_sub(last.1).to_le_bytes());
+            last = (angle, loudness);
+        }
+
+        bytes.extend(self.0.iter().map(|&(_, _, marker)| marker as u8));
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let (len, bytes) = bytes.split_first_chunk::<4>()?;
+        let len = u32::from_le_bytes(*len) as usize;
+        if bytes.len() != len * 5 {
+            return None;
+        }
+
+        let (deltas, markers) = bytes.split_at(len * 4);
+        let (mut angle, mut loudness) = (0_i16, 0_i16);
+        let points = deltas
+            .chunks_exact(4)
+            .zip(markers)
+            .map(|(delta, &marker)| {
+                angle = angle.wrapping_add(i16::from_le_bytes([delta[0], delta[1]]));
+                loudness = loudness.wrapping_add(i16::from_le_bytes([delta[2], delta[3]]));
+                (
+                    f32::from(angle) / 100.0,
+                    f32::from(loudness) / 100.0,
+                    marker.into(),
+                )
+            })
+            .collect();
+
+        Some(Self(points))
+    }
+}
+
+/// Saving the history with the project means the chart is still there after reopening it
+impl<'a> PersistentField<'a, Compressed<HistorySnapshot>> for Arc<History> {
+    fn set(&self, new_value: Compressed<HistorySnapshot>) {
+        let points = new_value.0 .0;
+        for &(angle, loudness, marker) in &points[points.len().saturating_sub(HISTORY_LEN)..] {
+            self.push(angle, loudness, marker);
+        }
+    }
+
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&Compressed<HistorySnapshot>) -> R,
+    {
+        f(&Compressed(HistorySnapshot(self.iter().collect())))
+    }
+}
+
+/// Audio thread side of the [`History`], which averages samples into history points and keeps track of the transport
+#[derive(Default)]
+pub struct HistoryRecorder {
+    samples_per_point: usize,
+    samples: usize,
+    sum_squares: f32,
+    pending_marker: Option<TransportMarker>,
+    last_bar: Option<i32>,
+    last_pos_samples: Option<i64>,
+}
+
+impl HistoryRecorder {
+    pub fn initialize(&mut self, sample_rate: f32) {
+        self.samples_per_point = (sample_rate / HISTORY_RATE_HZ as f32).round() as usize;
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.samples = 0;
+        self.sum_squares = 0.0;
+        self.pending_marker = None;
+        self.last_bar = None;
+        self.last_pos_samples = None;
+    }
+
+    /// Checks the transport for bar changes and loops. Call this once per block
+    pub fn update_transport(&mut self, transport: &Transport) {
+        if !transport.playing {
+            self.last_bar = None;
+            self.last_pos_samples = None;
+            return;
+        }
+
+        let pos_samples = transport.pos_samples();
+        let bar = transport.bar_number();
+
+        if let (Some(pos), Some(last_pos)) = (pos_samples, self.last_pos_samples) {
+            if pos < last_pos {
+                self.pending_marker = Some(TransportMarker::Loop);
+            }
+        }
+
+        if self.pending_marker.is_none() && self.last_bar.is_some() && bar != self.last_bar {
+            self.pending_marker = Some(TransportMarker::Bar);
+        }
+
+        self.last_bar = bar;
+        self.last_pos_samples = pos_samples;
+    }
+
+    pub fn process(&mut self, history: &History, left: f32, right: f32, angle: f32) {
+        let mid = (left + right) * 0.5;
+        self.sum_squares = mid.mul_add(mid, self.sum_squares);
+        self.samples += 1;
+
+        if self.samples >= self.samples_per_point.max(1) {
+            let rms = (self.sum_squares / self.samples as f32).sqrt();
+            history.push(
+                angle,
+                gain_to_db(rms),
+                self.pending_marker.take().unwrap_or(TransportMarker::None),
+            );
+
+            self.samples = 0;
+            self.sum_squares = 0.0;
+        }
+    }
+}
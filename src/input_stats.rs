@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use nih_plug::{
+    prelude::{AtomicF32, Buffer},
+    util::{db_to_gain, gain_to_db},
+};
+
+use crate::config::GLOBAL_CONFIG;
+
+const RMS_WINDOW_SECONDS: f32 = 3.0;
+/// Auto trim is about static level differences between the channels, so it listens for a lot longer
+const TRIM_WINDOW_SECONDS: f32 = 30.0;
+const DEFAULT_TARGET_PEAK_DB: f32 = -6.0;
+/// Suggestions smaller than this aren't worth bothering anyone with
+pub const MIN_SUGGESTION_DB: f32 = 1.0;
+
+/// Long term statistics of the (untrimmed) input level, for the gain staging advisor
+#[derive(Default)]
+pub struct InputStats {
+    peak: AtomicF32,
+    mean_square: AtomicF32,
+    left_mean_square: AtomicF32,
+    right_mean_square: AtomicF32,
+    reset_requested: AtomicBool,
+}
+
+/// Where the gain staging advisor tries to put the input's peaks
+pub fn target_peak_db() -> f32 {
+    GLOBAL_CONFIG
+        .metering
+        .target_peak_db
+        .unwrap_or(DEFAULT_TARGET_PEAK_DB)
+}
+
+impl InputStats {
+    pub fn peak_db(&self) -> f32 {
+        gain_to_db(self.peak.load(Ordering::Relaxed))
+    }
+
+    pub fn rms_db(&self) -> f32 {
+        gain_to_db(self.mean_square.load(Ordering::Relaxed).sqrt())
+    }
+
+    /// The input gain (in dB) that would put the input's peaks at [`target_peak_db`], or `None` if nothing has been
+    /// measured yet
+    pub fn suggested_gain_db(&self) -> Option<f32> {
+        let peak = self.peak.load(Ordering::Relaxed);
+        if peak < db_to_gain(-90.0) {
+            return None;
+        }
+
+        // nobody needs 0.1 dB precision for this
+        Some(((target_peak_db() - gain_to_db(peak)) * 2.0).round() / 2.0)
+    }
+
+    /// The per channel trims (in dB) that would bring both channels to the same long term loudness, split evenly
+    /// between them so the overall level stays the same. `None` if either channel hasn't had any signal yet
+    pub fn suggested_trims_db(&self) -> Option<(f32, f32)> {
+        let left_mean_square = self.left_mean_square.load(Ordering::Relaxed);
+        let right_mean_square = self.right_mean_square.load(Ordering::Relaxed);
+        let silence = db_to_gain(-90.0).powi(2);
+        if left_mean_square < silence || right_mean_square < silence {
+            return None;
+        }
+
+        let difference_db = gain_to_db((left_mean_square / right_mean_square).sqrt());
+        let trim_db = (difference_db * 5.0).round() / 10.0;
+        Some((-trim_db, trim_db))
+    }
+
+    pub fn request_reset(&self) {
+        self.reset_requested.store(true, Ordering::Relaxed);
+    }
+
+    /// Feeds a block of samples into the statistics. This is called from the audio thread
+    pub fn update(&self, buffer: &mut Buffer, sample_rate: f32) {
+        if self.reset_requested.swap(false, Ordering::Relaxed) {
+            self.peak.store(0.0, Ordering::Relaxed);
+            self.mean_square.store(0.0, Ordering::Relaxed);
+            self.left_mean_square.store(0.0, Ordering::Relaxed);
+            self.right_mean_square.store(0.0, Ordering::Relaxed);
+        }
+
+        let weight = (-(RMS_WINDOW_SECONDS * sample_rate).recip()).exp();
+        let trim_weight = (-(TRIM_WINDOW_SECONDS * sample_rate).recip()).exp();
+        let mut peak = self.peak.load(Ordering::Relaxed);
+        let mut mean_square = self.mean_square.load(Ordering::Relaxed);
+        let mut left_mean_square = self.left_mean_square.load(Ordering::Relaxed);
+        let mut right_mean_square = self.right_mean_square.load(Ordering::Relaxed);
+
+        for mut channel_samples in buffer.iter_samples() {
+            let num_channels = channel_samples.len() as f32;
+            let mut frame_square = 0.0;
+            for sample in channel_samples.iter_mut() {
+                peak = peak.max(sample.abs());
+                frame_square += *sample * *sample / num_channels;
+            }
+
+            mean_square = mean_square.mul_add(weight, frame_square * (1.0 - weight));
+
+            let left = *channel_samples.get_mut(0).unwrap();
+            let right = *channel_samples.get_mut(1).unwrap();
+            left_mean_square =
+                left_mean_square.mul_add(trim_weight, left * left * (1.0 - trim_weight));
+            right_mean_square =
+                right_mean_square.mul_add(trim_weight, right * right * (1.0 - trim_weight));
+        }
+
+        self.peak.store(peak, Ordering::Relaxed);
+        self.mean_square.store(mean_square, Ordering::Relaxed);
+        self.left_mean_square
+            .store(left_mean_square, Ordering::Relaxed);
+        self.right_mean_square
+            .store(right_mean_square, Ordering::Relaxed);
+    }
+}
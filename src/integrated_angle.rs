@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use nih_plug::prelude::{AtomicF32, Buffer};
+
+/// The output's balance integrated over everything since the last reset, the same way integrated loudness is. Louder
+/// passages count for more, and silence counts for nothing
+#[derive(Default)]
+pub struct IntegratedAngle {
+    /// Relative to the center, positive is right
+    angle: AtomicF32,
+    seconds: AtomicF32,
+    measured: AtomicBool,
+    reset_requested: AtomicBool,
+}
+
+impl IntegratedAngle {
+    /// The integrated angle (in degrees, positive is right) and how many seconds it covers. `None` if there hasn't
+    /// been any signal since the last reset
+    pub fn get(&self) -> Option<(f32, f32)> {
+        self.measured.load(Ordering::Relaxed).then(|| {
+            (
+                self.angle.load(Ordering::Relaxed),
+                self.seconds.load(Ordering::Relaxed),
+            )
+        })
+    }
+
+    pub fn request_reset(&self) {
+        self.reset_requested.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Audio thread side of the [`IntegratedAngle`]. The energy sums need more precision than the atomics can give them
+#[derive(Default)]
+pub struct AngleIntegrator {
+    left_energy: f64,
+    right_energy: f64,
+    samples: u64,
+}
+
+impl AngleIntegrator {
+    pub fn process(&mut self, buffer: &mut Buffer, sample_rate: f32, integrated: &IntegratedAngle) {
+        if integrated.reset_requested.swap(false, Ordering::Relaxed) {
+            self.left_energy = 0.0;
+            self.right_energy = 0.0;
+            self.samples = 0;
+            integrated.measured.store(false, Ordering::Relaxed);
+        }
+
+        for mut channel_samples in buffer.iter_samples() {
+            let left = *channel_samples.get_mut(0).unwrap();
+            let right = *channel_samples.get_mut(1).unwrap();
+            self.left_energy += f64::from(left * left);
+            self.right_energy += f64::from(right * right);
+        }
+        self.samples += buffer.samples() as u64;
+
+        if self.left_energy + self.right_energy > 0.0 {
+            let angle = self
+                .right_energy
+                .sqrt()
+                .atan2(self.left_energy.sqrt())
+                .to_degrees()
+                - 45.0;
+            integrated.angle.store(angle as f32, Ordering::Relaxed);
+            integrated.seconds.store(
+                (self.samples as f64 / f64::from(sample_rate)) as f32,
+                Ordering::Relaxed,
+            );
+            integrated.measured.store(true, Ordering::Relaxed);
+        }
+    }
+}
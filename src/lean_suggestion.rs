@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use nih_plug::prelude::{AtomicF32, Transport};
+
+use crate::config::GLOBAL_CONFIG;
+
+const DEFAULT_MIN_PLAYBACK_SECONDS: f32 = 30.0;
+/// Leans smaller than this aren't worth suggesting a fixed correction for
+const MIN_LEAN_DEG: f32 = 0.5;
+
+/// The average lean over the last stretch of playback, offered to the editor as a fixed correction once the transport
+/// stops
+#[derive(Default)]
+pub struct LeanSuggestion {
+    lean: AtomicF32,
+    available: AtomicBool,
+}
+
+impl LeanSuggestion {
+    /// Takes the average lean (in degrees, positive is right) if playback stopped since the last call
+    pub fn take(&self) -> Option<f32> {
+        self.available
+            .swap(false, Ordering::Relaxed)
+            .then(|| self.lean.load(Ordering::Relaxed))
+    }
+}
+
+/// Audio thread side of the [`LeanSuggestion`], which averages the detected angle while the transport is playing
+#[derive(Default)]
+pub struct LeanTracker {
+    min_playback_samples: u64,
+    was_playing: bool,
+    played_samples: u64,
+    detected_samples: u64,
+    angle_sum: f64,
+}
+
+impl LeanTracker {
+    pub fn initialize(&mut self, sample_rate: f32) {
+        let min_playback_seconds = GLOBAL_CONFIG
+            .editor
+            .lean_suggestion_seconds
+            .unwrap_or(DEFAULT_MIN_PLAYBACK_SECONDS);
+        self.min_playback_samples = (min_playback_seconds.max(0.0) * sample_rate) as u64;
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.was_playing = false;
+        self.played_samples = 0;
+        self.detected_samples = 0;
+        self.angle_sum = 0.0;
+    }
+
+    /// Call this once per block with the detector's target angle, `None` if there wasn't one
+    pub fn process(
+        &mut self,
+        transport: &Transport,
+        angle: Option<f32>,
+        num_samples: usize,
+        suggestion: &LeanSuggestion,
+    ) {
+        if !transport.playing {
+            if self.was_playing
+                && self.played_samples >= self.min_playback_samples
+                && self.detected_samples > 0
+            {
+                let lean = (self.angle_sum / self.detected_samples as f64) as f32 - 45.0;
+                if lean.abs() >= MIN_LEAN_DEG {
+                    suggestion.lean.store(lean, Ordering::Relaxed);
+                    suggestion.available.store(true, Ordering::Relaxed);
+                }
+            }
+
+            self.reset();
+            return;
+        }
+
+        self.was_playing = true;
+        self.played_samples += num_samples as u64;
+        if let Some(angle) = angle {
+            self.detected_samples += num_samples as u64;
+            self.angle_sum += f64::from(angle) * num_samples as f64;
+        }
+    }
+}
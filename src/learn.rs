@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use nih_plug::prelude::AtomicF32;
+
+/// The average lean from the last learn pass, for the editor to turn into a manual correction
+#[derive(Default)]
+pub struct LearnResult {
+    lean: AtomicF32,
+    available: AtomicBool,
+}
+
+impl LearnResult {
+    /// Takes the learned lean (in degrees, positive is right) if a learn pass finished since the last call
+    pub fn take(&self) -> Option<f32> {
+        self.available
+            .swap(false, Ordering::Relaxed)
+            .then(|| self.lean.load(Ordering::Relaxed))
+    }
+}
+
+/// Audio thread side of the [`LearnResult`], which averages the detected angle while learning is on. A pass ends when
+/// learning gets turned off, or once it's run for the learn duration if there is one
+#[derive(Default)]
+pub struct Learner {
+    was_learning: bool,
+    /// Set once the learn duration ran out, so the rest of the pass gets ignored until learning gets turned off
+    finished: bool,
+    learned_samples: u64,
+    detected_samples: u64,
+    angle_sum: f64,
+}
+
+impl Learner {
+    pub fn reset(&mut self) {
+        self.was_learning = false;
+        self.finished = false;
+        self.learned_samples = 0;
+        self.detected_samples = 0;
+        self.angle_sum = 0.0;
+    }
+
+    /// Call this once per block with the detector's target angle, `None` if there wasn't one. `duration_samples` is
+    /// `None` to keep learning for as long as `learning` stays on
+    pub fn process(
+        &mut self,
+        learning: bool,
+        duration_samples: Option<u64>,
+        angle: Option<f32>,
+        num_samples: usize,
+        result: &LearnResult,
+    ) {
+        if !learning {
+            if self.was_learning && !self.finished {
+                self.finish(result);
+            }
+
+            self.reset();
+            return;
+        }
+
+        self.was_learning = true;
+        if self.finished {
+            return;
+        }
+
+        self.learned_samples += num_samples as u64;
+        if let Some(angle) = angle {
+            self.detected_samples += num_samples as u64;
+            self.angle_sum += f64::from(angle) * num_samples as f64;
+        }
+
+        if duration_samples.is_some_and(|duration| self.learned_samples >= duration) {
+            self.finish(result);
+            self.finished = true;
+        }
+    }
+
+    /// Hands the average over to the editor, unless nothing got detected during the whole pass
+    fn finish(&self, result: &LearnResult) {
+        if self.detected_samples == 0 {
+            return;
+        }
+
+        let lean = (self.angle_sum / self.detected_samples as f64) as f32 - 45.0;
+        result.lean.store(lean, Ordering::Relaxed);
+        result.available.store(true, Ordering::Relaxed);
+    }
+}
@@ -1,23 +1,158 @@
-use editor::editor;
-use itertools::Either;
+use ab_clip::{AbClip, AbClipControl};
+use analyzer::{AnalyzerInput, DEFAULT_FFT_SIZE};
+use automation::AngleAutomation;
+use bass_mono::BassMono;
+use config::{enum_default, GLOBAL_CONFIG};
+use delay::ChannelAligner;
+use editor::{editor, KNOB_NATIVE_DRAG_DISTANCE};
+use estimator::{AngleEstimator, EstimatorKind, Estimators};
+use file_analysis::FileAnalyzer;
+use file_player::FilePlayer;
+use filter::{DetectionFilter, DetectionWeighting, Weighting};
+use history::{History, HistoryRecorder, DEFAULT_HISTORY_SPAN_SECONDS};
+use input_stats::InputStats;
+use integrated_angle::{AngleIntegrator, IntegratedAngle};
+use lean_suggestion::{LeanSuggestion, LeanTracker};
+use learn::{LearnResult, Learner};
+use limiter::{LimiterMeter, SafetyLimiter};
+use loudness::{Loudness, LoudnessMeter};
+use mono_compat::MonoCompatMeter;
+use multiband::{BandAmounts, CrossoverSettings, CrossoverSlope, MultibandCorrector};
 use nih_plug::prelude::*;
 use nih_plug_egui::EguiState;
-use std::sync::{atomic::Ordering, Arc};
+use null_test::{NullTest, NullTestControl};
+use param_locks::{
+    ParamLocks, CORRECTION_AMOUNT_ID, INPUT_GAIN_ID, LOOKAHEAD_ID, OUTPUT_GAIN_ID,
+    REACTION_TIME_ID, RELEASE_TIME_ID,
+};
+use profile::Profile;
+#[cfg(feature = "remote")]
+use remote::{
+    ContextCapturingEditor, GuiContextSlot, MeterSources, MeteringServer, RemoteUiServer,
+};
+#[cfg(feature = "scripting")]
+use script::{DetectionScript, WindowStats};
+use soft_bypass::SoftBypass;
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering},
+        Arc,
+    },
+};
+use transient::TransientDetector;
+use true_peak::TruePeakDetector;
+use vad::VoiceActivityDetector;
 
+mod ab_clip;
+mod analyzer;
+mod automation;
+mod ballistics;
+mod bass_mono;
+mod config;
+mod delay;
 mod editor;
+mod estimator;
+mod file_analysis;
+mod file_player;
+mod filter;
+mod history;
+mod input_stats;
+mod integrated_angle;
+mod lean_suggestion;
+mod learn;
+mod limiter;
+mod loudness;
+mod mono_compat;
+mod multiband;
+mod null_test;
+mod param_locks;
+mod persist;
+mod profile;
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "scripting")]
+mod script;
+mod soft_bypass;
+pub mod standalone;
+mod transient;
+mod true_peak;
+mod vad;
 
 pub const VERSION: &str = env!("VERGEN_GIT_DESCRIBE");
 
 pub const GONIO_NUM_SAMPLES: usize = 1000;
-const PEAK_METER_DECAY_MS: f64 = 150.0;
+const DEFAULT_PEAK_METER_DECAY_MS: f64 = 150.0;
 const MAX_LOOKAHEAD_MS: f32 = 10.0;
+/// How far [`CenteredParams::long_lookahead_time`] can reach
+const LONG_MAX_LOOKAHEAD_MS: f32 = 100.0;
+/// The longest [`CenteredParams::analysis_window`] can get
+const MAX_ANALYSIS_WINDOW_MS: f32 = 500.0;
+/// The correction glides for at least this long after the lookahead changes, even with snap on
+const LOOKAHEAD_CROSSFADE_MS: f32 = 5.0;
+/// Anything louder than this (+60 dBFS) coming out of the rotation means something blew up
+const WATCHDOG_MAX_AMPLITUDE: f32 = 1000.0;
+/// The speech oriented modes only listen to this band
+const SPEECH_BAND_HZ: (f32, f32) = (200.0, 4000.0);
+/// Dialogue mode rebalances slowly enough that it can't be heard working
+const DIALOGUE_REACTION_MS: f32 = 3000.0;
+/// Voice mode follows a talker moving around, but not every syllable
+const VOICE_REACTION_MS: f32 = 250.0;
+/// The speech oriented modes hold their correction through pauses quieter than this, so room tone doesn't move it
+const SPEECH_GATE_DB: f32 = -50.0;
+/// The correction holds while the detection window's correlation is below this. Rotating anti-phase material only
+/// smears it around, there's no direction for it to point in
+const ANTI_PHASE_CORRELATION: f32 = -0.5;
+/// With [`CenteredParams::mono_detection`] on, the input counts as mono while the detection window's correlation is
+/// above this and its balance is within [`MONO_ANGLE_TOLERANCE`] degrees of the center
+const MONO_CORRELATION: f32 = 0.999;
+const MONO_ANGLE_TOLERANCE: f32 = 0.5;
+/// The most either channel gets boosted or cut by when balancing
+const MAX_BALANCE_DB: f32 = 12.0;
+/// Input below this counts as silence for suspending the meters
+const METERING_SILENCE_DB: f32 = -90.0;
+/// How long the input needs to stay silent while the transport is playing before the meters get suspended. A stopped
+/// transport suspends them as soon as the input goes silent
+const METERING_IDLE_SECONDS: f32 = 2.0;
+/// How fast the detected angle has to move, in degrees per second, for the auto release to use the release time as is
+const AUTO_RELEASE_REFERENCE_SPEED: f32 = 20.0;
+/// The auto release stretches or shrinks the release time by at most this factor
+const AUTO_RELEASE_MAX_SCALE: f32 = 4.0;
+/// How long the auto release averages the detected angle's speed over
+const AUTO_RELEASE_WINDOW_SECONDS: f32 = 0.5;
+/// How spread out the detection window's angles have to be, as a standard deviation in degrees, for the adaptive
+/// reaction to use the reaction times as they are
+const ADAPTIVE_REACTION_REFERENCE_SPREAD: f32 = 15.0;
+/// The adaptive reaction stretches or shrinks the reaction times by at most this factor
+const ADAPTIVE_REACTION_MAX_SCALE: f32 = 4.0;
+/// Moves smaller than this many degrees don't have to wait out [`CenteredParams::hold_off`]
+const HOLD_OFF_TOLERANCE: f32 = 1.0;
 
 pub struct Centered {
     params: Arc<CenteredParams>,
     sample_rate: f32,
     lookahead_buffer: Vec<(f32, f32)>,
     lookahead_buffer_idx: usize,
+    /// How many more samples the correction has to glide for after a lookahead change
+    lookahead_crossfade_samples: usize,
+    /// The last [`CenteredParams::analysis_window`] of detection frames, empty while the window isn't fixed
+    analysis_buffer: Vec<(f32, f32)>,
+    analysis_buffer_idx: usize,
+    /// The current block as the detector hears it, after the detection filter
+    detection_frames: Vec<(f32, f32)>,
+    detection_filter: DetectionFilter,
+    transient_detector: TransientDetector,
+    voice_activity_detector: VoiceActivityDetector,
+    /// The latency the host was last told about, `None` if it hasn't been told anything since initializing
+    reported_latency: Option<u32>,
     correction_angle_smoother: Smoother<f32>,
+    /// The smoother's output after the slew limit, this is what actually gets corrected by
+    correction_angle: f32,
+    estimators: Estimators,
+    /// A separate set for the sidechain in [`CorrectionSource::Reference`], since the main set is busy with the input
+    reference_estimators: Estimators,
+    /// Where the sidechain sat the last time it had any signal, centered until then
+    reference_angle: f32,
     pre_stereo_data: Arc<[(AtomicF32, AtomicF32); GONIO_NUM_SAMPLES]>,
     pre_stereo_data_idx: usize,
     post_stereo_data: Arc<[(AtomicF32, AtomicF32); GONIO_NUM_SAMPLES]>,
@@ -25,7 +160,58 @@ pub struct Centered {
     pre_peak_meter: Arc<(AtomicF32, AtomicF32)>,
     post_peak_meter: Arc<(AtomicF32, AtomicF32)>,
     peak_meter_decay_weight: f32,
+    pre_mono_compat: Arc<MonoCompatMeter>,
+    post_mono_compat: Arc<MonoCompatMeter>,
+    loudness_meter: LoudnessMeter,
+    loudness: Arc<Loudness>,
+    true_peak_detectors: [TruePeakDetector; 2],
+    true_peak_clip: Arc<AtomicBool>,
+    safety_limiter: SafetyLimiter,
+    limiter_meter: Arc<LimiterMeter>,
+    history_recorder: HistoryRecorder,
+    ab_clip: AbClip,
+    ab_clip_control: Arc<AbClipControl>,
+    null_test: NullTest,
+    null_test_control: Arc<NullTestControl>,
+    soft_bypass: SoftBypass,
+    bass_mono: BassMono,
+    multiband: MultibandCorrector,
+    input_stats: Arc<InputStats>,
+    lean_tracker: LeanTracker,
+    lean_suggestion: Arc<LeanSuggestion>,
+    learner: Learner,
+    learn_result: Arc<LearnResult>,
+    angle_integrator: AngleIntegrator,
+    integrated_angle: Arc<IntegratedAngle>,
+    analyzer_input: Arc<AnalyzerInput>,
+    file_player: Arc<FilePlayer>,
+    file_analyzer: Arc<FileAnalyzer>,
+    /// Set when the watchdog had to mute the output, cleared by the editor
+    watchdog_tripped: Arc<AtomicBool>,
     correcting_angle: Arc<AtomicF32>,
+    channel_aligner: ChannelAligner,
+    /// The offset between the channels the aligner found in milliseconds, for the editor
+    channel_delay: Arc<AtomicF32>,
+    /// The transport position at the start of the last block, for catching loops and relocations
+    last_pos_samples: Option<i64>,
+    /// How many samples in a row the input has been silent for
+    silent_samples: usize,
+    /// The detected angle's recent speed in degrees per second, and where it was last block, for the auto release
+    angle_speed: f32,
+    last_target: Option<f32>,
+    /// The last target that made it through the hold-off, and how long the detected angle has been away from it
+    hold_off_target: Option<f32>,
+    hold_off_samples: usize,
+    /// Set while the meters are suspended to save CPU on idle instances, the editor stops repainting too
+    metering_idle: Arc<AtomicBool>,
+    /// Set while [`CenteredParams::mono_detection`] has the correction suspended, for the editor's indicator
+    mono_input: Arc<AtomicBool>,
+    #[cfg(feature = "remote")]
+    metering_server: Option<MeteringServer>,
+    #[cfg(feature = "remote")]
+    remote_ui_server: Option<RemoteUiServer>,
+    #[cfg(feature = "remote")]
+    gui_context: GuiContextSlot,
 }
 
 #[derive(Params)]
@@ -33,13 +219,440 @@ struct CenteredParams {
     /// The amount to correct the input by, represented as a percent
     #[id = "correction-amount"]
     pub correction_amount: FloatParam,
+    /// How quickly the correction follows the image moving away from the center. This is the attack, it kept its old
+    /// ID so existing sessions load the same
     #[id = "reaction-time"]
     pub reaction_time: FloatParam,
+    /// Scales the release with how fast the detected angle is moving, like a compressor's program dependent release
+    #[id = "auto-release"]
+    pub auto_release: BoolParam,
+    /// Leave the transients out of the detection, so hard panned percussion doesn't jerk the correction around while
+    /// the sustained parts are centered
+    #[id = "exclude-transients"]
+    pub exclude_transients: BoolParam,
+    /// Scales the attack and release with how spread out the angles in the detection window are, so a noisy estimate
+    /// gets smoothed more and a clear, steady offset gets corrected sooner
+    #[id = "adaptive-reaction"]
+    pub adaptive_reaction: BoolParam,
+    /// Use [`Self::reaction_time`] for the release as well, which is how the correction behaved before there was a
+    /// separate release
+    #[id = "release-linked"]
+    pub release_linked: BoolParam,
+    /// How quickly the correction follows the image moving back towards the center
+    #[id = "release-time"]
+    pub release_time: FloatParam,
+    /// Follow the host's tempo with [`Self::reaction_division`] instead of using [`Self::reaction_time`]
+    #[id = "reaction-sync"]
+    pub reaction_sync: BoolParam,
+    #[id = "reaction-division"]
+    pub reaction_division: EnumParam<NoteDivision>,
     #[id = "lookahead"]
     pub lookahead: FloatParam,
+    /// Uses [`Self::long_lookahead_time`] instead of [`Self::lookahead`], for offline passes where the extra latency
+    /// doesn't matter
+    #[id = "long-lookahead"]
+    pub long_lookahead: BoolParam,
+    /// The lookahead while [`Self::long_lookahead`] is on, up to [`LONG_MAX_LOOKAHEAD_MS`]. It's a parameter of its own
+    /// so the regular lookahead keeps its range for automation and saved sessions
+    #[id = "long-lookahead-time"]
+    pub long_lookahead_time: FloatParam,
+    /// A fixed rotation in degrees applied after the correction and on top of it, positive turns the image to the
+    /// right. With the correction on it turns the already corrected image, so it's for placing the result rather than
+    /// taking out a lean. [`CorrectionSource::Manual`] does that
+    #[id = "static-offset"]
+    pub static_offset: FloatParam,
+    /// Imbalances smaller than this many degrees get left alone, so a nearly centered mix doesn't get constant micro
+    /// corrections
+    #[id = "deadband"]
+    pub deadband: FloatParam,
+    /// How many degrees past [`Self::deadband`] it takes for the correction to fade in fully, 0 for a hard edge
+    #[id = "knee"]
+    pub knee: FloatParam,
+    /// The largest correction in degrees, big imbalances ease into this instead of getting corrected all the way
+    #[id = "max-correction"]
+    pub max_correction: FloatParam,
+    /// How long a new imbalance has to last before the correction starts following it, so a single loud hit off to
+    /// one side doesn't turn the whole mix
+    #[id = "hold-off"]
+    pub hold_off: FloatParam,
+    /// Caps how fast the correction can turn at [`Self::max_slew_rate`], after the smoothing, so the image never
+    /// audibly sweeps whatever the detector does
+    #[id = "slew-limit"]
+    pub slew_limit: BoolParam,
+    /// In degrees per second
+    #[id = "max-slew-rate"]
+    pub max_slew_rate: FloatParam,
+    /// The curve the correction follows on its way to the target
+    #[id = "smoothing-shape"]
+    pub smoothing_shape: EnumParam<SmoothingShape>,
+    /// Skips the reaction time smoothing entirely, so the correction jumps straight to its target
+    #[id = "snap"]
+    pub snap: BoolParam,
+    /// What the detector does when the input is silent
+    #[id = "silence-behavior"]
+    pub silence_behavior: EnumParam<SilenceBehavior>,
+    /// How long [`SilenceBehavior::Return`] takes to glide back to the center, separate from the reaction time so the
+    /// ends of phrases don't swing the image
+    #[id = "return-time"]
+    pub return_time: FloatParam,
+    /// How the angle of the input gets estimated from the analysis window
+    #[id = "estimator"]
+    pub estimator: EnumParam<EstimatorKind>,
+    /// The percentile [`EstimatorKind::Percentile`] corrects towards
+    #[id = "estimator-percentile"]
+    pub estimator_percentile: FloatParam,
+    /// Where the image gets brought to instead of the center, from hard left to hard right
+    #[id = "target-position"]
+    pub target_position: FloatParam,
+    /// Apply the opposite of the correction, which pushes the image further towards wherever it already leans
+    #[id = "inverse"]
+    pub inverse: BoolParam,
+    /// How the detected angle gets corrected
+    #[id = "mode"]
+    pub mode: EnumParam<CorrectionMode>,
+    /// Where the low band ends in [`CorrectionMode::Multiband`]
+    #[id = "multiband-low-crossover"]
+    pub multiband_low_crossover: FloatParam,
+    #[id = "multiband-low-slope"]
+    pub multiband_low_slope: EnumParam<CrossoverSlope>,
+    /// Where the high band starts in [`CorrectionMode::Multiband`]
+    #[id = "multiband-high-crossover"]
+    pub multiband_high_crossover: FloatParam,
+    #[id = "multiband-high-slope"]
+    pub multiband_high_slope: EnumParam<CrossoverSlope>,
+    #[nested(group = "Multiband")]
+    pub band_amounts: BandAmounts,
+    /// Where the correction angle comes from
+    #[id = "correction-source"]
+    pub correction_source: EnumParam<CorrectionSource>,
+    /// How far [`CorrectionSource::Manual`] turns the whole image in degrees, positive turns it to the right
+    #[id = "manual-angle"]
+    pub manual_angle: FloatParam,
+    /// Highpasses what the detector hears at [`Self::detection_highpass_frequency`], so rumble on one channel doesn't
+    /// pull the correction around. The audio itself isn't filtered
+    #[id = "detection-highpass"]
+    pub detection_highpass: BoolParam,
+    #[id = "detection-highpass-frequency"]
+    pub detection_highpass_frequency: FloatParam,
+    /// Shapes what the detector hears, so the correction follows the perceptually dominant midrange
+    #[id = "detection-weighting"]
+    pub detection_weighting: EnumParam<DetectionWeighting>,
+    #[id = "detection-weighting-frequency"]
+    pub detection_weighting_frequency: FloatParam,
+    /// How many dB the highs get over the lows in [`DetectionWeighting::Tilt`]
+    #[id = "detection-tilt"]
+    pub detection_tilt: FloatParam,
+    /// Only look at every nth sample when detecting the angle, to save CPU at high sample rates
+    #[id = "detection-decimation"]
+    pub detection_decimation: IntParam,
+    /// Detect over the last [`Self::analysis_window`] of audio instead of the lookahead or the current block, so the
+    /// detection doesn't change with the host's buffer size
+    #[id = "fixed-analysis-window"]
+    pub fixed_analysis_window: BoolParam,
+    #[id = "analysis-window"]
+    pub analysis_window: FloatParam,
+    /// Hold the correction while the detection window's correlation is below [`Self::min_correlation`], so it doesn't
+    /// chase phasey reverb tails. Anything below [`ANTI_PHASE_CORRELATION`] always holds
+    #[id = "correlation-gate"]
+    pub correlation_gate: BoolParam,
+    #[id = "min-correlation"]
+    pub min_correlation: FloatParam,
+    /// Hold the correction while the detection window is quieter than [`Self::level_gate_threshold`], so quiet
+    /// passages don't move it
+    #[id = "level-gate"]
+    pub level_gate: BoolParam,
+    #[id = "level-gate-threshold"]
+    pub level_gate_threshold: FloatParam,
+    /// Gain applied to the input before anything else happens
+    #[id = "input-gain"]
+    pub input_gain: FloatParam,
+    /// Static per channel gain, applied along with the input gain to even out a lopsided recording
+    #[id = "trim-left"]
+    pub trim_left: FloatParam,
+    #[id = "trim-right"]
+    pub trim_right: FloatParam,
+    /// Line the channels up in time before correcting, for spaced pairs that sound off center because one mic hears
+    /// the source later than the other. Rotating can't fix a timing difference
+    #[id = "align-channels"]
+    pub align_channels: BoolParam,
+    /// Gain applied to every channel after the correction, before the safety limiter
+    #[id = "output-gain"]
+    pub output_gain: FloatParam,
+    /// Detect the angle from the sidechain input instead of the main input, while still correcting the main input
+    #[id = "detect-from-sidechain"]
+    pub detect_from_sidechain: BoolParam,
+    /// Average the detected angle for as long as this is on, or for [`Self::learn_duration`], and then switch to
+    /// [`CorrectionSource::Manual`] with the result in [`Self::manual_angle`], so the learned correction stays fixed
+    /// from then on. That last part happens in the editor, so a pass that finishes while it's closed gets applied once
+    /// it opens again
+    #[id = "learn"]
+    pub learn: BoolParam,
+    /// In seconds, 0 learns for as long as [`Self::learn`] stays on
+    #[id = "learn-duration"]
+    pub learn_duration: FloatParam,
+    /// Lock the correction where it is. The detector keeps running, so the meters still show what it hears
+    #[id = "freeze"]
+    pub freeze: BoolParam,
+    /// Leave the image alone while the input is mono or identical dual mono, instead of chasing the tiny differences
+    /// between its channels. Only applies to [`CorrectionSource::Live`]
+    #[id = "mono-detection"]
+    pub mono_detection: BoolParam,
+    /// Hold the correction while the sidechain is above [`Self::sidechain_threshold`]
+    #[id = "sidechain-freeze"]
+    pub sidechain_freeze: BoolParam,
+    #[id = "sidechain-threshold"]
+    pub sidechain_threshold: FloatParam,
+    /// Folds everything below [`Self::bass_mono_frequency`] down to mono after the correction
+    #[id = "bass-mono"]
+    pub bass_mono: BoolParam,
+    #[id = "bass-mono-frequency"]
+    pub bass_mono_frequency: FloatParam,
+    #[id = "bass-mono-slope"]
+    pub bass_mono_slope: EnumParam<CrossoverSlope>,
+    /// Fades over to the untouched input, this is the plugin's bypass as far as the host is concerned
+    #[id = "bypass"]
+    pub bypass: BoolParam,
+    /// Brickwall limits the output to [`Self::limiter_ceiling`]
+    #[id = "safety-limiter"]
+    pub safety_limiter: BoolParam,
+    #[id = "limiter-ceiling"]
+    pub limiter_ceiling: FloatParam,
 
     #[persist = "editor-state"]
     pub editor_state: Arc<EguiState>,
+    /// Whether the post meters show 4x oversampled true peak instead of sample peak
+    #[persist = "true-peak-metering"]
+    pub true_peak_metering: AtomicBool,
+    #[persist = "show-meters"]
+    pub show_meters: AtomicBool,
+    #[persist = "show-controls"]
+    pub show_controls: AtomicBool,
+    #[persist = "show-history"]
+    pub show_history: AtomicBool,
+    /// Whether the scope joins consecutive samples with lines instead of drawing separate dots
+    #[persist = "scope-connected"]
+    pub scope_connected: AtomicBool,
+    /// Whether the scope highlights samples where the channels have opposite signs
+    #[persist = "scope-anti-phase"]
+    pub scope_anti_phase: AtomicBool,
+    /// Angles imported from a CSV file, for [`CorrectionSource::Imported`]
+    #[persist = "angle-automation"]
+    pub angle_automation: Arc<AngleAutomation>,
+    /// The expression for [`CorrectionSource::Script`]
+    #[cfg(feature = "scripting")]
+    #[persist = "detection-script"]
+    pub detection_script: Arc<DetectionScript>,
+    /// The correction angle and loudness history, saved with the project so the chart survives reopening it
+    #[persist = "history"]
+    pub history: Arc<History>,
+    /// How many seconds the history chart shows
+    #[persist = "history-span"]
+    pub history_span: AtomicF32,
+    /// How many pixels the knobs need to be dragged to go from their minimum to their maximum
+    #[persist = "knob-drag-distance"]
+    pub knob_drag_distance: AtomicF32,
+    /// Where the correction was when the project was saved, so reopening it picks up from there instead of sweeping
+    /// in from nothing
+    #[persist = "last-correction-angle"]
+    pub last_correction_angle: AtomicF32,
+    /// The active [`Profile`]
+    #[persist = "profile"]
+    pub profile: AtomicU8,
+    /// The spectrum analyzer's window function
+    #[persist = "analyzer-window"]
+    pub analyzer_window: AtomicU8,
+    /// The spectrum analyzer's FFT size. This is display only, so it doesn't add any latency
+    #[persist = "analyzer-fft-size"]
+    pub analyzer_fft_size: AtomicU32,
+
+    /// The knobs locked from the editor
+    #[persist = "param-locks"]
+    pub param_locks: ParamLocks,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SilenceBehavior {
+    /// Keep the last correction until there's signal again
+    #[id = "hold"]
+    #[name = "Hold"]
+    Hold,
+    /// Treat silence as centered, which pulls the correction back towards the middle
+    #[id = "center"]
+    #[name = "Bias To Center"]
+    Center,
+    /// Leave silence out like [`Self::Hold`], but glide the correction back to the center over
+    /// [`CenteredParams::return_time`] while the input stays silent or gated
+    #[id = "return"]
+    #[name = "Return To Center"]
+    Return,
+}
+
+/// The curve the correction follows towards its target
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmoothingShape {
+    #[id = "linear"]
+    #[name = "Linear"]
+    Linear,
+    /// Only works between positive angles, moves that start or end at or past hard left are linear instead
+    #[id = "logarithmic"]
+    #[name = "Logarithmic"]
+    Logarithmic,
+    /// Fast at first and then easing in, which hides slow drift corrections best
+    #[id = "exponential"]
+    #[name = "Exponential"]
+    Exponential,
+}
+
+impl SmoothingShape {
+    fn style(self, ms: f32) -> SmoothingStyle {
+        match self {
+            Self::Linear => SmoothingStyle::Linear(ms),
+            Self::Logarithmic => SmoothingStyle::Logarithmic(ms),
+            Self::Exponential => SmoothingStyle::Exponential(ms),
+        }
+    }
+}
+
+/// A tempo synced reaction time
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteDivision {
+    #[id = "sixteenth"]
+    #[name = "1/16"]
+    Sixteenth,
+    #[id = "eighth"]
+    #[name = "1/8"]
+    Eighth,
+    #[id = "quarter"]
+    #[name = "1/4"]
+    Quarter,
+    #[id = "half"]
+    #[name = "1/2"]
+    Half,
+    #[id = "bar"]
+    #[name = "1 Bar"]
+    Bar,
+}
+
+impl NoteDivision {
+    /// How long this division lasts at the host's tempo. Returns `None` if the host doesn't report a tempo
+    fn length_ms(self, transport: &Transport) -> Option<f32> {
+        let quarter_notes = match self {
+            Self::Sixteenth => 0.25,
+            Self::Eighth => 0.5,
+            Self::Quarter => 1.0,
+            Self::Half => 2.0,
+            Self::Bar => {
+                let numerator = transport.time_sig_numerator.unwrap_or(4);
+                let denominator = transport.time_sig_denominator.unwrap_or(4);
+                f64::from(numerator) * 4.0 / f64::from(denominator)
+            }
+        };
+
+        transport
+            .tempo
+            .filter(|&tempo| tempo > 0.0)
+            .map(|tempo| (quarter_notes * 60_000.0 / tempo) as f32)
+    }
+}
+
+/// Work that's too slow for the editor's thread
+pub enum CenteredTask {
+    /// Decode and analyze a file that was dropped onto the editor
+    AnalyzeFile(PathBuf, EstimatorKind),
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorrectionMode {
+    /// Rotate the stereo field until the detected angle is centered
+    #[id = "rotation"]
+    #[name = "Rotation"]
+    Rotation,
+    /// For unbalanced dual mono dialogue: slowly rebalance the channels' gains instead of rotating, only listening to
+    /// the speech band and holding through pauses
+    #[id = "dialogue"]
+    #[name = "Dialogue Balance"]
+    Dialogue,
+    /// Rotation that only listens to the speech band and ignores pauses, for centering a voice without caring where
+    /// the room tone sits
+    #[id = "voice"]
+    #[name = "Voice"]
+    Voice,
+    /// Dialogue balance for podcasts and interviews, which only listens while someone is talking. It goes by a voice
+    /// activity detector instead of the level, so room tone and breaths between sentences don't swing the balance of
+    /// a two mic recording
+    #[id = "podcast"]
+    #[name = "Podcast"]
+    Podcast,
+    /// Steers only the side channel, taking out the part of it that follows the mid. The mono sum comes out of the
+    /// correction the same as it went in, give or take a float rounding step. The static offset, the output gain, bass
+    /// mono, the safety limiter and a bypass fade can all still change it further down
+    #[id = "side-only"]
+    #[name = "Side Only"]
+    SideOnly,
+    /// Rebalances the channels' gains instead of rotating, over the full range and at the regular reaction time. This
+    /// leaves hard panned sources where they are instead of smearing them across the field
+    #[id = "balance"]
+    #[name = "Balance"]
+    Balance,
+    /// Rotation, split into bands that each get detected and corrected on their own
+    #[id = "multiband"]
+    #[name = "Multiband"]
+    Multiband,
+}
+
+impl CorrectionMode {
+    /// The band the detector listens to, `None` for the full range
+    fn detection_band(self) -> Option<(f32, f32)> {
+        match self {
+            Self::Rotation | Self::SideOnly | Self::Balance | Self::Multiband => None,
+            Self::Dialogue | Self::Voice | Self::Podcast => Some(SPEECH_BAND_HZ),
+        }
+    }
+
+    /// The reaction time this mode always uses, `None` if it follows the reaction time parameter
+    fn fixed_reaction_ms(self) -> Option<f32> {
+        match self {
+            Self::Rotation | Self::SideOnly | Self::Balance | Self::Multiband => None,
+            Self::Dialogue | Self::Podcast => Some(DIALOGUE_REACTION_MS),
+            Self::Voice => Some(VOICE_REACTION_MS),
+        }
+    }
+
+    /// Whether the correction holds while the detection window is below [`SPEECH_GATE_DB`]
+    fn gates_pauses(self) -> bool {
+        matches!(self, Self::Dialogue | Self::Voice)
+    }
+
+    /// Whether the correction holds whenever the [`VoiceActivityDetector`] doesn't hear anyone talking
+    fn gates_on_speech(self) -> bool {
+        self == Self::Podcast
+    }
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorrectionSource {
+    /// Detect the angle from the input
+    #[id = "live"]
+    #[name = "Live"]
+    Live,
+    /// Play back angles imported from a file, following the host's transport
+    #[id = "imported"]
+    #[name = "Imported"]
+    Imported,
+    /// Turn the input until it sits where the sidechain does, instead of at the center
+    #[id = "reference"]
+    #[name = "Match Sidechain"]
+    Reference,
+    /// Skip detection and the correction mode, and turn the whole image by [`CenteredParams::manual_angle`] instead.
+    /// This is a true rotation, so the plugin works as a plain rotator
+    #[id = "manual"]
+    #[name = "Manual"]
+    Manual,
+    /// Run the detected statistics through a user supplied script
+    #[cfg(feature = "scripting")]
+    #[id = "script"]
+    #[name = "Script"]
+    Script,
 }
 
 impl Default for Centered {
@@ -47,28 +660,89 @@ impl Default for Centered {
         Self {
             params: Arc::new(CenteredParams::default()),
             correction_angle_smoother: Smoother::default(),
+            correction_angle: -45.0,
+            estimators: Estimators::default(),
+            reference_estimators: Estimators::default(),
+            reference_angle: 45.0,
             sample_rate: 0.0,
             lookahead_buffer: Vec::default(),
             lookahead_buffer_idx: 0,
+            lookahead_crossfade_samples: 0,
+            analysis_buffer: Vec::new(),
+            analysis_buffer_idx: 0,
+            detection_frames: Vec::new(),
+            detection_filter: DetectionFilter::default(),
+            transient_detector: TransientDetector::default(),
+            voice_activity_detector: VoiceActivityDetector::default(),
+            reported_latency: None,
             // evil hack because AtomicF32 doesn't implement copy
             pre_stereo_data: Arc::new([0; GONIO_NUM_SAMPLES].map(|_| Default::default())),
             post_stereo_data: Arc::new([0; GONIO_NUM_SAMPLES].map(|_| Default::default())),
             pre_peak_meter: Arc::new(Default::default()),
             post_peak_meter: Arc::new(Default::default()),
             peak_meter_decay_weight: 0.0,
+            pre_mono_compat: Arc::default(),
+            post_mono_compat: Arc::default(),
+            loudness_meter: LoudnessMeter::default(),
+            loudness: Arc::default(),
+            true_peak_detectors: Default::default(),
+            true_peak_clip: Arc::default(),
+            safety_limiter: SafetyLimiter::default(),
+            limiter_meter: Arc::default(),
+            history_recorder: HistoryRecorder::default(),
+            ab_clip: AbClip::default(),
+            ab_clip_control: Arc::default(),
+            null_test: NullTest::default(),
+            null_test_control: Arc::default(),
+            soft_bypass: SoftBypass::default(),
+            bass_mono: BassMono::default(),
+            multiband: MultibandCorrector::default(),
+            input_stats: Arc::default(),
+            lean_tracker: LeanTracker::default(),
+            lean_suggestion: Arc::default(),
+            learner: Learner::default(),
+            learn_result: Arc::default(),
+            angle_integrator: AngleIntegrator::default(),
+            integrated_angle: Arc::default(),
+            analyzer_input: Arc::default(),
+            file_player: Arc::default(),
+            file_analyzer: Arc::default(),
+            watchdog_tripped: Arc::default(),
             pre_stereo_data_idx: 0,
             post_stereo_data_idx: 0,
             correcting_angle: Arc::default(),
+            channel_aligner: ChannelAligner::default(),
+            channel_delay: Arc::default(),
+            last_pos_samples: None,
+            silent_samples: 0,
+            angle_speed: 0.0,
+            last_target: None,
+            hold_off_target: None,
+            hold_off_samples: 0,
+            metering_idle: Arc::default(),
+            mono_input: Arc::default(),
+            #[cfg(feature = "remote")]
+            metering_server: None,
+            #[cfg(feature = "remote")]
+            remote_ui_server: None,
+            #[cfg(feature = "remote")]
+            gui_context: GuiContextSlot::default(),
         }
     }
 }
 
 impl Default for CenteredParams {
     fn default() -> Self {
+        let defaults = &GLOBAL_CONFIG.parameters;
+        let editor_defaults = &GLOBAL_CONFIG.editor;
+
         Self {
             correction_amount: FloatParam::new(
                 "Correction Amount",
-                100.0,
+                defaults
+                    .correction_amount
+                    .unwrap_or(100.0)
+                    .clamp(0.0, 100.0),
                 FloatRange::Linear {
                     min: 0.0,
                     max: 100.0,
@@ -79,7 +753,7 @@ impl Default for CenteredParams {
 
             reaction_time: FloatParam::new(
                 "Reaction Time",
-                5.0,
+                defaults.reaction_time.unwrap_or(5.0).clamp(0.0, 25.0),
                 FloatRange::Linear {
                     min: 0.0,
                     max: 25.0,
@@ -88,9 +762,36 @@ impl Default for CenteredParams {
             .with_unit(" ms")
             .with_step_size(0.1),
 
+            auto_release: BoolParam::new("Auto Release", false),
+
+            adaptive_reaction: BoolParam::new("Adaptive Reaction", false),
+
+            exclude_transients: BoolParam::new("Exclude Transients", false),
+
+            release_linked: BoolParam::new("Release Linked", true),
+
+            release_time: FloatParam::new(
+                "Release Time",
+                25.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 250.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1),
+
+            reaction_sync: BoolParam::new("Reaction Sync", false),
+
+            reaction_division: EnumParam::new("Reaction Division", NoteDivision::Sixteenth),
+
             lookahead: FloatParam::new(
                 "Lookahead",
-                5.0,
+                defaults
+                    .lookahead
+                    .unwrap_or(5.0)
+                    .clamp(0.0, MAX_LOOKAHEAD_MS),
                 FloatRange::Linear {
                     min: 0.0,
                     max: MAX_LOOKAHEAD_MS,
@@ -99,11 +800,381 @@ impl Default for CenteredParams {
             .with_unit(" ms")
             .with_step_size(0.1),
 
+            long_lookahead: BoolParam::new("Long Lookahead", false),
+
+            long_lookahead_time: FloatParam::new(
+                "Long Lookahead Time",
+                LONG_MAX_LOOKAHEAD_MS,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: LONG_MAX_LOOKAHEAD_MS,
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1),
+
+            static_offset: FloatParam::new(
+                "Static Offset",
+                0.0,
+                FloatRange::Linear {
+                    min: -45.0,
+                    max: 45.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_unit("°")
+            .with_step_size(0.1),
+
+            deadband: FloatParam::new(
+                "Deadband",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 10.0,
+                },
+            )
+            .with_unit("°")
+            .with_step_size(0.1),
+            knee: FloatParam::new(
+                "Knee",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 20.0,
+                },
+            )
+            .with_unit("°")
+            .with_step_size(0.1),
+            // with the easing starting at half of this, the top of the range never touches a 45 degree imbalance
+            max_correction: FloatParam::new(
+                "Max Correction",
+                90.0,
+                FloatRange::Linear {
+                    min: 1.0,
+                    max: 90.0,
+                },
+            )
+            .with_unit("°")
+            .with_step_size(0.1),
+
+            hold_off: FloatParam::new(
+                "Hold-Off",
+                0.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 2000.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(1.0),
+
+            slew_limit: BoolParam::new("Slew Limit", false),
+            max_slew_rate: FloatParam::new(
+                "Max Slew Rate",
+                10.0,
+                FloatRange::Skewed {
+                    min: 0.5,
+                    max: 180.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit("°/s")
+            .with_step_size(0.1),
+
+            smoothing_shape: EnumParam::new("Smoothing Style", SmoothingShape::Linear),
+
+            snap: BoolParam::new("Snap", defaults.snap.unwrap_or(false)),
+
+            silence_behavior: EnumParam::new(
+                "Silence Behavior",
+                enum_default(defaults.silence_behavior.as_deref(), SilenceBehavior::Hold),
+            ),
+            return_time: FloatParam::new(
+                "Return Time",
+                1000.0,
+                FloatRange::Skewed {
+                    min: 10.0,
+                    max: 10000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(1.0),
+
+            estimator: EnumParam::new(
+                "Estimator",
+                enum_default(defaults.estimator.as_deref(), EstimatorKind::Mean),
+            ),
+
+            estimator_percentile: FloatParam::new(
+                "Estimator Percentile",
+                70.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 100.0,
+                },
+            )
+            .with_unit("%")
+            .with_step_size(1.0),
+
+            target_position: FloatParam::new(
+                "Target Position",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_step_size(0.01)
+            .with_value_to_string(formatters::v2s_f32_panning())
+            .with_string_to_value(formatters::s2v_f32_panning()),
+
+            inverse: BoolParam::new("Inverse", false),
+
+            mode: EnumParam::new(
+                "Mode",
+                enum_default(defaults.mode.as_deref(), CorrectionMode::Rotation),
+            ),
+
+            multiband_low_crossover: crossover_param("Multiband Low Crossover", 200.0, 20.0, 800.0),
+            multiband_low_slope: EnumParam::new("Multiband Low Slope", CrossoverSlope::Db24),
+            multiband_high_crossover: crossover_param(
+                "Multiband High Crossover",
+                2500.0,
+                800.0,
+                16000.0,
+            ),
+            multiband_high_slope: EnumParam::new("Multiband High Slope", CrossoverSlope::Db24),
+            band_amounts: BandAmounts::default(),
+
+            correction_source: EnumParam::new("Correction Source", CorrectionSource::Live),
+            manual_angle: FloatParam::new(
+                "Manual Angle",
+                0.0,
+                FloatRange::Linear {
+                    min: -45.0,
+                    max: 45.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_unit("°")
+            .with_step_size(0.1),
+
+            detection_highpass: BoolParam::new("Detection Highpass", false),
+            detection_highpass_frequency: crossover_param(
+                "Detection Highpass Frequency",
+                80.0,
+                20.0,
+                500.0,
+            ),
+
+            detection_weighting: EnumParam::new("Detection Weighting", DetectionWeighting::Off),
+            detection_weighting_frequency: crossover_param(
+                "Detection Weighting Frequency",
+                1000.0,
+                100.0,
+                8000.0,
+            ),
+            detection_tilt: FloatParam::new(
+                "Detection Tilt",
+                0.0,
+                FloatRange::Linear {
+                    min: -12.0,
+                    max: 12.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1),
+
+            detection_decimation: IntParam::new(
+                "Detection Decimation",
+                defaults.detection_decimation.unwrap_or(1).clamp(1, 16),
+                IntRange::Linear { min: 1, max: 16 },
+            )
+            .with_unit("x"),
+
+            fixed_analysis_window: BoolParam::new("Fixed Analysis Window", false),
+            analysis_window: FloatParam::new(
+                "Analysis Window",
+                50.0,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: MAX_ANALYSIS_WINDOW_MS,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1),
+
+            correlation_gate: BoolParam::new("Correlation Gate", false),
+            min_correlation: FloatParam::new(
+                "Min Correlation",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_step_size(0.01),
+
+            level_gate: BoolParam::new("Level Gate", false),
+            level_gate_threshold: FloatParam::new(
+                "Level Gate Threshold",
+                -50.0,
+                FloatRange::Linear {
+                    min: -90.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1),
+
+            input_gain: FloatParam::new(
+                "Input Gain",
+                util::db_to_gain(defaults.input_gain.unwrap_or(0.0).clamp(-24.0, 24.0)),
+                FloatRange::Skewed {
+                    min: util::db_to_gain(-24.0),
+                    max: util::db_to_gain(24.0),
+                    factor: FloatRange::gain_skew_factor(-24.0, 24.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
+            .with_string_to_value(formatters::s2v_f32_gain_to_db()),
+
+            trim_left: trim_param("Left Trim"),
+            trim_right: trim_param("Right Trim"),
+            align_channels: BoolParam::new("Align Channels", false),
+
+            output_gain: FloatParam::new(
+                "Output Gain",
+                util::db_to_gain(0.0),
+                FloatRange::Skewed {
+                    min: util::db_to_gain(-24.0),
+                    max: util::db_to_gain(24.0),
+                    factor: FloatRange::gain_skew_factor(-24.0, 24.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
+            .with_string_to_value(formatters::s2v_f32_gain_to_db()),
+
+            detect_from_sidechain: BoolParam::new("Detect From Sidechain", false),
+
+            learn: BoolParam::new("Learn", false),
+            learn_duration: FloatParam::new(
+                "Learn Duration",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 120.0,
+                },
+            )
+            .with_unit(" s")
+            .with_step_size(1.0),
+
+            freeze: BoolParam::new("Freeze", false),
+            mono_detection: BoolParam::new("Mono Detection", true),
+
+            sidechain_freeze: BoolParam::new("Sidechain Freeze", false),
+
+            sidechain_threshold: FloatParam::new(
+                "Sidechain Threshold",
+                -30.0,
+                FloatRange::Linear {
+                    min: -60.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1),
+
+            bass_mono: BoolParam::new("Bass Mono", false),
+            bass_mono_frequency: crossover_param("Bass Mono Frequency", 120.0, 20.0, 300.0),
+            bass_mono_slope: EnumParam::new("Bass Mono Slope", CrossoverSlope::Db24),
+
+            bypass: BoolParam::new("Bypass", false).make_bypass(),
+
+            safety_limiter: BoolParam::new("Safety Limiter", false),
+
+            limiter_ceiling: FloatParam::new(
+                "Limiter Ceiling",
+                -0.3,
+                FloatRange::Linear {
+                    min: -12.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1),
+
             editor_state: EguiState::from_size(600, 480),
+            true_peak_metering: AtomicBool::new(GLOBAL_CONFIG.metering.true_peak.unwrap_or(false)),
+            show_meters: AtomicBool::new(editor_defaults.show_meters.unwrap_or(true)),
+            show_controls: AtomicBool::new(editor_defaults.show_controls.unwrap_or(true)),
+            show_history: AtomicBool::new(editor_defaults.show_history.unwrap_or(true)),
+            scope_connected: AtomicBool::new(false),
+            scope_anti_phase: AtomicBool::new(true),
+            angle_automation: Arc::default(),
+            #[cfg(feature = "scripting")]
+            detection_script: Arc::default(),
+            history: Arc::default(),
+            history_span: AtomicF32::new(
+                editor_defaults
+                    .history_span
+                    .unwrap_or(DEFAULT_HISTORY_SPAN_SECONDS),
+            ),
+            knob_drag_distance: AtomicF32::new(
+                editor_defaults
+                    .knob_drag_distance
+                    .unwrap_or(KNOB_NATIVE_DRAG_DISTANCE),
+            ),
+            // the same angle a fresh smoother starts at
+            last_correction_angle: AtomicF32::new(-45.0),
+            profile: AtomicU8::new(Profile::Custom as u8),
+            analyzer_window: AtomicU8::new(0),
+            analyzer_fft_size: AtomicU32::new(DEFAULT_FFT_SIZE as u32),
+            param_locks: ParamLocks::default(),
         }
     }
 }
 
+/// A frequency in Hz, skewed towards the low end so the knob spends most of its travel where cutoffs are usually set
+fn crossover_param(name: &str, default: f32, min: f32, max: f32) -> FloatParam {
+    FloatParam::new(
+        name,
+        default,
+        FloatRange::Skewed {
+            min,
+            max,
+            factor: FloatRange::skew_factor(-1.0),
+        },
+    )
+    .with_unit(" Hz")
+    .with_value_to_string(formatters::v2s_f32_rounded(0))
+}
+
+/// A per channel trim, displayed in dB like the input gain
+fn trim_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        util::db_to_gain(0.0),
+        FloatRange::Skewed {
+            min: util::db_to_gain(-12.0),
+            max: util::db_to_gain(12.0),
+            factor: FloatRange::gain_skew_factor(-12.0, 12.0),
+        },
+    )
+    .with_smoother(SmoothingStyle::Logarithmic(50.0))
+    .with_unit(" dB")
+    .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
+    .with_string_to_value(formatters::s2v_f32_gain_to_db())
+}
+
 impl Plugin for Centered {
     const NAME: &'static str = "Centered";
     const VENDOR: &'static str = "cozy dsp";
@@ -112,15 +1183,60 @@ impl Plugin for Centered {
 
     const VERSION: &'static str = VERSION;
 
-    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
-        main_input_channels: NonZeroU32::new(2),
-        main_output_channels: NonZeroU32::new(2),
+    // CLAP hosts get to switch between these through the audio-ports-config extension. The surround layouts only
+    // correct the front left and right channels, everything else passes through
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+
+            aux_input_ports: &[new_nonzero_u32(2)],
+            aux_output_ports: &[new_nonzero_u32(2)],
+
+            names: PortNames {
+                layout: Some("Stereo + Sidechain"),
+                aux_inputs: &["Sidechain"],
+                aux_outputs: &["Reference"],
+                ..PortNames::const_default()
+            },
+        },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
 
-        aux_input_ports: &[],
-        aux_output_ports: &[],
+            aux_input_ports: &[],
+            aux_output_ports: &[],
 
-        names: PortNames::const_default(),
-    }];
+            names: PortNames {
+                layout: Some("Stereo"),
+                ..PortNames::const_default()
+            },
+        },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(6),
+            main_output_channels: NonZeroU32::new(6),
+
+            aux_input_ports: &[],
+            aux_output_ports: &[],
+
+            names: PortNames {
+                layout: Some("5.1"),
+                ..PortNames::const_default()
+            },
+        },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(8),
+            main_output_channels: NonZeroU32::new(8),
+
+            aux_input_ports: &[],
+            aux_output_ports: &[],
+
+            names: PortNames {
+                layout: Some("7.1"),
+                ..PortNames::const_default()
+            },
+        },
+    ];
 
     const MIDI_INPUT: MidiConfig = MidiConfig::None;
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
@@ -128,52 +1244,192 @@ impl Plugin for Centered {
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
     type SysExMessage = ();
-    type BackgroundTask = ();
+    type BackgroundTask = CenteredTask;
 
     fn initialize(
         &mut self,
-        _audio_io_layout: &AudioIOLayout,
+        audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
         context: &mut impl InitContext<Self>,
     ) -> bool {
+        let peak_meter_decay_ms = GLOBAL_CONFIG
+            .metering
+            .peak_decay_ms
+            .unwrap_or(DEFAULT_PEAK_METER_DECAY_MS);
         self.peak_meter_decay_weight = 0.25f64
-            .powf((buffer_config.sample_rate as f64 * PEAK_METER_DECAY_MS / 1000.).recip())
+            .powf((buffer_config.sample_rate as f64 * peak_meter_decay_ms / 1000.).recip())
             as f32;
         self.sample_rate = buffer_config.sample_rate;
-        self.lookahead_buffer.reserve((self.sample_rate * (MAX_LOOKAHEAD_MS / 1000.0)).round() as usize);
+        self.history_recorder.initialize(self.sample_rate);
+        self.ab_clip.initialize(self.sample_rate);
+        self.null_test
+            .initialize(self.sample_rate, buffer_config.max_buffer_size as usize);
+        self.soft_bypass.initialize(
+            self.sample_rate,
+            audio_io_layout
+                .main_output_channels
+                .map_or(0, NonZeroU32::get) as usize,
+            buffer_config.max_buffer_size as usize,
+        );
+        self.bass_mono.initialize(self.sample_rate);
+        self.safety_limiter.initialize(self.sample_rate);
+        self.lean_tracker.initialize(self.sample_rate);
+        self.analyzer_input.set_sample_rate(self.sample_rate);
+        let max_lookahead_samples =
+            (self.sample_rate * (LONG_MAX_LOOKAHEAD_MS / 1000.0)).round() as usize;
+        self.lookahead_buffer.reserve(max_lookahead_samples);
+        let max_analysis_samples =
+            (self.sample_rate * (MAX_ANALYSIS_WINDOW_MS / 1000.0)).round() as usize;
+        self.analysis_buffer.reserve(max_analysis_samples);
+        self.detection_frames
+            .reserve(buffer_config.max_buffer_size as usize);
+        self.detection_filter.initialize(self.sample_rate);
+        self.channel_aligner.initialize(self.sample_rate);
+        self.voice_activity_detector.initialize(self.sample_rate);
+        self.loudness_meter.initialize(self.sample_rate);
+        self.multiband
+            .initialize(self.sample_rate, buffer_config.max_buffer_size as usize);
         self.lookahead_buffer.resize(self.get_lookahead_samples(), (0.0, 0.0));
+        self.analysis_buffer
+            .resize(self.get_analysis_window_samples(), (0.0, 0.0));
+        let max_window_samples = max_lookahead_samples
+            .max(max_analysis_samples)
+            .max(buffer_config.max_buffer_size as usize);
+        self.estimators.initialize(max_window_samples);
+        self.transient_detector
+            .initialize(self.sample_rate, max_window_samples);
+        self.reference_estimators
+            .initialize(buffer_config.max_buffer_size as usize);
+
+        self.reported_latency = None;
+        if let Some(latency) = self.take_latency_change() {
+            context.set_latency_samples(latency);
+        }
 
-        context.set_latency_samples(self.get_lookahead_samples() as u32);
+        #[cfg(feature = "remote")]
+        self.start_remote_servers();
 
         true
     }
 
     fn reset(&mut self) {
-        self.correction_angle_smoother.reset(-45.0);
+        self.correction_angle = self.params.last_correction_angle.load(Ordering::Relaxed);
+        self.correction_angle_smoother.reset(self.correction_angle);
+        self.lookahead_crossfade_samples = 0;
+        self.last_pos_samples = None;
+        self.reference_angle = 45.0;
+        self.angle_speed = 0.0;
+        self.last_target = None;
+        self.hold_off_target = None;
+        self.hold_off_samples = 0;
+        self.detection_filter.reset();
+        self.voice_activity_detector.reset();
+        self.channel_aligner.reset();
+        self.multiband.reset();
+        for detector in &mut self.true_peak_detectors {
+            detector.reset();
+        }
+        self.history_recorder.reset();
+        self.ab_clip.reset();
+        self.bass_mono.reset();
+        self.safety_limiter.reset();
+        self.soft_bypass.reset(self.params.bypass.value());
+        self.lean_tracker.reset();
+        self.learner.reset();
+        self.pre_mono_compat.reset();
+        self.post_mono_compat.reset();
+        self.loudness_meter.reset();
+        self.loudness.reset();
     }
 
     fn params(&self) -> Arc<dyn Params> {
         self.params.clone()
     }
 
-    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
-        editor(
+    fn task_executor(&mut self) -> TaskExecutor<Self> {
+        let file_analyzer = self.file_analyzer.clone();
+
+        Box::new(move |task| match task {
+            CenteredTask::AnalyzeFile(path, estimator_kind) => {
+                file_analyzer.run(&path, estimator_kind);
+            }
+        })
+    }
+
+    fn editor(&mut self, async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        let editor = editor(
             self.params.clone(),
+            async_executor,
             self.pre_stereo_data.clone(),
             self.post_stereo_data.clone(),
             self.pre_peak_meter.clone(),
             self.post_peak_meter.clone(),
+            self.pre_mono_compat.clone(),
+            self.post_mono_compat.clone(),
+            self.true_peak_clip.clone(),
+            self.limiter_meter.clone(),
+            self.ab_clip_control.clone(),
+            self.null_test_control.clone(),
+            self.input_stats.clone(),
+            self.lean_suggestion.clone(),
+            self.learn_result.clone(),
+            self.integrated_angle.clone(),
+            self.analyzer_input.clone(),
+            self.file_player.clone(),
+            self.file_analyzer.clone(),
+            self.watchdog_tripped.clone(),
             self.correcting_angle.clone(),
-        )
+            self.metering_idle.clone(),
+            self.mono_input.clone(),
+            self.channel_delay.clone(),
+        )?;
+        // the remote UI needs the GUI context the editor gets opened with to set parameters
+        #[cfg(feature = "remote")]
+        let editor = Box::new(ContextCapturingEditor {
+            inner: editor,
+            gui_context: self.gui_context.clone(),
+        });
+
+        Some(editor)
     }
 
     fn process(
         &mut self,
         buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
+        aux: &mut AuxiliaryBuffers,
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        if self.params.editor_state.is_open() {
+        self.file_player.read_input(buffer);
+        self.null_test
+            .capture_input(buffer, &self.null_test_control);
+        self.soft_bypass.capture_input(buffer);
+        self.input_stats.update(buffer, self.sample_rate);
+        self.update_metering_idle(buffer, context.transport());
+        // every pass through a loop starts from the same detector state, so changes can be compared between passes
+        if self.transport_jumped_back(context.transport()) {
+            self.reset_detection();
+        }
+
+        for mut channel_samples in buffer.iter_samples() {
+            let input_gain = self.params.param_locks.plain_value(
+                INPUT_GAIN_ID,
+                &self.params.input_gain,
+                self.params.input_gain.smoothed.next(),
+            );
+            *channel_samples.get_mut(0).unwrap() *=
+                input_gain * self.params.trim_left.smoothed.next();
+            *channel_samples.get_mut(1).unwrap() *=
+                input_gain * self.params.trim_right.smoothed.next();
+            // the trims are for the front pair, any surround channels only get the input gain
+            for sample in channel_samples.iter_mut().skip(2) {
+                *sample *= input_gain;
+            }
+        }
+
+        self.ab_clip.capture_input(buffer, &self.ab_clip_control);
+        write_reference(buffer, aux);
+
+        if self.metering_active() {
             for mut channel_samples in buffer.iter_samples() {
                 let channel_left = *channel_samples.get_mut(0).unwrap();
                 let channel_right = *channel_samples.get_mut(1).unwrap();
@@ -191,70 +1447,370 @@ impl Plugin for Centered {
                 [&self.pre_peak_meter.0, &self.pre_peak_meter.1],
                 self.peak_meter_decay_weight,
             );
-        };
+            self.pre_mono_compat.update(buffer, self.sample_rate);
 
-        if self.get_lookahead_samples() != self.lookahead_buffer.len() {
-            self.lookahead_buffer.resize(self.get_lookahead_samples(), (0.0, 0.0));
-            context.set_latency_samples(self.get_lookahead_samples() as u32);
-        }
+            self.analyzer_input.write(buffer);
+        };
 
-        if self.params.lookahead.modulated_plain_value() > 0.0 {
-            for mut sample in buffer.iter_samples() {
-                if self.lookahead_buffer_idx >= self.lookahead_buffer.len() {
-                    self.lookahead_buffer_idx = 0;
-                }
+        self.channel_aligner
+            .process(buffer, self.params.align_channels.value());
+        self.channel_delay
+            .store(self.channel_aligner.delay_ms(), Ordering::Relaxed);
 
-                self.lookahead_buffer[self.lookahead_buffer_idx] = (*sample.get_mut(0).unwrap(), *sample.get_mut(1).unwrap());
+        let lookahead_samples = self.get_lookahead_samples();
+        if lookahead_samples != self.lookahead_buffer.len() {
+            self.resize_lookahead(lookahead_samples);
+        }
+        let analysis_window_samples = self.get_analysis_window_samples();
+        if analysis_window_samples != self.analysis_buffer.len() {
+            resize_ring(
+                &mut self.analysis_buffer,
+                &mut self.analysis_buffer_idx,
+                analysis_window_samples,
+            );
+        }
+        if let Some(latency) = self.take_latency_change() {
+            context.set_latency_samples(latency);
+        }
 
-                self.lookahead_buffer_idx += 1;
+        let mode = self.params.mode.value();
+        let percentile = self.params.estimator_percentile.value();
+        self.estimators.set_percentile(percentile);
+        self.reference_estimators.set_percentile(percentile);
+        self.multiband.set_percentile(percentile);
+        self.detection_filter.set_band(mode.detection_band());
+        self.detection_filter.set_rumble_cutoff(
+            self.params
+                .detection_highpass
+                .value()
+                .then(|| self.params.detection_highpass_frequency.value()),
+        );
+        self.detection_filter.set_weighting(Weighting::new(
+            self.params.detection_weighting.value(),
+            self.params.detection_weighting_frequency.value(),
+            self.params.detection_tilt.value(),
+        ));
+        self.detection_frames.clear();
+        // without a sidechain connected, this falls back to the main input
+        match aux.inputs.first_mut() {
+            Some(sidechain) if self.params.detect_from_sidechain.value() => {
+                filter_detection_frames(
+                    sidechain,
+                    &mut self.detection_filter,
+                    &mut self.detection_frames,
+                )
             }
+            _ => filter_detection_frames(
+                buffer,
+                &mut self.detection_filter,
+                &mut self.detection_frames,
+            ),
         }
 
-        self.correction_angle_smoother.style =
-            SmoothingStyle::Linear(self.params.reaction_time.modulated_plain_value());
+        write_ring(
+            &mut self.lookahead_buffer,
+            &mut self.lookahead_buffer_idx,
+            &self.detection_frames,
+        );
+        write_ring(
+            &mut self.analysis_buffer,
+            &mut self.analysis_buffer_idx,
+            &self.detection_frames,
+        );
 
-        let t = |x: f32, y: f32| {
-            // if the input is silent, bias the pan towards the center. the math gets weird if you don't do this
-            if x == 0.0 && y == 0.0 {
-                -45.0
-            } else {
-                (y.abs() / x.abs()).atan().to_degrees()
+        let source = self.params.correction_source.value();
+        // nothing gets detected in manual mode, so there's nothing for the gates to hold back either
+        let detecting = source != CorrectionSource::Manual;
+        let detection_rms = self.detection_rms();
+        let no_speech =
+            mode.gates_on_speech() && !self.voice_activity_detector.process(&self.detection_frames);
+        let gated = detecting
+            && ((mode.gates_pauses() && detection_rms < util::db_to_gain(SPEECH_GATE_DB))
+                || no_speech
+                || (self.params.level_gate.value()
+                    && detection_rms < util::db_to_gain(self.params.level_gate_threshold.value())));
+        let min_correlation = if self.params.correlation_gate.value() {
+            self.params
+                .min_correlation
+                .value()
+                .max(ANTI_PHASE_CORRELATION)
+        } else {
+            ANTI_PHASE_CORRELATION
+        };
+        let decorrelated = self
+            .detection_correlation()
+            .is_some_and(|correlation| correlation < min_correlation);
+        let held = gated || (detecting && (decorrelated || self.sidechain_freeze_active(aux)));
+        let mono = !held
+            && source == CorrectionSource::Live
+            && self.params.mono_detection.value()
+            && self.detection_is_mono();
+        self.mono_input.store(mono, Ordering::Relaxed);
+        let target = if held {
+            None
+        } else {
+            match source {
+                CorrectionSource::Live => self.detect_angle(),
+                CorrectionSource::Imported => self.imported_angle(context.transport()),
+                CorrectionSource::Reference => self.matched_angle(aux),
+                // the output gets turned by the manual angle directly, this is only what the meters and the lean
+                // tracker go by
+                CorrectionSource::Manual => Some(45.0 + self.params.manual_angle.value()),
+                #[cfg(feature = "scripting")]
+                CorrectionSource::Script => self.scripted_angle(),
             }
         };
-
-        let iter = if self.params.lookahead.modulated_normalized_value() > 0.0 {
-            Either::Left(self.lookahead_buffer.iter().map(|(left, right)| t(*left, *right)))
+        // the live estimate flags anti-phase content it had to fold back into range. that holds the correction like
+        // the correlation gate does, instead of passing for in-phase content
+        let anti_phase = !held
+            && source == CorrectionSource::Live
+            && self
+                .estimators
+                .get(self.params.estimator.value())
+                .anti_phase();
+        let held = held || anti_phase;
+        let target = if anti_phase { None } else { target };
+        self.update_angle_speed(target, buffer.samples());
+        self.lean_tracker.process(
+            context.transport(),
+            target,
+            buffer.samples(),
+            &self.lean_suggestion,
+        );
+        let learn_seconds = self.params.learn_duration.value();
+        self.learner.process(
+            self.params.learn.value(),
+            (learn_seconds > 0.0).then(|| (learn_seconds * self.sample_rate) as u64),
+            target,
+            buffer.samples(),
+            &self.learn_result,
+        );
+        // the lean tracker above still hears what the detector heard, this only changes where the correction goes
+        let returning = self.params.silence_behavior.value() == SilenceBehavior::Return
+            && (gated || (!held && target.is_none()));
+        // mono input has nowhere to be turned to, so it goes to exactly the center instead of wherever the tiny
+        // differences between its channels point
+        let target = if returning || mono {
+            Some(45.0)
+        } else {
+            target
+        };
+        let target = target.map(|angle| {
+            45.0 + correction_curve(
+                angle - 45.0,
+                self.params.deadband.value(),
+                self.params.knee.value(),
+                self.params.max_correction.value(),
+            )
+        });
+        let target = if returning || mono {
+            target
+        } else {
+            self.apply_hold_off(target, buffer.samples())
+        };
+        let frozen = self.params.freeze.value();
+        if frozen {
+            // pins the smoother where the correction actually is, so it doesn't finish whatever glide it was on
+            self.correction_angle_smoother.reset(self.correction_angle);
+        }
+        let target = if frozen { None } else { target };
+        let smoothing_ms = match mode.fixed_reaction_ms() {
+            _ if returning => Some(self.params.return_time.value()),
+            Some(reaction_ms) => Some(reaction_ms),
+            None if self.params.snap.value() => None,
+            None => {
+                let attack_ms = self.reaction_time_ms(context.transport());
+                let attacking = target.is_some_and(|angle| {
+                    is_attack(self.correction_angle_smoother.previous_value(), angle)
+                });
+                let release_ms = if self.params.release_linked.value() {
+                    attack_ms
+                } else {
+                    self.params.param_locks.plain_value(
+                        RELEASE_TIME_ID,
+                        &self.params.release_time,
+                        self.params.release_time.modulated_plain_value(),
+                    )
+                };
+                let smoothing_ms = if attacking {
+                    attack_ms
+                } else if self.params.auto_release.value() {
+                    release_ms * self.auto_release_scale()
+                } else {
+                    release_ms
+                };
+                if self.params.adaptive_reaction.value() {
+                    Some(smoothing_ms * self.adaptive_reaction_scale())
+                } else {
+                    Some(smoothing_ms)
+                }
+            }
+        };
+        let smoothing_ms = if self.lookahead_crossfade_samples > 0 {
+            self.lookahead_crossfade_samples = self
+                .lookahead_crossfade_samples
+                .saturating_sub(buffer.samples());
+            Some(smoothing_ms.map_or(LOOKAHEAD_CROSSFADE_MS, |ms| ms.max(LOOKAHEAD_CROSSFADE_MS)))
         } else {
-            Either::Right(buffer
-                .iter_samples()
-                .map(|mut s| t(*s.get_mut(0).unwrap(), *s.get_mut(1).unwrap())))
+            smoothing_ms
         };
+        let smoothing_style = smoothing_ms.map_or(SmoothingStyle::None, |ms| {
+            self.params.smoothing_shape.value().style(ms)
+        });
+        // if there's nothing to go on, keep going towards whatever we were going towards before. The style only
+        // changes along with the target, since the smoother's steps only make sense in the style they were set up in
+        if let Some(angle) = target {
+            self.correction_angle_smoother.style = style_towards(
+                &smoothing_style,
+                self.correction_angle_smoother.previous_value(),
+                angle,
+            );
+            self.correction_angle_smoother
+                .set_target(self.sample_rate, angle);
+        }
 
-        let average_angle = iter
-            .filter(|s| !s.is_nan())
-            .zip(1..)
-            .fold(0.0_f32, |acc, (i, d)| {
-                // this never approaches 2^23 so it doesn't matter
-                acc.mul_add((d - 1) as f32, i) / d as f32
-            });
-        self.correction_angle_smoother
-            .set_target(self.sample_rate, average_angle);
+        if mode == CorrectionMode::Multiband {
+            let crossovers = CrossoverSettings {
+                low_frequency: self.params.multiband_low_crossover.value(),
+                low_slope: self.params.multiband_low_slope.value(),
+                high_frequency: self.params.multiband_high_crossover.value(),
+                high_slope: self.params.multiband_high_slope.value(),
+            };
+            self.multiband.analyze(
+                buffer,
+                &crossovers,
+                self.params.estimator.value(),
+                self.params.silence_behavior.value(),
+                smoothing_style,
+                held || frozen,
+            );
+        }
 
-        for mut channel_samples in buffer.iter_samples() {
-            #[allow(clippy::cast_precision_loss)]
-            let pan_deg = (-45.0 - self.correction_angle_smoother.next()).to_radians()
-                * self.params.correction_amount.modulated_normalized_value();
+        let max_slew_step = self
+            .params
+            .slew_limit
+            .value()
+            .then(|| self.params.max_slew_rate.value() / self.sample_rate);
+        let inverse = self.params.inverse.value();
+        let manual = source == CorrectionSource::Manual;
+        for (sample_idx, mut channel_samples) in buffer.iter_samples().enumerate() {
+            let correction_angle = self.next_correction_angle(max_slew_step);
+            let amount = self
+                .params
+                .param_locks
+                .normalized_value(CORRECTION_AMOUNT_ID, &self.params.correction_amount);
+            // the balancing modes push the other way with a negative amount
+            let signed_amount = if inverse { -amount } else { amount };
+            let target = self.params.target_position.smoothed.next() * 45.0;
+            let pan_deg = correction_pan(correction_angle, target, amount, inverse);
             self.correcting_angle
                 .store(pan_deg, std::sync::atomic::Ordering::Relaxed);
 
             let left = *channel_samples.get_mut(0).unwrap();
             let right = *channel_samples.get_mut(1).unwrap();
-            let (pan_sin, pan_cos) = pan_deg.sin_cos();
-            *channel_samples.get_mut(0).unwrap() = left.mul_add(pan_cos, -(right * pan_sin));
-            *channel_samples.get_mut(1).unwrap() = left.mul_add(-pan_sin, -(right * pan_cos));
+            match mode {
+                // `rotate` reflects the image around the correction angle, a plain rotator has to turn all of it
+                _ if manual => {
+                    let (left, right) = turn(left, right, self.params.manual_angle.smoothed.next());
+                    *channel_samples.get_mut(0).unwrap() = left;
+                    *channel_samples.get_mut(1).unwrap() = right;
+                }
+                CorrectionMode::Rotation | CorrectionMode::Voice => {
+                    let (left, right) = rotate(left, right, pan_deg);
+                    *channel_samples.get_mut(0).unwrap() = left;
+                    *channel_samples.get_mut(1).unwrap() = right;
+                }
+                CorrectionMode::Multiband => {
+                    let (left, right) = self.multiband.next_frame(
+                        sample_idx,
+                        target,
+                        amount,
+                        self.params.band_amounts.values(&self.params.param_locks),
+                        inverse,
+                    );
+                    *channel_samples.get_mut(0).unwrap() = left;
+                    *channel_samples.get_mut(1).unwrap() = right;
+                }
+                CorrectionMode::Dialogue | CorrectionMode::Podcast | CorrectionMode::Balance => {
+                    let (left_gain, right_gain) =
+                        balance_gains(correction_angle, target, signed_amount);
+                    *channel_samples.get_mut(0).unwrap() = left * left_gain;
+                    *channel_samples.get_mut(1).unwrap() = right * right_gain;
+                }
+                CorrectionMode::SideOnly => {
+                    let steering = side_steering(correction_angle, target, signed_amount);
+                    let (left, right) = steer_side(left, right, steering);
+                    *channel_samples.get_mut(0).unwrap() = left;
+                    *channel_samples.get_mut(1).unwrap() = right;
+                }
+            }
+
+            let offset = self.params.static_offset.smoothed.next();
+            if offset != 0.0 {
+                let (left, right) = turn(
+                    *channel_samples.get_mut(0).unwrap(),
+                    *channel_samples.get_mut(1).unwrap(),
+                    offset,
+                );
+                *channel_samples.get_mut(0).unwrap() = left;
+                *channel_samples.get_mut(1).unwrap() = right;
+            }
+
+            let output_gain = self.params.param_locks.plain_value(
+                OUTPUT_GAIN_ID,
+                &self.params.output_gain,
+                self.params.output_gain.smoothed.next(),
+            );
+            for sample in channel_samples.iter_mut() {
+                *sample *= output_gain;
+            }
+        }
+        self.params
+            .last_correction_angle
+            .store(self.correction_angle, Ordering::Relaxed);
+
+        if self.params.bass_mono.value() {
+            self.bass_mono.process(
+                buffer,
+                self.params.bass_mono_frequency.value(),
+                self.params.bass_mono_slope.value(),
+            );
         }
 
-        if self.params.editor_state.is_open() {
+        self.run_watchdog(buffer);
+
+        if self.params.safety_limiter.value() {
+            self.safety_limiter.process(
+                buffer,
+                util::db_to_gain(self.params.limiter_ceiling.value()),
+                &self.limiter_meter,
+            );
+        } else {
+            self.safety_limiter.bypass(&self.limiter_meter);
+        }
+
+        self.soft_bypass
+            .process_output(buffer, self.params.bypass.value());
+
+        self.angle_integrator
+            .process(buffer, self.sample_rate, &self.integrated_angle);
+        self.null_test
+            .process_output(buffer, &self.null_test_control);
+
+        self.file_player.write_output(buffer);
+
+        self.ab_clip.process_output(buffer, &self.ab_clip_control);
+
+        if self.metering_active() {
+            // same conversion the editor does for the correction arc
+            let display_angle = self.correcting_angle.load(Ordering::Relaxed).to_degrees()
+                + 90.0
+                    * self
+                        .params
+                        .param_locks
+                        .normalized_value(CORRECTION_AMOUNT_ID, &self.params.correction_amount);
+            self.history_recorder.update_transport(context.transport());
+
             for mut channel_samples in buffer.iter_samples() {
                 let channel_left = *channel_samples.get_mut(0).unwrap();
                 let channel_right = *channel_samples.get_mut(1).unwrap();
@@ -265,13 +1821,35 @@ impl Plugin for Centered {
     
                 self.post_stereo_data_idx += 1;
                 self.post_stereo_data_idx %= GONIO_NUM_SAMPLES - 1;
+
+                self.history_recorder.process(
+                    &self.params.history,
+                    channel_left,
+                    channel_right,
+                    display_angle,
+                );
             }
     
-            calc_peak(
-                buffer,
-                [&self.post_peak_meter.0, &self.post_peak_meter.1],
-                self.peak_meter_decay_weight,
-            );
+            if self.params.true_peak_metering.load(Ordering::Relaxed) {
+                let true_peak = calc_true_peak(
+                    buffer,
+                    &mut self.true_peak_detectors,
+                    [&self.post_peak_meter.0, &self.post_peak_meter.1],
+                    self.peak_meter_decay_weight,
+                );
+
+                if true_peak > 1.0 {
+                    self.true_peak_clip.store(true, Ordering::Relaxed);
+                }
+            } else {
+                calc_peak(
+                    buffer,
+                    [&self.post_peak_meter.0, &self.post_peak_meter.1],
+                    self.peak_meter_decay_weight,
+                );
+            }
+            self.post_mono_compat.update(buffer, self.sample_rate);
+            self.loudness_meter.process(buffer, &self.loudness);
         }
 
         ProcessStatus::Normal
@@ -279,8 +1857,685 @@ impl Plugin for Centered {
 }
 
 impl Centered {
+    /// Estimates the input's angle from the [`Self::detection_window`]. Returns `None` if everything was silent
+    fn detect_angle(&mut self) -> Option<f32> {
+        let silence_behavior = self.params.silence_behavior.value();
+        let estimator = self.estimators.get(self.params.estimator.value());
+        estimator.clear();
+
+        // every frame that does get looked at stands in for the frames that were skipped after it
+        let decimation = self.params.detection_decimation.value() as usize;
+        let weight =
+            |idx: usize, num_frames: usize| (num_frames - idx * decimation).min(decimation) as f32;
+
+        // same as `detection_window()`, which can't be called while the estimator is borrowed. The rings' oldest
+        // frame sits where the next one gets written
+        let (window, start) = if !self.analysis_buffer.is_empty() {
+            (&self.analysis_buffer, self.analysis_buffer_idx)
+        } else if self.lookahead_buffer.is_empty() {
+            (&self.detection_frames, 0)
+        } else {
+            (&self.lookahead_buffer, self.lookahead_buffer_idx)
+        };
+        let exclude_transients = self.params.exclude_transients.value();
+        if exclude_transients {
+            self.transient_detector.detect(window, start);
+        }
+        for (idx, (left, right)) in window.iter().step_by(decimation).enumerate() {
+            if exclude_transients && self.transient_detector.is_transient(idx * decimation) {
+                continue;
+            }
+
+            push_detection_frame(
+                estimator,
+                silence_behavior,
+                *left,
+                *right,
+                weight(idx, window.len()),
+            );
+        }
+
+        estimator.estimate()
+    }
+
+    /// Resizes the lookahead ring buffer with [`resize_ring`], and glides the correction over the change
+    fn resize_lookahead(&mut self, len: usize) {
+        resize_ring(
+            &mut self.lookahead_buffer,
+            &mut self.lookahead_buffer_idx,
+            len,
+        );
+        self.lookahead_crossfade_samples =
+            (self.sample_rate * LOOKAHEAD_CROSSFADE_MS / 1000.0).round() as usize;
+    }
+
+    /// The frames the detector looks at: the fixed analysis window if there is one, otherwise the lookahead buffer, or
+    /// the current block if there's no lookahead either
+    fn detection_window(&self) -> &[(f32, f32)] {
+        if !self.analysis_buffer.is_empty() {
+            &self.analysis_buffer
+        } else if self.lookahead_buffer.is_empty() {
+            &self.detection_frames
+        } else {
+            &self.lookahead_buffer
+        }
+    }
+
+    /// The RMS level of the detection window, across both channels
+    fn detection_rms(&self) -> f32 {
+        let window = self.detection_window();
+        let sum_squares: f32 = window
+            .iter()
+            .map(|(left, right)| left.mul_add(*left, right * right))
+            .sum();
+
+        (sum_squares / (window.len() * 2).max(1) as f32).sqrt()
+    }
+
+    /// The correlation between the channels over the detection window, from -1 to 1. Returns `None` unless both
+    /// channels have signal
+    fn detection_correlation(&self) -> Option<f32> {
+        let (left_squares, right_squares, cross) = self.detection_sums();
+        if left_squares == 0.0 || right_squares == 0.0 {
+            return None;
+        }
+
+        Some((cross / (left_squares * right_squares).sqrt()).clamp(-1.0, 1.0))
+    }
+
+    /// Whether the detection window is mono or identical dual mono, see [`MONO_CORRELATION`]
+    fn detection_is_mono(&self) -> bool {
+        let (left_squares, right_squares, cross) = self.detection_sums();
+        if left_squares == 0.0 || right_squares == 0.0 {
+            return false;
+        }
+
+        let correlation = cross / (left_squares * right_squares).sqrt();
+        let angle = right_squares.sqrt().atan2(left_squares.sqrt()).to_degrees();
+        correlation > MONO_CORRELATION && (angle - 45.0).abs() < MONO_ANGLE_TOLERANCE
+    }
+
+    /// The sums of the left channel's squares, the right channel's squares, and their products over the detection
+    /// window
+    fn detection_sums(&self) -> (f32, f32, f32) {
+        self.detection_window().iter().fold(
+            (0.0, 0.0, 0.0),
+            |(left_squares, right_squares, cross): (f32, f32, f32), (left, right)| {
+                (
+                    left.mul_add(*left, left_squares),
+                    right.mul_add(*right, right_squares),
+                    left.mul_add(*right, cross),
+                )
+            },
+        )
+    }
+
+    /// Runs the detected angle and a few statistics of the same window through the detection script
+    #[cfg(feature = "scripting")]
+    fn scripted_angle(&mut self) -> Option<f32> {
+        let mean_angle = self.detect_angle()? - 45.0;
+        let stats = WindowStats::new(mean_angle, self.detection_window().iter().copied());
+
+        self.params
+            .detection_script
+            .evaluate(&stats)
+            .map(|angle| angle.clamp(-45.0, 45.0) + 45.0)
+    }
+
+    /// The reaction time from the parameters, following the host's tempo if it's synced. Synced reaction times fall
+    /// back to the plain reaction time when the host doesn't report a tempo
+    fn reaction_time_ms(&self, transport: &Transport) -> f32 {
+        let reaction_time = self.params.param_locks.plain_value(
+            REACTION_TIME_ID,
+            &self.params.reaction_time,
+            self.params.reaction_time.modulated_plain_value(),
+        );
+        if !self.params.reaction_sync.value() {
+            return reaction_time;
+        }
+
+        self.params
+            .reaction_division
+            .value()
+            .length_ms(transport)
+            .unwrap_or(reaction_time)
+    }
+
+    /// Keeps track of how fast the detected angle is moving, averaged over [`AUTO_RELEASE_WINDOW_SECONDS`]. Blocks
+    /// without a target don't move it either way
+    fn update_angle_speed(&mut self, target: Option<f32>, num_samples: usize) {
+        let Some(target) = target.filter(|_| num_samples > 0) else {
+            return;
+        };
+
+        let block_seconds = num_samples as f32 / self.sample_rate;
+        if let Some(last_target) = self.last_target {
+            let speed = (target - last_target).abs() / block_seconds;
+            let weight = (-block_seconds / AUTO_RELEASE_WINDOW_SECONDS).exp();
+            self.angle_speed = self.angle_speed.mul_add(weight, speed * (1.0 - weight));
+        }
+        self.last_target = Some(target);
+    }
+
+    /// Steps the smoother and moves the correction towards it by at most `max_step` degrees. Until the correction has
+    /// settled anywhere at all it jumps straight there, so the first detection doesn't have to crawl in from nothing
+    fn next_correction_angle(&mut self, max_step: Option<f32>) -> f32 {
+        let angle = self.correction_angle_smoother.next();
+        self.correction_angle = match max_step {
+            Some(max_step) if (0.0..=90.0).contains(&self.correction_angle) => {
+                self.correction_angle + (angle - self.correction_angle).clamp(-max_step, max_step)
+            }
+            _ => angle,
+        };
+
+        self.correction_angle
+    }
+
+    /// Passes `target` on once it's been away from the last target that made it through for at least
+    /// [`CenteredParams::hold_off`], and returns `None` until then. Every target that's close enough to the last one
+    /// starts the wait over
+    fn apply_hold_off(&mut self, target: Option<f32>, num_samples: usize) -> Option<f32> {
+        let hold_off_samples =
+            (self.sample_rate * (self.params.hold_off.value() / 1000.0)).round() as usize;
+        let target = target?;
+        match self.hold_off_target {
+            Some(held) if (target - held).abs() > HOLD_OFF_TOLERANCE => {
+                self.hold_off_samples += num_samples;
+                if self.hold_off_samples < hold_off_samples {
+                    return None;
+                }
+            }
+            _ => {}
+        }
+
+        self.hold_off_target = Some(target);
+        self.hold_off_samples = 0;
+        Some(target)
+    }
+
+    /// How much the auto release stretches the release time. An angle that jumps around a lot gets a longer release so
+    /// the correction doesn't pump, and one that drifts slowly gets a shorter one so the correction doesn't lag behind
+    fn auto_release_scale(&self) -> f32 {
+        (self.angle_speed / AUTO_RELEASE_REFERENCE_SPEED)
+            .clamp(AUTO_RELEASE_MAX_SCALE.recip(), AUTO_RELEASE_MAX_SCALE)
+    }
+
+    /// How much the adaptive reaction stretches the reaction times, from the energy weighted spread of the detection
+    /// window's angles. A spread out window means the estimate is noisy and gets a longer reaction, and a tight one
+    /// means there's a clear offset that gets a shorter one
+    fn adaptive_reaction_scale(&self) -> f32 {
+        let decimation = self.params.detection_decimation.value() as usize;
+        let (mut energy_sum, mut angle_sum, mut angle_square_sum) = (0.0, 0.0, 0.0);
+        for (left, right) in self.detection_window().iter().step_by(decimation) {
+            let energy = left.mul_add(*left, right * right);
+            if energy == 0.0 {
+                continue;
+            }
+
+            let angle = right.abs().atan2(left.abs()).to_degrees();
+            energy_sum += energy;
+            angle_sum += angle * energy;
+            angle_square_sum += angle * angle * energy;
+        }
+        if energy_sum == 0.0 {
+            return 1.0;
+        }
+
+        let mean = angle_sum / energy_sum;
+        let spread = (angle_square_sum / energy_sum - mean * mean)
+            .max(0.0)
+            .sqrt();
+        (spread / ADAPTIVE_REACTION_REFERENCE_SPREAD).clamp(
+            ADAPTIVE_REACTION_MAX_SCALE.recip(),
+            ADAPTIVE_REACTION_MAX_SCALE,
+        )
+    }
+
+    /// The input's angle relative to the sidechain's, shifted so that sitting where the sidechain does counts as
+    /// centered. The sidechain only gets looked at over the current block. While it's silent or disconnected, the
+    /// last place it had any signal is used instead
+    fn matched_angle(&mut self, aux: &mut AuxiliaryBuffers) -> Option<f32> {
+        if let Some(sidechain) = aux.inputs.first_mut() {
+            let estimator = self.reference_estimators.get(self.params.estimator.value());
+            estimator.clear();
+            for mut sample in sidechain.iter_samples() {
+                // a silent reference doesn't say anything about where it sits
+                push_detection_frame(
+                    estimator,
+                    SilenceBehavior::Hold,
+                    *sample.get_mut(0).unwrap(),
+                    *sample.get_mut(1).unwrap(),
+                    1.0,
+                );
+            }
+            if let Some(angle) = estimator.estimate() {
+                self.reference_angle = angle;
+            }
+        }
+
+        // kept within the detector's range, so every mode can make sense of it
+        self.detect_angle()
+            .map(|angle| (angle - self.reference_angle + 45.0).clamp(0.0, 90.0))
+    }
+
+    /// The imported automation's angle at the current transport position, as a detector angle. Returns `None` if
+    /// the transport is stopped or nothing's imported
+    fn imported_angle(&self, transport: &Transport) -> Option<f32> {
+        if !transport.playing {
+            return None;
+        }
+
+        transport
+            .pos_seconds()
+            .and_then(|seconds| self.params.angle_automation.angle_at(seconds))
+            .map(|angle| angle + 45.0)
+    }
+
+    /// Whether the sidechain went over the freeze threshold during this block. Always `false` if freezing is off or
+    /// the host didn't connect a sidechain, since an unconnected sidechain is silent
+    fn sidechain_freeze_active(&self, aux: &AuxiliaryBuffers) -> bool {
+        if !self.params.sidechain_freeze.value() {
+            return false;
+        }
+
+        let threshold = util::db_to_gain(self.params.sidechain_threshold.value());
+        aux.inputs.first().is_some_and(|sidechain| {
+            sidechain
+                .as_slice_immutable()
+                .iter()
+                .flat_map(|channel| channel.iter())
+                .any(|sample| sample.abs() > threshold)
+        })
+    }
+
+    /// Mutes the block and resets the detection state if the rotation produced NaN, infinity, or something absurdly
+    /// loud, so a blown up state can't make it to anyone's monitors
+    fn run_watchdog(&mut self, buffer: &mut Buffer) {
+        let blown_up = buffer
+            .as_slice_immutable()
+            .iter()
+            .flat_map(|channel| channel.iter())
+            .any(|sample| !sample.is_finite() || sample.abs() > WATCHDOG_MAX_AMPLITUDE);
+
+        if !blown_up {
+            return;
+        }
+
+        for channel in buffer.as_slice() {
+            channel.fill(0.0);
+        }
+
+        self.reset_detection();
+        self.watchdog_tripped.store(true, Ordering::Relaxed);
+    }
+
+    /// Starts the detector over from nothing: the correction, the lookahead and analysis windows, and the detection
+    /// filter
+    fn reset_detection(&mut self) {
+        self.correction_angle_smoother.reset(-45.0);
+        self.correction_angle = -45.0;
+        self.lookahead_buffer.fill((0.0, 0.0));
+        self.lookahead_buffer_idx = 0;
+        self.analysis_buffer.fill((0.0, 0.0));
+        self.analysis_buffer_idx = 0;
+        self.lookahead_crossfade_samples = 0;
+        self.detection_filter.reset();
+        self.voice_activity_detector.reset();
+        self.multiband.reset();
+        self.last_target = None;
+        self.hold_off_target = None;
+        self.hold_off_samples = 0;
+    }
+
+    /// Whether the transport moved backwards since the last block, which means it looped or got relocated
+    fn transport_jumped_back(&mut self, transport: &Transport) -> bool {
+        let pos_samples = if transport.playing {
+            transport.pos_samples()
+        } else {
+            None
+        };
+        let jumped_back = matches!(
+            (pos_samples, self.last_pos_samples),
+            (Some(pos), Some(last_pos)) if pos < last_pos
+        );
+
+        self.last_pos_samples = pos_samples;
+        jumped_back
+    }
+
+    /// Starts whichever of the remote servers the config asks for, unless they're already running
+    #[cfg(feature = "remote")]
+    fn start_remote_servers(&mut self) {
+        let sources = MeterSources {
+            params: self.params.clone(),
+            correcting_angle: self.correcting_angle.clone(),
+            pre_stereo_data: self.pre_stereo_data.clone(),
+            post_stereo_data: self.post_stereo_data.clone(),
+            pre_peak_meter: self.pre_peak_meter.clone(),
+            post_peak_meter: self.post_peak_meter.clone(),
+            post_mono_compat: self.post_mono_compat.clone(),
+            loudness: self.loudness.clone(),
+        };
+
+        if self.metering_server.is_none() {
+            if let Some(port) = GLOBAL_CONFIG.remote.websocket_port {
+                self.metering_server = MeteringServer::start(port, sources.clone());
+            }
+        }
+        if self.remote_ui_server.is_none() {
+            if let Some(port) = GLOBAL_CONFIG.remote.http_port {
+                self.remote_ui_server =
+                    RemoteUiServer::start(port, sources, self.gui_context.clone());
+            }
+        }
+    }
+
+    /// Suspends the meters once the input goes quiet, and clears them so they don't sit frozen at their last reading.
+    /// Audio still flowing through a stopped transport (monitoring a live input, say) keeps them running
+    fn update_metering_idle(&mut self, buffer: &Buffer, transport: &Transport) {
+        let silence = util::db_to_gain(METERING_SILENCE_DB);
+        let silent = buffer
+            .as_slice_immutable()
+            .iter()
+            .flat_map(|channel| channel.iter())
+            .all(|sample| sample.abs() < silence);
+
+        self.silent_samples = if silent {
+            self.silent_samples.saturating_add(buffer.samples())
+        } else {
+            0
+        };
+        let idle = silent
+            && (!transport.playing
+                || self.silent_samples as f32 >= METERING_IDLE_SECONDS * self.sample_rate);
+
+        if idle && !self.metering_idle.swap(true, Ordering::Relaxed) {
+            for (left, right) in self
+                .pre_stereo_data
+                .iter()
+                .chain(self.post_stereo_data.iter())
+            {
+                left.store(0.0, Ordering::Relaxed);
+                right.store(0.0, Ordering::Relaxed);
+            }
+            for meter in [&self.pre_peak_meter, &self.post_peak_meter] {
+                meter.0.store(0.0, Ordering::Relaxed);
+                meter.1.store(0.0, Ordering::Relaxed);
+            }
+        } else if !idle {
+            self.metering_idle.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether anything is watching the meters, so they're worth updating
+    fn metering_active(&self) -> bool {
+        if self.metering_idle.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        #[cfg(feature = "remote")]
+        if self.metering_server.is_some() || self.remote_ui_server.is_some() {
+            return true;
+        }
+
+        self.params.editor_state.is_open()
+    }
+
+    /// The plugin's total latency, which is just the lookahead. None of the processing modes add any delay of their own
+    /// right now, so one that starts to has to be added here
+    fn latency_samples(&self) -> u32 {
+        self.get_lookahead_samples() as u32
+    }
+
+    /// Returns the latency if it changed since it was last reported. All latency reporting goes through this, so the
+    /// host is only ever told about actual changes
+    fn take_latency_change(&mut self) -> Option<u32> {
+        let latency = self.latency_samples();
+        if self.reported_latency == Some(latency) {
+            return None;
+        }
+
+        self.reported_latency = Some(latency);
+        Some(latency)
+    }
+
     fn get_lookahead_samples(&self) -> usize {
-        (self.sample_rate * (self.params.lookahead.modulated_plain_value() / 1000.0)).round() as usize
+        let long_lookahead = self.params.long_lookahead.value();
+        let locked_lookahead = self
+            .params
+            .param_locks
+            .locked_normalized_value(LOOKAHEAD_ID)
+            .filter(|_| !long_lookahead);
+
+        // a locked lookahead stays where it was locked, even when the profile turns the lookahead off
+        let lookahead_ms = if let Some(normalized) = locked_lookahead {
+            self.params.lookahead.preview_plain(normalized)
+        } else if Profile::from(self.params.profile.load(Ordering::Relaxed)).disables_lookahead() {
+            return 0;
+        } else if long_lookahead {
+            self.params.long_lookahead_time.modulated_plain_value()
+        } else {
+            self.params.lookahead.modulated_plain_value()
+        };
+
+        (self.sample_rate * (lookahead_ms / 1000.0)).round() as usize
+    }
+
+    /// The length of the fixed analysis window, 0 if it's off
+    fn get_analysis_window_samples(&self) -> usize {
+        if !self.params.fixed_analysis_window.value() {
+            return 0;
+        }
+
+        let analysis_window_ms = self.params.analysis_window.value();
+        ((self.sample_rate * (analysis_window_ms / 1000.0)).round() as usize).max(1)
+    }
+}
+
+/// Writes `frames` into the ring buffer `ring`, starting at `idx` and wrapping around. Does nothing to an empty ring
+fn write_ring(ring: &mut [(f32, f32)], idx: &mut usize, frames: &[(f32, f32)]) {
+    if ring.is_empty() {
+        return;
+    }
+
+    for &frame in frames {
+        if *idx >= ring.len() {
+            *idx = 0;
+        }
+
+        ring[*idx] = frame;
+
+        *idx += 1;
+    }
+}
+
+/// Resizes a ring buffer without scrambling it. The ring gets unrolled so the oldest frame comes first, then trimmed
+/// or padded at that end, so it keeps holding the most recent audio in order. A plain resize would splice silence into
+/// the middle of the window and make the correction jump. This never allocates as long as the ring's capacity was
+/// reserved up front
+fn resize_ring(ring: &mut Vec<(f32, f32)>, idx: &mut usize, len: usize) {
+    let old_len = ring.len();
+    ring.rotate_left((*idx).min(old_len));
+    if len < old_len {
+        ring.drain(..old_len - len);
+    } else {
+        ring.resize(len, (0.0, 0.0));
+        ring.rotate_right(len - old_len);
+    }
+
+    // the oldest frame is at the start now, so that's where the next one goes
+    *idx = 0;
+}
+
+/// Whether going from `current` to `target` moves the correction further from the center, which is what the attack
+/// covers. Until the correction has settled anywhere at all, everything counts as the attack
+fn is_attack(current: f32, target: f32) -> bool {
+    !(0.0..=90.0).contains(&current) || (target - 45.0).abs() > (current - 45.0).abs()
+}
+
+/// Runs every frame of `source` through the detection filter, into `frames`
+fn filter_detection_frames(
+    source: &mut Buffer,
+    filter: &mut DetectionFilter,
+    frames: &mut Vec<(f32, f32)>,
+) {
+    for mut sample in source.iter_samples() {
+        frames.push(filter.process(*sample.get_mut(0).unwrap(), *sample.get_mut(1).unwrap()));
+    }
+}
+
+/// Adds a frame to the estimator's analysis window, treating digital silence the way `silence_behavior` says to
+fn push_detection_frame(
+    estimator: &mut dyn AngleEstimator,
+    silence_behavior: SilenceBehavior,
+    left: f32,
+    right: f32,
+    weight: f32,
+) {
+    if left == 0.0 && right == 0.0 {
+        match silence_behavior {
+            // stand in a vanishingly quiet centered frame, so silence pulls the correction back to the middle with
+            // however much weight the estimator gives quiet frames
+            SilenceBehavior::Center => estimator.push(f32::EPSILON, f32::EPSILON, weight),
+            // silent samples don't say anything about the image, so they're left out of the estimate entirely
+            SilenceBehavior::Hold | SilenceBehavior::Return => {}
+        }
+    } else {
+        estimator.push(left, right, weight);
+    }
+}
+
+/// Maps a detected offset from the center to the offset that gets corrected, all in degrees. Offsets inside the
+/// `deadband` are left alone, the correction fades in over the `knee` past it, and then follows the offset as is until
+/// it eases into `max_correction`, starting at half of it
+fn correction_curve(offset: f32, deadband: f32, knee: f32, max_correction: f32) -> f32 {
+    let magnitude = offset.abs();
+    let magnitude = if magnitude < deadband {
+        0.0
+    } else if magnitude < deadband + knee {
+        let fade = (magnitude - deadband) / knee;
+        magnitude * fade * fade * 2.0f32.mul_add(-fade, 3.0)
+    } else {
+        magnitude
+    };
+
+    let ease_start = max_correction / 2.0;
+    let magnitude = if magnitude < ease_start {
+        magnitude
+    } else if magnitude < ease_start + max_correction {
+        magnitude - (magnitude - ease_start).powi(2) / (2.0 * max_correction)
+    } else {
+        max_correction
+    };
+
+    magnitude.copysign(offset)
+}
+
+/// `style` for a smoother going from `from` to `to`. Logarithmic smoothing can't cross or touch zero, so those moves
+/// fall back to linear
+fn style_towards(style: &SmoothingStyle, from: f32, to: f32) -> SmoothingStyle {
+    match style {
+        SmoothingStyle::Logarithmic(ms) if from <= 0.0 || to <= 0.0 => SmoothingStyle::Linear(*ms),
+        style => style.clone(),
+    }
+}
+
+/// The pan in radians that [`rotate`] gets, to bring a signal at the smoothed `angle` `target` degrees to the right of
+/// the center. `inverse` moves the image away from the target by as much as the regular correction would move it
+/// towards the target, instead
+fn correction_pan(angle: f32, target: f32, amount: f32, inverse: bool) -> f32 {
+    if !inverse {
+        return (-45.0 - target - angle).to_radians() * amount;
+    }
+
+    // a fresh smoother sits outside the detector's range, which means there's nothing to push away from yet. this keeps
+    // the center where it is, like the regular correction does with nothing to correct
+    if !(0.0..=90.0).contains(&angle) {
+        return (-90.0f32).to_radians();
+    }
+
+    // `rotate` brings a frame at `angle` to `-pan - angle`, so this lands it at `angle` plus its distance from the
+    // target. scaling that distance instead of the pan keeps the output in phase at every amount
+    let output_angle = (angle - 45.0 - target).mul_add(amount, angle);
+    (-output_angle - angle).to_radians()
+}
+
+/// The correction every rotating mode applies. Despite the name this is a reflection, which brings a frame at the
+/// angle `θ` to `-pan - θ`
+fn rotate(left: f32, right: f32, pan: f32) -> (f32, f32) {
+    let (pan_sin, pan_cos) = pan.sin_cos();
+    (
+        left.mul_add(pan_cos, -(right * pan_sin)),
+        left.mul_add(-pan_sin, -(right * pan_cos)),
+    )
+}
+
+/// Turns the whole frame by `degrees`, positive to the right. Unlike [`rotate`] this is an actual rotation, so
+/// everything in the image moves by the same angle
+fn turn(left: f32, right: f32, degrees: f32) -> (f32, f32) {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    (
+        left.mul_add(cos, -(right * sin)),
+        left.mul_add(sin, right * cos),
+    )
+}
+
+/// The channel gains that bring a signal at the detected `angle` to `target` degrees right of its center, by meeting
+/// at the geometric mean of both channels' levels. `amount` scales the correction from 0 to 1
+fn balance_gains(angle: f32, target: f32, amount: f32) -> (f32, f32) {
+    // a fresh smoother sits outside the detector's range, which means there's nothing to balance by yet
+    if !(0.0..=90.0).contains(&angle) {
+        return (1.0, 1.0);
+    }
+
+    // the angle is atan(right / left), so tan gives back the level ratio between the channels
+    let ratio_db = util::gain_to_db(angle.to_radians().tan().abs())
+        - util::gain_to_db((45.0 + target).to_radians().tan().abs());
+    let half_ratio_db = (ratio_db / 2.0).clamp(-MAX_BALANCE_DB, MAX_BALANCE_DB) * amount;
+
+    (
+        util::db_to_gain(half_ratio_db),
+        util::db_to_gain(-half_ratio_db),
+    )
+}
+
+/// How much of the mid to take out of the side to move a signal at the detected `angle` to `target` degrees right of
+/// the center. A source panned to `angle` has a side channel of `tan(45° - angle)` times its mid, so swapping that for
+/// the target's side channel moves it without touching the mid at all. `amount` scales the correction from 0 to 1
+fn side_steering(angle: f32, target: f32, amount: f32) -> f32 {
+    // a fresh smoother sits outside the detector's range, which means there's nothing to steer by yet
+    if !(0.0..=90.0).contains(&angle) {
+        return 0.0;
+    }
+
+    ((45.0 - angle).to_radians().tan().clamp(-1.0, 1.0) + target.to_radians().tan()) * amount
+}
+
+/// Rebuilds the channels with `steering` times the mid taken out of the side. The right channel is the input's sum
+/// minus the new left, so the mono sum only picks up the rounding of that subtraction and of summing it back up. It
+/// can't be bit-identical in general: once the steered channels get louder than their sum, their float steps are
+/// coarser than the sum's lowest bits
+fn steer_side(left: f32, right: f32, steering: f32) -> (f32, f32) {
+    let sum = left + right;
+    let mid = sum * 0.5;
+    let steered_left = mid + (left - right).mul_add(0.5, -steering * mid);
+
+    (steered_left, sum - steered_left)
+}
+
+/// Copies the front pair of the uncorrected input to the reference output, if the host connected one. The correction
+/// adds no delay of its own and the host compensates every output for the reported latency, so this lines up with the
+/// main output as is
+fn write_reference(buffer: &mut Buffer, aux: &mut AuxiliaryBuffers) {
+    let Some(reference) = aux.outputs.first_mut() else {
+        return;
+    };
+
+    for (mut channel_samples, mut reference_samples) in
+        buffer.iter_samples().zip(reference.iter_samples())
+    {
+        *reference_samples.get_mut(0).unwrap() = *channel_samples.get_mut(0).unwrap();
+        *reference_samples.get_mut(1).unwrap() = *channel_samples.get_mut(1).unwrap();
     }
 }
 
@@ -300,6 +2555,39 @@ fn calc_peak(buffer: &mut Buffer, peak: [&AtomicF32; 2], decay_weight: f32) {
     }
 }
 
+/// Same as [`calc_peak`], but runs every channel through a true peak detector first. Returns the highest true peak
+/// in the buffer
+fn calc_true_peak(
+    buffer: &mut Buffer,
+    detectors: &mut [TruePeakDetector; 2],
+    peak: [&AtomicF32; 2],
+    decay_weight: f32,
+) -> f32 {
+    let mut max_true_peak: f32 = 0.0;
+
+    for mut channel_samples in buffer.iter_samples() {
+        for ((sample, detector), peak) in channel_samples
+            .iter_mut()
+            .zip(detectors.iter_mut())
+            .zip(peak.iter())
+        {
+            let amp = detector.process(*sample);
+            max_true_peak = max_true_peak.max(amp);
+
+            let current_peak = peak.load(Ordering::Relaxed);
+            let new_peak = if amp > current_peak {
+                amp
+            } else {
+                current_peak * decay_weight + amp * (1. - decay_weight)
+            };
+
+            peak.store(new_peak, Ordering::Relaxed);
+        }
+    }
+
+    max_true_peak
+}
+
 impl ClapPlugin for Centered {
     const CLAP_ID: &'static str = "space.cozydsp.centered";
     const CLAP_DESCRIPTION: Option<&'static str> = Some("an attempt at recentering stereo signals");
@@ -326,3 +2614,109 @@ impl Vst3Plugin for Centered {
 
 nih_export_clap!(Centered);
 nih_export_vst3!(Centered);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The angle of a frame in degrees with 45 as the center. Unlike [`estimator::frame_angle`] this keeps the signs,
+    /// so a polarity flip shows up as an angle outside 0 to 90 degrees
+    fn signed_angle(left: f32, right: f32) -> f32 {
+        right.atan2(left).to_degrees()
+    }
+
+    fn frame_at(angle: f32) -> (f32, f32) {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        (cos, sin)
+    }
+
+    #[test]
+    fn turning_by_nothing_changes_nothing() {
+        for (left, right) in [(1.0, 0.0), (0.5, 0.5), (-0.25, 0.75)] {
+            assert_eq!(turn(left, right, 0.0), (left, right));
+        }
+    }
+
+    #[test]
+    fn turning_moves_hard_left_by_the_angle() {
+        for degrees in [-30.0, 10.0, 45.0] {
+            let (left, right) = turn(1.0, 0.0, degrees);
+            let output_angle = signed_angle(left, right);
+            assert!(
+                (output_angle - degrees).abs() < 1e-3,
+                "turning by {degrees} ended up at {output_angle}"
+            );
+        }
+    }
+
+    #[test]
+    fn inverse_leaves_centered_input_alone() {
+        for amount in [0.0, 0.25, 0.5, 1.0] {
+            let pan = correction_pan(45.0, 0.0, amount, true);
+            let (left, right) = rotate(0.5, 0.5, pan);
+            assert!(
+                (left - 0.5).abs() < 1e-5 && (right - 0.5).abs() < 1e-5,
+                "{amount} turned the center into ({left}, {right})"
+            );
+        }
+    }
+
+    #[test]
+    fn inverse_exaggerates_without_flipping_polarity() {
+        for (angle, expected) in [(50.0, 55.0), (40.0, 35.0), (30.0, 15.0)] {
+            let (left, right) = frame_at(angle);
+            let (left, right) = rotate(left, right, correction_pan(angle, 0.0, 1.0, true));
+            let output_angle = signed_angle(left, right);
+            assert!(
+                (output_angle - expected).abs() < 1e-3,
+                "{angle} ended up at {output_angle} instead of {expected}"
+            );
+        }
+
+        let (left, right) = frame_at(50.0);
+        let (left, right) = rotate(left, right, correction_pan(50.0, 0.0, 0.5, true));
+        let output_angle = signed_angle(left, right);
+        assert!(
+            (output_angle - 52.5).abs() < 1e-3,
+            "half the amount ended up at {output_angle}"
+        );
+    }
+
+    #[test]
+    fn side_only_keeps_the_mono_sum() {
+        // a plain xorshift, so the test doesn't need a random number crate
+        let mut seed = 0x2545_f491_u32;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+        };
+
+        for _ in 0..100_000 {
+            let (left, right, steering) = (next(), next(), next() * 2.0);
+            for (left, right) in [(left, right), (left, right * 0.01), (left, -left * 0.999)] {
+                let (out_left, out_right) = steer_side(left, right, steering);
+                // the subtraction and the sum each round by at most half a step of the largest value involved
+                let largest = out_left
+                    .abs()
+                    .max(out_right.abs())
+                    .max((left + right).abs());
+                let tolerance = f32::EPSILON * largest;
+                assert!(
+                    (out_left + out_right - (left + right)).abs() <= tolerance,
+                    "steering ({left}, {right}) by {steering} changed the mono sum"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn side_only_centers_hard_left() {
+        let (left, right) = steer_side(1.0, 0.0, side_steering(0.0, 0.0, 1.0));
+        assert!(
+            (left - 0.5).abs() < 1e-6 && (right - 0.5).abs() < 1e-6,
+            "hard left ended up at ({left}, {right})"
+        );
+    }
+}
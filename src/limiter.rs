@@ -0,0 +1,80 @@
+use std::sync::atomic::Ordering;
+
+use nih_plug::{
+    prelude::{AtomicF32, Buffer},
+    util::gain_to_db,
+};
+
+const RELEASE_MS: f32 = 50.0;
+
+/// Gain reduction for the editor's GR meter, in positive dB. The held maximum sticks around until the editor clears
+/// it
+#[derive(Default)]
+pub struct LimiterMeter {
+    pub reduction_db: AtomicF32,
+    pub held_reduction_db: AtomicF32,
+}
+
+/// Stereo linked brickwall limiter for the output. Rotating a wide signal can stack both channels' peaks onto one
+/// side, and this keeps that from clipping. The attack is instant, so nothing ever gets past the ceiling
+pub struct SafetyLimiter {
+    gain: f32,
+    release_weight: f32,
+}
+
+impl Default for SafetyLimiter {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            release_weight: 0.0,
+        }
+    }
+}
+
+impl SafetyLimiter {
+    pub fn initialize(&mut self, sample_rate: f32) {
+        self.release_weight = (-(sample_rate * RELEASE_MS / 1000.0).recip()).exp();
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+
+    /// Limits the buffer's peaks to `ceiling`, which is a linear gain
+    pub fn process(&mut self, buffer: &mut Buffer, ceiling: f32, meter: &LimiterMeter) {
+        let mut min_gain: f32 = 1.0;
+
+        for mut channel_samples in buffer.iter_samples() {
+            let peak = channel_samples
+                .iter_mut()
+                .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+            let target_gain = if peak > ceiling { ceiling / peak } else { 1.0 };
+
+            self.gain = if target_gain < self.gain {
+                target_gain
+            } else {
+                (self.gain - target_gain).mul_add(self.release_weight, target_gain)
+            };
+            min_gain = min_gain.min(self.gain);
+
+            for sample in channel_samples.iter_mut() {
+                *sample *= self.gain;
+            }
+        }
+
+        let reduction_db = -gain_to_db(min_gain);
+        meter.reduction_db.store(reduction_db, Ordering::Relaxed);
+        if reduction_db > meter.held_reduction_db.load(Ordering::Relaxed) {
+            meter
+                .held_reduction_db
+                .store(reduction_db, Ordering::Relaxed);
+        }
+    }
+
+    /// Clears the meter when the limiter gets switched off, so it doesn't keep showing stale reduction
+    pub fn bypass(&mut self, meter: &LimiterMeter) {
+        self.reset();
+        meter.reduction_db.store(0.0, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,115 @@
+use std::sync::atomic::Ordering;
+
+use nih_plug::prelude::{AtomicF32, Buffer};
+
+use crate::filter::{DetectionFilter, Weighting};
+
+/// BS.1770 measures over windows that slide in 100 ms steps
+const SEGMENT_SECONDS: f32 = 0.1;
+/// The momentary loudness covers the last 400 ms
+const MOMENTARY_SEGMENTS: usize = 4;
+/// The short-term loudness covers the last 3 seconds
+const SHORT_TERM_SEGMENTS: usize = 30;
+/// BS.1770's absolute gate. Anything quieter doesn't get a reading
+const ABSOLUTE_GATE_LUFS: f32 = -70.0;
+
+/// The output's momentary and short-term loudness, K-weighted the way a LUFS meter does it
+#[derive(Default)]
+pub struct Loudness {
+    /// Both stored as the K-weighted mean square summed over the channels, so the defaults read as silence
+    momentary: AtomicF32,
+    short_term: AtomicF32,
+}
+
+impl Loudness {
+    /// The loudness over the last 400 ms in LUFS. `None` while it's below the absolute gate
+    pub fn momentary_lufs(&self) -> Option<f32> {
+        to_lufs(self.momentary.load(Ordering::Relaxed))
+    }
+
+    /// The loudness over the last 3 seconds in LUFS. `None` while it's below the absolute gate
+    pub fn short_term_lufs(&self) -> Option<f32> {
+        to_lufs(self.short_term.load(Ordering::Relaxed))
+    }
+
+    pub fn reset(&self) {
+        self.momentary.store(0.0, Ordering::Relaxed);
+        self.short_term.store(0.0, Ordering::Relaxed);
+    }
+}
+
+fn to_lufs(mean_square: f32) -> Option<f32> {
+    let lufs = -0.691 + 10.0 * mean_square.log10();
+    (lufs >= ABSOLUTE_GATE_LUFS).then_some(lufs)
+}
+
+/// Audio thread side of the [`Loudness`]. It reuses the detection filter's K-weighting, and keeps the energy of the
+/// last few 100 ms segments so both windows can be summed up whenever a segment finishes
+#[derive(Default)]
+pub struct LoudnessMeter {
+    filter: DetectionFilter,
+    segment_len: usize,
+    /// How many samples went into the current segment so far, and their summed energy
+    segment_samples: usize,
+    segment_energy: f64,
+    /// The mean square of the last segments, a ring buffer with the newest at `segment_idx`
+    segments: [f32; SHORT_TERM_SEGMENTS],
+    segment_idx: usize,
+}
+
+impl LoudnessMeter {
+    pub fn initialize(&mut self, sample_rate: f32) {
+        self.filter.initialize(sample_rate);
+        self.filter.set_weighting(Some(Weighting::KWeighting));
+        self.segment_len = ((SEGMENT_SECONDS * sample_rate) as usize).max(1);
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.filter.reset();
+        self.segment_samples = 0;
+        self.segment_energy = 0.0;
+        self.segments = [0.0; SHORT_TERM_SEGMENTS];
+        self.segment_idx = 0;
+    }
+
+    pub fn process(&mut self, buffer: &mut Buffer, loudness: &Loudness) {
+        if self.segment_len == 0 {
+            return;
+        }
+
+        for mut channel_samples in buffer.iter_samples() {
+            let (left, right) = self.filter.process(
+                *channel_samples.get_mut(0).unwrap(),
+                *channel_samples.get_mut(1).unwrap(),
+            );
+            self.segment_energy += f64::from(left.mul_add(left, right * right));
+            self.segment_samples += 1;
+
+            if self.segment_samples == self.segment_len {
+                self.segment_idx = (self.segment_idx + 1) % SHORT_TERM_SEGMENTS;
+                self.segments[self.segment_idx] =
+                    (self.segment_energy / self.segment_len as f64) as f32;
+                self.segment_samples = 0;
+                self.segment_energy = 0.0;
+
+                loudness
+                    .momentary
+                    .store(self.mean_square(MOMENTARY_SEGMENTS), Ordering::Relaxed);
+                loudness
+                    .short_term
+                    .store(self.mean_square(SHORT_TERM_SEGMENTS), Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// The mean square over the newest `num_segments` segments
+    fn mean_square(&self, num_segments: usize) -> f32 {
+        (0..num_segments)
+            .map(|age| {
+                self.segments[(self.segment_idx + SHORT_TERM_SEGMENTS - age) % SHORT_TERM_SEGMENTS]
+            })
+            .sum::<f32>()
+            / num_segments as f32
+    }
+}
@@ -1,6 +1,14 @@
-use centered::Centered;
-use nih_plug::nih_export_standalone;
+use centered::{
+    standalone::{mark_standalone, StandaloneConfig},
+    Centered,
+};
+use nih_plug::nih_export_standalone_with_args;
 
 fn main() {
-    nih_export_standalone::<Centered>();
+    mark_standalone();
+
+    let mut args: Vec<String> = std::env::args().collect();
+    StandaloneConfig::load().apply_to_args(&mut args);
+
+    nih_export_standalone_with_args::<Centered, _>(args);
 }
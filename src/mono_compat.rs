@@ -0,0 +1,73 @@
+use std::sync::atomic::Ordering;
+
+use nih_plug::prelude::{AtomicF32, Buffer};
+
+const WINDOW_SECONDS: f32 = 3.0;
+/// Scores below this count as failing
+pub const PASSING_SCORE: f32 = 50.0;
+const SILENCE: f32 = 1e-9;
+
+/// A rolling estimate of how much of the signal survives being folded down to mono
+#[derive(Default)]
+pub struct MonoCompatMeter {
+    left_mean_square: AtomicF32,
+    right_mean_square: AtomicF32,
+    cross_mean: AtomicF32,
+}
+
+impl MonoCompatMeter {
+    /// The share of the energy that's in the mid channel, as a percentage. This works out to the correlation between
+    /// the channels, weighted by how balanced they are: 100% is mono, 50% is uncorrelated stereo that loses 3 dB when
+    /// summed, and 0% cancels out completely. Returns `None` while the signal is silent
+    pub fn score(&self) -> Option<f32> {
+        let left = self.left_mean_square.load(Ordering::Relaxed);
+        let right = self.right_mean_square.load(Ordering::Relaxed);
+        let cross = self.cross_mean.load(Ordering::Relaxed);
+        if left + right < SILENCE {
+            return None;
+        }
+
+        // mid² = (L² + R² + 2LR) / 4 and mid² + side² = (L² + R²) / 2
+        Some(((left + right + 2.0 * cross) / (2.0 * (left + right)) * 100.0).clamp(0.0, 100.0))
+    }
+
+    /// The correlation between the channels, from -1 to 1. Returns `None` unless both channels have signal
+    pub fn correlation(&self) -> Option<f32> {
+        let left = self.left_mean_square.load(Ordering::Relaxed);
+        let right = self.right_mean_square.load(Ordering::Relaxed);
+        if left < SILENCE || right < SILENCE {
+            return None;
+        }
+
+        Some((self.cross_mean.load(Ordering::Relaxed) / (left * right).sqrt()).clamp(-1.0, 1.0))
+    }
+
+    pub fn reset(&self) {
+        self.left_mean_square.store(0.0, Ordering::Relaxed);
+        self.right_mean_square.store(0.0, Ordering::Relaxed);
+        self.cross_mean.store(0.0, Ordering::Relaxed);
+    }
+
+    /// Feeds a block of samples into the meter. This is called from the audio thread
+    pub fn update(&self, buffer: &mut Buffer, sample_rate: f32) {
+        let weight = (-(WINDOW_SECONDS * sample_rate).recip()).exp();
+        let mut left_mean_square = self.left_mean_square.load(Ordering::Relaxed);
+        let mut right_mean_square = self.right_mean_square.load(Ordering::Relaxed);
+        let mut cross_mean = self.cross_mean.load(Ordering::Relaxed);
+
+        for mut channel_samples in buffer.iter_samples() {
+            let left = *channel_samples.get_mut(0).unwrap();
+            let right = *channel_samples.get_mut(1).unwrap();
+
+            left_mean_square = left_mean_square.mul_add(weight, left * left * (1.0 - weight));
+            right_mean_square = right_mean_square.mul_add(weight, right * right * (1.0 - weight));
+            cross_mean = cross_mean.mul_add(weight, left * right * (1.0 - weight));
+        }
+
+        self.left_mean_square
+            .store(left_mean_square, Ordering::Relaxed);
+        self.right_mean_square
+            .store(right_mean_square, Ordering::Relaxed);
+        self.cross_mean.store(cross_mean, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,337 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use nih_plug::prelude::{Buffer, Enum, FloatParam, FloatRange, Params, Smoother, SmoothingStyle};
+
+use crate::{
+    correction_pan,
+    estimator::{EstimatorKind, Estimators},
+    filter::Biquad,
+    param_locks::{ParamLocks, HIGH_BAND_AMOUNT_ID, LOW_BAND_AMOUNT_ID, MID_BAND_AMOUNT_ID},
+    push_detection_frame, rotate, style_towards, SilenceBehavior,
+};
+
+pub const NUM_BANDS: usize = 3;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossoverSlope {
+    /// Gentle enough to leave full mixes sounding natural, at the cost of a lot of overlap between bands
+    #[id = "6"]
+    #[name = "6 dB/oct"]
+    Db6,
+    #[id = "12"]
+    #[name = "12 dB/oct"]
+    Db12,
+    /// Steep enough to isolate a problem band
+    #[id = "24"]
+    #[name = "24 dB/oct"]
+    Db24,
+}
+
+/// How much of the correction each band gets, on top of the overall correction amount
+#[derive(Params)]
+pub struct BandAmounts {
+    #[id = "multiband-low-amount"]
+    pub low: FloatParam,
+    #[id = "multiband-mid-amount"]
+    pub mid: FloatParam,
+    #[id = "multiband-high-amount"]
+    pub high: FloatParam,
+}
+
+impl Default for BandAmounts {
+    fn default() -> Self {
+        Self {
+            low: amount_param("Low Band Amount"),
+            mid: amount_param("Mid Band Amount"),
+            high: amount_param("High Band Amount"),
+        }
+    }
+}
+
+impl BandAmounts {
+    /// Every band's amount from 0 to 1, low to high, at the values they're locked at if they're locked
+    pub fn values(&self, param_locks: &ParamLocks) -> [f32; NUM_BANDS] {
+        [
+            (LOW_BAND_AMOUNT_ID, &self.low),
+            (MID_BAND_AMOUNT_ID, &self.mid),
+            (HIGH_BAND_AMOUNT_ID, &self.high),
+        ]
+        .map(|(id, amount)| param_locks.normalized_value(id, amount))
+    }
+}
+
+fn amount_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        100.0,
+        FloatRange::Linear {
+            min: 0.0,
+            max: 100.0,
+        },
+    )
+    .with_unit("%")
+    .with_step_size(0.1)
+}
+
+/// One channel of a crossover. Every slope sums back up to an allpass: first order is complementary, and the higher
+/// orders are Linkwitz-Riley, with the high band flipped for 12 dB/oct so it sums in phase
+#[derive(Default, Clone, Copy)]
+struct CrossoverChannel {
+    lowpass: [Biquad; 2],
+    highpass: [Biquad; 2],
+}
+
+impl CrossoverChannel {
+    fn set(&mut self, sample_rate: f32, frequency: f32, slope: CrossoverSlope) {
+        match slope {
+            CrossoverSlope::Db6 => self.lowpass[0].set_first_order_lowpass(sample_rate, frequency),
+            CrossoverSlope::Db12 => {
+                self.lowpass[0].set_lowpass(sample_rate, frequency, 0.5);
+                self.highpass[0].set_highpass(sample_rate, frequency, 0.5);
+            }
+            CrossoverSlope::Db24 => {
+                for (lowpass, highpass) in self.lowpass.iter_mut().zip(self.highpass.iter_mut()) {
+                    lowpass.set_lowpass(sample_rate, frequency, FRAC_1_SQRT_2);
+                    highpass.set_highpass(sample_rate, frequency, FRAC_1_SQRT_2);
+                }
+            }
+        }
+    }
+
+    fn split(&mut self, sample: f32, slope: CrossoverSlope) -> (f32, f32) {
+        match slope {
+            CrossoverSlope::Db6 => {
+                let low = self.lowpass[0].process(sample);
+                (low, sample - low)
+            }
+            CrossoverSlope::Db12 => (
+                self.lowpass[0].process(sample),
+                -self.highpass[0].process(sample),
+            ),
+            CrossoverSlope::Db24 => (
+                self.lowpass[1].process(self.lowpass[0].process(sample)),
+                self.highpass[1].process(self.highpass[0].process(sample)),
+            ),
+        }
+    }
+
+    fn reset(&mut self) {
+        for filter in self.lowpass.iter_mut().chain(self.highpass.iter_mut()) {
+            filter.reset();
+        }
+    }
+}
+
+/// A stereo crossover point
+#[derive(Default)]
+pub struct Crossover {
+    /// The current frequency and slope, `None` until the coefficients have been calculated
+    settings: Option<(f32, CrossoverSlope)>,
+    channels: [CrossoverChannel; 2],
+}
+
+impl Crossover {
+    /// Cheap to call every block, the coefficients only get recalculated when something actually changes
+    pub fn set(&mut self, sample_rate: f32, frequency: f32, slope: CrossoverSlope) {
+        let frequency = frequency.min(sample_rate * 0.45);
+        if self.settings == Some((frequency, slope)) {
+            return;
+        }
+
+        for channel in &mut self.channels {
+            channel.set(sample_rate, frequency, slope);
+        }
+        self.settings = Some((frequency, slope));
+    }
+
+    /// Returns the `(left, right)` frames below and above the crossover
+    pub fn split(&mut self, left: f32, right: f32) -> ((f32, f32), (f32, f32)) {
+        let slope = self
+            .settings
+            .map_or(CrossoverSlope::Db24, |(_, slope)| slope);
+        let (low_left, high_left) = self.channels[0].split(left, slope);
+        let (low_right, high_right) = self.channels[1].split(right, slope);
+
+        ((low_left, low_right), (high_left, high_right))
+    }
+
+    /// Forces the coefficients to be recalculated on the next [`Self::set`], for when the sample rate changes
+    pub fn invalidate(&mut self) {
+        self.settings = None;
+    }
+
+    /// Runs the signal through the crossover and sums it back up, which gives it the same phase shift the bands that
+    /// went through this crossover got
+    fn allpass(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let ((low_left, low_right), (high_left, high_right)) = self.split(left, right);
+        (low_left + high_left, low_right + high_right)
+    }
+
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.reset();
+        }
+    }
+}
+
+/// The crossover frequencies and slopes, low then high
+pub struct CrossoverSettings {
+    pub low_frequency: f32,
+    pub low_slope: CrossoverSlope,
+    pub high_frequency: f32,
+    pub high_slope: CrossoverSlope,
+}
+
+/// Splits the signal into [`NUM_BANDS`] bands, and detects and corrects each one on its own before summing them back
+/// up. This way a heavily panned bass doesn't drag the rest of the mix around with it. The bands only get detected
+/// over the current block, they don't go through the lookahead
+#[derive(Default)]
+pub struct MultibandCorrector {
+    sample_rate: f32,
+    low_crossover: Crossover,
+    high_crossover: Crossover,
+    /// Gives the low band the phase shift of the high crossover, so the bands sum back up flat
+    low_allpass: Crossover,
+    band_frames: [Vec<(f32, f32)>; NUM_BANDS],
+    estimators: [Estimators; NUM_BANDS],
+    smoothers: [Smoother<f32>; NUM_BANDS],
+}
+
+impl MultibandCorrector {
+    pub fn initialize(&mut self, sample_rate: f32, max_buffer_size: usize) {
+        self.sample_rate = sample_rate;
+        // force the coefficients to be recalculated for the new sample rate
+        for crossover in [
+            &mut self.low_crossover,
+            &mut self.high_crossover,
+            &mut self.low_allpass,
+        ] {
+            crossover.invalidate();
+        }
+        for (frames, estimators) in self.band_frames.iter_mut().zip(self.estimators.iter_mut()) {
+            frames.reserve(max_buffer_size);
+            estimators.initialize(max_buffer_size);
+        }
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        for crossover in [
+            &mut self.low_crossover,
+            &mut self.high_crossover,
+            &mut self.low_allpass,
+        ] {
+            crossover.reset();
+        }
+        for smoother in &mut self.smoothers {
+            smoother.reset(-45.0);
+        }
+    }
+
+    /// See [`Estimators::set_percentile`]
+    pub fn set_percentile(&mut self, percentile: f32) {
+        for estimators in &mut self.estimators {
+            estimators.set_percentile(percentile);
+        }
+    }
+
+    /// Splits the block into bands and points every band's correction at its detected angle, unless `held` is set.
+    /// Call this before [`Self::next_frame`]
+    pub fn analyze(
+        &mut self,
+        buffer: &mut Buffer,
+        crossovers: &CrossoverSettings,
+        estimator_kind: EstimatorKind,
+        silence_behavior: SilenceBehavior,
+        smoothing_style: SmoothingStyle,
+        held: bool,
+    ) {
+        self.low_crossover.set(
+            self.sample_rate,
+            crossovers.low_frequency,
+            crossovers.low_slope,
+        );
+        self.high_crossover.set(
+            self.sample_rate,
+            crossovers.high_frequency,
+            crossovers.high_slope,
+        );
+        self.low_allpass.set(
+            self.sample_rate,
+            crossovers.high_frequency,
+            crossovers.high_slope,
+        );
+
+        for frames in &mut self.band_frames {
+            frames.clear();
+        }
+        for mut channel_samples in buffer.iter_samples() {
+            let left = *channel_samples.get_mut(0).unwrap();
+            let right = *channel_samples.get_mut(1).unwrap();
+
+            let (low, rest) = self.low_crossover.split(left, right);
+            let (mid, high) = self.high_crossover.split(rest.0, rest.1);
+            let low = self.low_allpass.allpass(low.0, low.1);
+
+            for (frames, frame) in self.band_frames.iter_mut().zip([low, mid, high]) {
+                frames.push(frame);
+            }
+        }
+
+        for ((frames, estimators), smoother) in self
+            .band_frames
+            .iter()
+            .zip(self.estimators.iter_mut())
+            .zip(self.smoothers.iter_mut())
+        {
+            if held {
+                continue;
+            }
+
+            let estimator = estimators.get(estimator_kind);
+            estimator.clear();
+            for &(left, right) in frames {
+                push_detection_frame(estimator, silence_behavior, left, right, 1.0);
+            }
+
+            // a band with nothing in it keeps going towards wherever it was going before, unless it's supposed to
+            // return to the center. a mostly anti-phase band keeps going too, instead of chasing the folded angle
+            let target = match estimator.estimate() {
+                Some(_) if estimator.anti_phase() => continue,
+                Some(angle) => angle,
+                None if silence_behavior == SilenceBehavior::Return => 45.0,
+                None => continue,
+            };
+            smoother.style = style_towards(&smoothing_style, smoother.previous_value(), target);
+            smoother.set_target(self.sample_rate, target);
+        }
+    }
+
+    /// Corrects every band of the frame at `idx` in the block that was last analyzed towards `target` and sums them back
+    /// up. `amount` scales the whole correction and `band_amounts` every band's share of it, all from 0 to 1. `inverse`
+    /// pushes every band away from the target instead, see [`correction_pan`]
+    pub fn next_frame(
+        &mut self,
+        idx: usize,
+        target: f32,
+        amount: f32,
+        band_amounts: [f32; NUM_BANDS],
+        inverse: bool,
+    ) -> (f32, f32) {
+        let (mut sum_left, mut sum_right) = (0.0, 0.0);
+        for ((frames, smoother), band_amount) in self
+            .band_frames
+            .iter()
+            .zip(self.smoothers.iter_mut())
+            .zip(band_amounts)
+        {
+            let (left, right) = frames[idx];
+            let pan = correction_pan(smoother.next(), target, amount * band_amount, inverse);
+            let (left, right) = rotate(left, right, pan);
+            sum_left += left;
+            sum_right += right;
+        }
+
+        (sum_left, sum_right)
+    }
+}
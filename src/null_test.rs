@@ -0,0 +1,108 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use nih_plug::{
+    prelude::{AtomicF32, Buffer},
+    util::gain_to_db,
+};
+
+const WINDOW_SECONDS: f32 = 1.0;
+const SILENCE: f32 = 1e-12;
+
+/// The editor's side of the null test
+#[derive(Default)]
+pub struct NullTestControl {
+    /// The audio thread only measures while this is on
+    pub active: AtomicBool,
+    /// Replaces the output with the residual, so it can be listened to
+    pub listening: AtomicBool,
+    input_mean_square: AtomicF32,
+    residual_mean_square: AtomicF32,
+}
+
+impl NullTestControl {
+    /// The residual's RMS level in dBFS
+    pub fn residual_db(&self) -> f32 {
+        gain_to_db(self.residual_mean_square.load(Ordering::Relaxed).sqrt())
+    }
+
+    /// How far below the input the residual sits, in dB. Returns `None` while the input is silent
+    pub fn depth_db(&self) -> Option<f32> {
+        let input = self.input_mean_square.load(Ordering::Relaxed);
+        if input < SILENCE {
+            return None;
+        }
+
+        let residual = self.residual_mean_square.load(Ordering::Relaxed);
+        Some(gain_to_db((residual / input).sqrt()))
+    }
+}
+
+/// Subtracts the output from the untouched input to show exactly what the plugin changes. Only the front left and
+/// right channels get compared. The processing itself adds no delay, so the input lines up with the output as is
+#[derive(Default)]
+pub struct NullTest {
+    input: Vec<(f32, f32)>,
+    weight: f32,
+}
+
+impl NullTest {
+    pub fn initialize(&mut self, sample_rate: f32, max_buffer_size: usize) {
+        self.input.reserve(max_buffer_size);
+        self.weight = (-(WINDOW_SECONDS * sample_rate).recip()).exp();
+    }
+
+    /// Keeps a copy of the input. Call this before anything touches the buffer
+    pub fn capture_input(&mut self, buffer: &mut Buffer, control: &NullTestControl) {
+        self.input.clear();
+        if !control.active.load(Ordering::Relaxed) {
+            return;
+        }
+
+        for mut channel_samples in buffer.iter_samples() {
+            self.input.push((
+                *channel_samples.get_mut(0).unwrap(),
+                *channel_samples.get_mut(1).unwrap(),
+            ));
+        }
+    }
+
+    /// Measures the residual, and puts it in place of the output if the editor is listening to it
+    pub fn process_output(&mut self, buffer: &mut Buffer, control: &NullTestControl) {
+        if self.input.is_empty() {
+            return;
+        }
+
+        let listening = control.listening.load(Ordering::Relaxed);
+        let mut input_mean_square = control.input_mean_square.load(Ordering::Relaxed);
+        let mut residual_mean_square = control.residual_mean_square.load(Ordering::Relaxed);
+        for (mut channel_samples, &(input_left, input_right)) in
+            buffer.iter_samples().zip(self.input.iter())
+        {
+            let residual_left = input_left - *channel_samples.get_mut(0).unwrap();
+            let residual_right = input_right - *channel_samples.get_mut(1).unwrap();
+
+            input_mean_square = input_mean_square.mul_add(
+                self.weight,
+                input_left.mul_add(input_left, input_right * input_right) / 2.0
+                    * (1.0 - self.weight),
+            );
+            residual_mean_square = residual_mean_square.mul_add(
+                self.weight,
+                residual_left.mul_add(residual_left, residual_right * residual_right) / 2.0
+                    * (1.0 - self.weight),
+            );
+
+            if listening {
+                *channel_samples.get_mut(0).unwrap() = residual_left;
+                *channel_samples.get_mut(1).unwrap() = residual_right;
+            }
+        }
+
+        control
+            .input_mean_square
+            .store(input_mean_square, Ordering::Relaxed);
+        control
+            .residual_mean_square
+            .store(residual_mean_square, Ordering::Relaxed);
+    }
+}
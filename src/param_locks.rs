@@ -0,0 +1,171 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use nih_plug::{
+    params::persist::PersistentField,
+    prelude::{AtomicF32, Param},
+};
+use serde::{Deserialize, Serialize};
+
+// these have to match the parameters' `#[id]` attributes
+pub const CORRECTION_AMOUNT_ID: &str = "correction-amount";
+pub const REACTION_TIME_ID: &str = "reaction-time";
+pub const RELEASE_TIME_ID: &str = "release-time";
+pub const LOOKAHEAD_ID: &str = "lookahead";
+pub const INPUT_GAIN_ID: &str = "input-gain";
+pub const OUTPUT_GAIN_ID: &str = "output-gain";
+pub const LOW_BAND_AMOUNT_ID: &str = "multiband-low-amount";
+pub const MID_BAND_AMOUNT_ID: &str = "multiband-mid-amount";
+pub const HIGH_BAND_AMOUNT_ID: &str = "multiband-high-amount";
+
+/// The IDs of every parameter that can be locked, which are the ones with a knob
+const LOCKABLE_PARAM_IDS: [&str; 9] = [
+    CORRECTION_AMOUNT_ID,
+    REACTION_TIME_ID,
+    RELEASE_TIME_ID,
+    LOOKAHEAD_ID,
+    INPUT_GAIN_ID,
+    OUTPUT_GAIN_ID,
+    LOW_BAND_AMOUNT_ID,
+    MID_BAND_AMOUNT_ID,
+    HIGH_BAND_AMOUNT_ID,
+];
+
+#[derive(Default)]
+struct Lock {
+    locked: AtomicBool,
+    normalized_value: AtomicF32,
+}
+
+/// Parameters that were locked from the editor by their ID, along with the normalized value they're locked to. The
+/// plugin processes a locked parameter at that value whatever a preset load or automation sets it to, whether or not
+/// the editor is open. The locks are saved with the project, see [`LockedValues`] for how restoring them works
+pub struct ParamLocks {
+    /// Every lockable parameter has an entry from the start, so the audio thread never has to wait on the map
+    locks: HashMap<&'static str, Lock>,
+}
+
+impl Default for ParamLocks {
+    fn default() -> Self {
+        Self {
+            locks: LOCKABLE_PARAM_IDS
+                .into_iter()
+                .map(|id| (id, Lock::default()))
+                .collect(),
+        }
+    }
+}
+
+impl ParamLocks {
+    /// Locks the parameter with the ID `id` at `param`'s current value
+    pub fn lock(&self, id: &str, param: &impl Param) {
+        if let Some(lock) = self.locks.get(id) {
+            lock.normalized_value
+                .store(param.unmodulated_normalized_value(), Ordering::Relaxed);
+            lock.locked.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn unlock(&self, id: &str) {
+        if let Some(lock) = self.locks.get(id) {
+            lock.locked.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// The normalized value the parameter with the ID `id` is locked at, if it's locked
+    pub fn locked_normalized_value(&self, id: &str) -> Option<f32> {
+        self.locks
+            .get(id)
+            .filter(|lock| lock.locked.load(Ordering::Relaxed))
+            .map(|lock| lock.normalized_value.load(Ordering::Relaxed))
+    }
+
+    /// The normalized value to process `param` at: the one it's locked at, or its own modulated value
+    pub fn normalized_value(&self, id: &str, param: &impl Param) -> f32 {
+        self.locked_normalized_value(id)
+            .unwrap_or_else(|| param.modulated_normalized_value())
+    }
+
+    /// The plain value to process `param` at: the one it's locked at, or `value` as the parameter has it. `value` is
+    /// passed in so smoothed parameters can keep their smoothers running while they're locked
+    pub fn plain_value<P: Param>(&self, id: &str, param: &P, value: P::Plain) -> P::Plain {
+        self.locked_normalized_value(id)
+            .map_or(value, |normalized| param.preview_plain(normalized))
+    }
+}
+
+/// The locked parameters' IDs and the normalized values they're locked at
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LockedValues(BTreeMap<String, f32>);
+
+/// Restoring the state only ever adds locks. A fresh instance reopening a project gets back exactly what was locked,
+/// while loading a preset into a running instance can't unlock or move anything that's locked there
+impl<'a> PersistentField<'a, LockedValues> for ParamLocks {
+    fn set(&self, new_value: LockedValues) {
+        for (id, normalized_value) in new_value.0 {
+            if let Some(lock) = self.locks.get(id.as_str()) {
+                if !lock.locked.load(Ordering::Relaxed) {
+                    lock.normalized_value
+                        .store(normalized_value.clamp(0.0, 1.0), Ordering::Relaxed);
+                    lock.locked.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&LockedValues) -> R,
+    {
+        f(&LockedValues(
+            self.locks
+                .keys()
+                .filter_map(|id| Some((id.to_string(), self.locked_normalized_value(id)?)))
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nih_plug::prelude::{FloatParam, FloatRange};
+
+    use super::*;
+
+    fn param(value: f32) -> FloatParam {
+        FloatParam::new("Test", value, FloatRange::Linear { min: 0.0, max: 1.0 })
+    }
+
+    fn restore(from: &ParamLocks, into: &ParamLocks) {
+        from.map(|values| into.set(LockedValues(values.0.clone())));
+    }
+
+    #[test]
+    fn locks_survive_a_reload() {
+        let locks = ParamLocks::default();
+        locks.lock(LOOKAHEAD_ID, &param(0.25));
+        locks.lock(OUTPUT_GAIN_ID, &param(0.75));
+        locks.unlock(OUTPUT_GAIN_ID);
+
+        let reloaded = ParamLocks::default();
+        restore(&locks, &reloaded);
+        assert_eq!(reloaded.locked_normalized_value(LOOKAHEAD_ID), Some(0.25));
+        assert_eq!(reloaded.locked_normalized_value(OUTPUT_GAIN_ID), None);
+    }
+
+    #[test]
+    fn restoring_never_moves_a_lock() {
+        let preset = ParamLocks::default();
+        preset.lock(LOOKAHEAD_ID, &param(0.25));
+        preset.lock(INPUT_GAIN_ID, &param(0.5));
+
+        let locks = ParamLocks::default();
+        locks.lock(LOOKAHEAD_ID, &param(0.75));
+        restore(&preset, &locks);
+        assert_eq!(locks.locked_normalized_value(LOOKAHEAD_ID), Some(0.75));
+        assert_eq!(locks.locked_normalized_value(INPUT_GAIN_ID), Some(0.5));
+    }
+}
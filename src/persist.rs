@@ -0,0 +1,48 @@
+use std::io::{Read, Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A binary encoding for persisted data that would be too big as plain JSON
+pub trait CompactCodec: Sized {
+    fn encode(&self, bytes: &mut Vec<u8>);
+
+    /// Returns `None` if the bytes are malformed
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+/// Persists a [`CompactCodec`] as deflated, base64 encoded bytes instead of going through the default field
+/// serialization, which keeps project files small and fast to load
+pub struct Compressed<T>(pub T);
+
+impl<T: CompactCodec> Serialize for Compressed<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::new();
+        self.0.encode(&mut bytes);
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        let compressed = encoder
+            .write_all(&bytes)
+            .and_then(|()| encoder.finish())
+            .map_err(ser::Error::custom)?;
+
+        serializer.serialize_str(&STANDARD.encode(compressed))
+    }
+}
+
+impl<'de, T: CompactCodec> Deserialize<'de> for Compressed<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let compressed = STANDARD.decode(encoded).map_err(de::Error::custom)?;
+
+        let mut bytes = Vec::new();
+        DeflateDecoder::new(compressed.as_slice())
+            .read_to_end(&mut bytes)
+            .map_err(de::Error::custom)?;
+
+        T::decode(&bytes)
+            .map(Self)
+            .ok_or_else(|| de::Error::custom("malformed compressed data"))
+    }
+}
@@ -0,0 +1,55 @@
+/// One click setups for specific jobs. A profile sets the parameters it cares about when it's picked, and can also
+/// keep some behavior pinned for as long as it's active
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Profile {
+    /// Whatever the parameters say
+    #[default]
+    Custom = 0,
+    /// For dropping onto a bus mid-show: no lookahead, a quick reaction over a short analysis window, a slew limit so
+    /// it never lurches, and nothing that can change the latency
+    Live = 1,
+    /// For careful offline work: the longest analysis window, the most robust estimator, and true peak metering
+    Mastering = 2,
+}
+
+impl From<u8> for Profile {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Live,
+            2 => Self::Mastering,
+            _ => Self::Custom,
+        }
+    }
+}
+
+impl Profile {
+    pub const ALL: [Self; 3] = [Self::Custom, Self::Live, Self::Mastering];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Custom => "CUSTOM",
+            Self::Live => "LIVE",
+            Self::Mastering => "MASTERING",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Custom => "Whatever the parameters say",
+            Self::Live => {
+                "Zero lookahead, a 10 ms reaction time over a 20 ms analysis window, and a 20°/s slew limit, with \
+                 the lookahead locked off so the latency can't change mid-show"
+            }
+            Self::Mastering => {
+                "The long 100 ms lookahead, a 500 ms analysis window, the median estimator at full precision, true \
+                 peak metering, and the suggested input gain"
+            }
+        }
+    }
+
+    /// Whether the lookahead parameter gets ignored, so the plugin never reports any latency
+    pub fn disables_lookahead(self) -> bool {
+        self == Self::Live
+    }
+}
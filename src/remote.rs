@@ -0,0 +1,428 @@
+use std::{
+    any::Any,
+    io::{BufRead, BufReader, ErrorKind, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use nih_plug::{
+    nih_log,
+    prelude::{AtomicF32, Editor, FloatParam, GuiContext, Param, ParamSetter, ParentWindowHandle},
+    util::gain_to_db,
+};
+use serde::Serialize;
+use tungstenite::Message;
+
+use crate::{
+    loudness::Loudness, mono_compat::MonoCompatMeter, param_locks::CORRECTION_AMOUNT_ID,
+    CenteredParams, GONIO_NUM_SAMPLES,
+};
+
+/// How often connected clients get a new frame
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+/// How long the servers sleep between checking for new connections and for being shut down
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+/// How long a client gets to send or take anything before its connection gets dropped, so a stalled one can't hold
+/// on to a thread forever
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How many connections each server handles at once. Anything past that gets closed right away
+const MAX_CONNECTIONS: usize = 32;
+/// How much of a request gets read before giving up on it, request line and headers together
+const MAX_REQUEST_HEAD_BYTES: u64 = 8 * 1024;
+/// The remote UI only shows every nth scope sample, phones don't need all of them
+const REMOTE_SCOPE_STRIDE: usize = 4;
+/// The knobs the remote UI mirrors, by parameter ID
+const REMOTE_KNOBS: [&str; 3] = ["correction-amount", "reaction-time", "lookahead"];
+const REMOTE_PAGE: &str = include_str!("../assets/remote.html");
+
+/// The host's GUI context, from the first time the editor was opened. Setting parameters from outside the editor
+/// needs one, and nih-plug only hands it to the editor
+pub type GuiContextSlot = Arc<Mutex<Option<Arc<dyn GuiContext>>>>;
+
+/// One snapshot of the meters, as sent to clients
+#[derive(Serialize)]
+struct MeteringFrame {
+    /// The angle the correction is currently turning the image by, in degrees
+    correction_deg: f32,
+    correlation: Option<f32>,
+    mono_compat: Option<f32>,
+    pre_peak_db: [f32; 2],
+    post_peak_db: [f32; 2],
+    /// The output's loudness over the last 400 ms, `None` while it's below -70 LUFS
+    momentary_lufs: Option<f32>,
+    /// The output's loudness over the last 3 seconds, `None` while it's below -70 LUFS
+    short_term_lufs: Option<f32>,
+}
+
+#[derive(Serialize)]
+struct RemoteKnob {
+    id: &'static str,
+    name: String,
+    /// Normalized, so the page doesn't need to know the ranges
+    value: f32,
+    display: String,
+}
+
+/// Everything the remote UI polls for
+#[derive(Serialize)]
+struct RemoteState {
+    meters: MeteringFrame,
+    knobs: Vec<RemoteKnob>,
+    pre_scope: Vec<[f32; 2]>,
+    post_scope: Vec<[f32; 2]>,
+    /// Whether the knobs can be changed from the page, see [`GuiContextSlot`]
+    controllable: bool,
+}
+
+/// Everything the metering frames get built from
+#[derive(Clone)]
+pub struct MeterSources {
+    pub params: Arc<CenteredParams>,
+    pub correcting_angle: Arc<AtomicF32>,
+    pub pre_stereo_data: Arc<[(AtomicF32, AtomicF32); GONIO_NUM_SAMPLES]>,
+    pub post_stereo_data: Arc<[(AtomicF32, AtomicF32); GONIO_NUM_SAMPLES]>,
+    pub pre_peak_meter: Arc<(AtomicF32, AtomicF32)>,
+    pub post_peak_meter: Arc<(AtomicF32, AtomicF32)>,
+    pub post_mono_compat: Arc<MonoCompatMeter>,
+    pub loudness: Arc<Loudness>,
+}
+
+impl MeterSources {
+    fn frame(&self) -> MeteringFrame {
+        let load_db = |meter: &(AtomicF32, AtomicF32)| {
+            [
+                gain_to_db(meter.0.load(Ordering::Relaxed)),
+                gain_to_db(meter.1.load(Ordering::Relaxed)),
+            ]
+        };
+
+        MeteringFrame {
+            // same conversion the editor does for the correction arc
+            correction_deg: self.correcting_angle.load(Ordering::Relaxed).to_degrees()
+                + 90.0
+                    * self
+                        .params
+                        .param_locks
+                        .normalized_value(CORRECTION_AMOUNT_ID, &self.params.correction_amount),
+            correlation: self.post_mono_compat.correlation(),
+            mono_compat: self.post_mono_compat.score(),
+            pre_peak_db: load_db(&self.pre_peak_meter),
+            post_peak_db: load_db(&self.post_peak_meter),
+            momentary_lufs: self.loudness.momentary_lufs(),
+            short_term_lufs: self.loudness.short_term_lufs(),
+        }
+    }
+
+    fn remote_state(&self, controllable: bool) -> RemoteState {
+        let load_scope = |data: &[(AtomicF32, AtomicF32)]| {
+            data.iter()
+                .step_by(REMOTE_SCOPE_STRIDE)
+                .map(|(left, right)| [left.load(Ordering::Relaxed), right.load(Ordering::Relaxed)])
+                .collect()
+        };
+
+        RemoteState {
+            meters: self.frame(),
+            knobs: REMOTE_KNOBS
+                .iter()
+                .filter_map(|&id| {
+                    let param = remote_knob(&self.params, id)?;
+                    Some(RemoteKnob {
+                        id,
+                        name: param.name().to_string(),
+                        value: param.unmodulated_normalized_value(),
+                        display: param.to_string(),
+                    })
+                })
+                .collect(),
+            pre_scope: load_scope(self.pre_stereo_data.as_slice()),
+            post_scope: load_scope(self.post_stereo_data.as_slice()),
+            controllable,
+        }
+    }
+}
+
+fn remote_knob<'a>(params: &'a CenteredParams, id: &str) -> Option<&'a FloatParam> {
+    match id {
+        "correction-amount" => Some(&params.correction_amount),
+        "reaction-time" => Some(&params.reaction_time),
+        "lookahead" => Some(&params.lookahead),
+        _ => None,
+    }
+}
+
+/// Streams JSON metering frames to any WebSocket clients on localhost, for stream overlays and external dashboards.
+/// The server shuts down when this is dropped
+pub struct MeteringServer {
+    shutdown: Arc<AtomicBool>,
+}
+
+impl MeteringServer {
+    /// Starts listening on `port`. Returns `None` if the port can't be bound, which usually means another instance
+    /// already has it
+    pub fn start(port: u16, sources: MeterSources) -> Option<Self> {
+        let shutdown = listen(Ipv4Addr::LOCALHOST, port, move |stream, shutdown| {
+            stream_frames(stream, &sources, shutdown);
+        })?;
+
+        Some(Self { shutdown })
+    }
+}
+
+impl Drop for MeteringServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Serves a small web page that mirrors the scope, meters, and main knobs, so the plugin can be watched and tweaked
+/// from a phone or tablet. This listens on every interface, so anyone on the network can reach it. The server shuts
+/// down when this is dropped
+pub struct RemoteUiServer {
+    shutdown: Arc<AtomicBool>,
+}
+
+impl RemoteUiServer {
+    /// Starts listening on `port`. Returns `None` if the port can't be bound, which usually means another instance
+    /// already has it
+    pub fn start(port: u16, sources: MeterSources, gui_context: GuiContextSlot) -> Option<Self> {
+        let shutdown = listen(Ipv4Addr::UNSPECIFIED, port, move |stream, _| {
+            if let Err(err) = serve_request(stream, &sources, &gui_context) {
+                nih_log!("Remote UI request failed: {err}");
+            }
+        })?;
+
+        Some(Self { shutdown })
+    }
+}
+
+impl Drop for RemoteUiServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Hands the plugin's editor through unchanged, but keeps the GUI context it gets opened with in a
+/// [`GuiContextSlot`]
+pub struct ContextCapturingEditor {
+    pub inner: Box<dyn Editor>,
+    pub gui_context: GuiContextSlot,
+}
+
+impl Editor for ContextCapturingEditor {
+    fn spawn(
+        &self,
+        parent: ParentWindowHandle,
+        context: Arc<dyn GuiContext>,
+    ) -> Box<dyn Any + Send> {
+        *self.gui_context.lock().unwrap() = Some(context.clone());
+        self.inner.spawn(parent, context)
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.inner.size()
+    }
+
+    fn set_scale_factor(&self, factor: f32) -> bool {
+        self.inner.set_scale_factor(factor)
+    }
+
+    fn param_value_changed(&self, id: &str, normalized_value: f32) {
+        self.inner.param_value_changed(id, normalized_value);
+    }
+
+    fn param_modulation_changed(&self, id: &str, modulation_offset: f32) {
+        self.inner.param_modulation_changed(id, modulation_offset);
+    }
+
+    fn param_values_changed(&self) {
+        self.inner.param_values_changed();
+    }
+}
+
+/// Binds a listener and hands every connection to `handle` on its own thread, up to [`MAX_CONNECTIONS`] at a time,
+/// until the returned flag gets set
+fn listen(
+    address: Ipv4Addr,
+    port: u16,
+    handle: impl Fn(TcpStream, &AtomicBool) + Send + Sync + 'static,
+) -> Option<Arc<AtomicBool>> {
+    let listener = match TcpListener::bind((address, port)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            nih_log!("Couldn't listen on port {port}: {err}");
+            return None;
+        }
+    };
+    // polling for connections lets the thread notice when it's time to shut down
+    listener.set_nonblocking(true).ok()?;
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    {
+        let shutdown = shutdown.clone();
+        let handle = Arc::new(handle);
+        let connections = Arc::new(AtomicUsize::new(0));
+        thread::spawn(move || {
+            while !shutdown.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        // some platforms hand out sockets that inherit the listener's non-blocking mode
+                        if stream.set_nonblocking(false).is_err()
+                            || stream.set_read_timeout(Some(CLIENT_TIMEOUT)).is_err()
+                            || stream.set_write_timeout(Some(CLIENT_TIMEOUT)).is_err()
+                        {
+                            continue;
+                        }
+                        // dropping the stream closes the connection
+                        if connections.fetch_add(1, Ordering::Relaxed) >= MAX_CONNECTIONS {
+                            connections.fetch_sub(1, Ordering::Relaxed);
+                            continue;
+                        }
+
+                        let handle = handle.clone();
+                        let shutdown = shutdown.clone();
+                        let connections = connections.clone();
+                        thread::spawn(move || {
+                            handle(stream, &shutdown);
+                            connections.fetch_sub(1, Ordering::Relaxed);
+                        });
+                    }
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_INTERVAL);
+                    }
+                    Err(err) => {
+                        nih_log!("Couldn't accept a connection on port {port}: {err}");
+                        thread::sleep(ACCEPT_INTERVAL);
+                    }
+                }
+            }
+        });
+    }
+
+    Some(shutdown)
+}
+
+fn stream_frames(stream: TcpStream, sources: &MeterSources, shutdown: &AtomicBool) {
+    let Ok(mut socket) = tungstenite::accept(stream) else {
+        return;
+    };
+
+    while !shutdown.load(Ordering::Relaxed) {
+        let Ok(frame) = serde_json::to_string(&sources.frame()) else {
+            break;
+        };
+        // this fails once the client goes away
+        if socket.send(Message::Text(frame)).is_err() {
+            return;
+        }
+
+        thread::sleep(FRAME_INTERVAL);
+    }
+
+    let _ = socket.close(None);
+}
+
+/// Handles a single HTTP request. The page only ever needs three routes, so this doesn't bother with anything more
+/// than the request line. Reading stops after [`MAX_REQUEST_HEAD_BYTES`], and the listener's timeout covers clients
+/// that stop sending halfway
+fn serve_request(
+    stream: TcpStream,
+    sources: &MeterSources,
+    gui_context: &GuiContextSlot,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_HEAD_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // skip the headers, none of them matter here. Running out before the blank line after them means the request was
+    // either cut off or too big
+    let mut header = String::new();
+    let complete = loop {
+        header.clear();
+        if reader.read_line(&mut header)? == 0 {
+            break false;
+        }
+        if header.trim_end().is_empty() {
+            break true;
+        }
+    };
+
+    let too_big = reader.get_ref().limit() == 0;
+    let mut stream = reader.into_inner().into_inner();
+    if !complete {
+        return if too_big {
+            respond(
+                &mut stream,
+                "431 Request Header Fields Too Large",
+                "text/plain",
+                b"Request too large",
+            )
+        } else {
+            respond(&mut stream, "400 Bad Request", "text/plain", b"Bad request")
+        };
+    }
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return respond(&mut stream, "400 Bad Request", "text/plain", b"Bad request");
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let context = gui_context.lock().unwrap().clone();
+
+    match (method, path) {
+        ("GET", "/") => respond(&mut stream, "200 OK", "text/html", REMOTE_PAGE.as_bytes()),
+        ("GET", "/state") => {
+            let state = serde_json::to_vec(&sources.remote_state(context.is_some()))
+                .map_err(std::io::Error::other)?;
+            respond(&mut stream, "200 OK", "application/json", &state)
+        }
+        ("POST", "/set") => {
+            let mut id = None;
+            let mut value = None;
+            for (key, val) in form_urlencoded::parse(query.as_bytes()) {
+                match &*key {
+                    "id" => id = Some(val.into_owned()),
+                    "value" => value = val.parse::<f32>().ok(),
+                    _ => (),
+                }
+            }
+
+            let param = id.and_then(|id| remote_knob(&sources.params, &id));
+            match (param, value, context) {
+                (Some(param), Some(value), Some(context)) => {
+                    let setter = ParamSetter::new(context.as_ref());
+                    setter.begin_set_parameter(param);
+                    setter.set_parameter_normalized(param, value.clamp(0.0, 1.0));
+                    setter.end_set_parameter(param);
+                    respond(&mut stream, "204 No Content", "text/plain", b"")
+                }
+                (_, _, None) => respond(
+                    &mut stream,
+                    "409 Conflict",
+                    "text/plain",
+                    b"Open the plugin window once to enable remote control",
+                ),
+                _ => respond(&mut stream, "400 Bad Request", "text/plain", b"Bad request"),
+            }
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", b"Not found"),
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: \
+         close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)
+}
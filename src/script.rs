@@ -0,0 +1,167 @@
+use std::sync::{Arc, Mutex, RwLock};
+
+use nih_plug::{params::persist::PersistentField, util::permit_alloc};
+use rhai::{Dynamic, Engine, Scope, AST, FLOAT};
+use serde::{Deserialize, Serialize};
+
+/// What the script gets to look at. Angles are in degrees relative to the center, with positive angles leaning to
+/// the right
+pub struct WindowStats {
+    pub mean_angle: f32,
+    pub correlation: f32,
+    pub rms_left: f32,
+    pub rms_right: f32,
+}
+
+impl WindowStats {
+    pub fn new(mean_angle: f32, frames: impl Iterator<Item = (f32, f32)>) -> Self {
+        let (mut left_energy, mut right_energy, mut cross_energy, mut num_frames) =
+            (0.0, 0.0, 0.0, 0);
+        for (left, right) in frames {
+            left_energy = left.mul_add(left, left_energy);
+            right_energy = right.mul_add(right, right_energy);
+            cross_energy = left.mul_add(right, cross_energy);
+            num_frames += 1;
+        }
+
+        let num_frames = num_frames.max(1) as f32;
+        let correlation = if left_energy > 0.0 && right_energy > 0.0 {
+            cross_energy / (left_energy * right_energy).sqrt()
+        } else {
+            0.0
+        };
+
+        Self {
+            mean_angle,
+            correlation,
+            rms_left: (left_energy / num_frames).sqrt(),
+            rms_right: (right_energy / num_frames).sqrt(),
+        }
+    }
+}
+
+/// A user supplied rhai expression that turns the [`WindowStats`] into the correction target, for experimenting with
+/// custom centering strategies. Only expressions are allowed (no loops, functions, or statements) and the engine is
+/// capped on operations, so a script can't hang the audio thread or reach outside of its sandbox
+pub struct DetectionScript {
+    engine: Engine,
+    source: RwLock<String>,
+    ast: RwLock<Option<AST>>,
+    /// The last error from running the script, for the editor
+    error: Mutex<Option<String>>,
+}
+
+pub const DEFAULT_SCRIPT: &str = "mean_angle";
+
+impl Default for DetectionScript {
+    fn default() -> Self {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(10_000)
+            .set_max_expr_depths(32, 32)
+            .set_max_string_size(256)
+            .set_max_array_size(256)
+            .set_max_map_size(256)
+            .on_print(|_| {})
+            .on_debug(|_, _, _| {});
+
+        let ast = engine.compile_expression(DEFAULT_SCRIPT).ok();
+
+        Self {
+            engine,
+            source: RwLock::new(DEFAULT_SCRIPT.to_string()),
+            ast: RwLock::new(ast),
+            error: Mutex::new(None),
+        }
+    }
+}
+
+impl DetectionScript {
+    pub fn source(&self) -> String {
+        self.source.read().unwrap().clone()
+    }
+
+    /// Compiles and switches to a new script. If it doesn't compile, the old one keeps running
+    pub fn set_source(&self, source: &str) -> Result<(), String> {
+        let ast = self
+            .engine
+            .compile_expression(source)
+            .map_err(|err| err.to_string())?;
+
+        *self.ast.write().unwrap() = Some(ast);
+        *self.source.write().unwrap() = source.to_string();
+        *self.error.lock().unwrap() = None;
+        Ok(())
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    /// Runs the script, returning its target angle relative to the center. This is called from the audio thread, so
+    /// it returns `None` instead of waiting if the editor is busy swapping scripts
+    pub fn evaluate(&self, stats: &WindowStats) -> Option<f32> {
+        let ast = self.ast.try_read().ok()?;
+        let ast = ast.as_ref()?;
+
+        // rhai allocates all over the place. this is an opt in experimentation feature, so that's a trade off worth
+        // making here
+        permit_alloc(|| {
+            let mut scope = Scope::new();
+            scope.push("mean_angle", FLOAT::from(stats.mean_angle));
+            scope.push("correlation", FLOAT::from(stats.correlation));
+            scope.push("rms_left", FLOAT::from(stats.rms_left));
+            scope.push("rms_right", FLOAT::from(stats.rms_right));
+
+            let result = self
+                .engine
+                .eval_ast_with_scope::<Dynamic>(&mut scope, ast)
+                .map_err(|err| err.to_string())
+                .and_then(|value| {
+                    value
+                        .as_float()
+                        .or_else(|_| value.as_int().map(|value| value as FLOAT))
+                        .map_err(|type_name| format!("expected a number, got {type_name}"))
+                });
+
+            match result {
+                Ok(angle) if angle.is_finite() => Some(angle as f32),
+                Ok(angle) => {
+                    self.report_error(format!("the script returned {angle}"));
+                    None
+                }
+                Err(err) => {
+                    self.report_error(err);
+                    None
+                }
+            }
+        })
+    }
+
+    fn report_error(&self, error: String) {
+        if let Ok(mut last_error) = self.error.try_lock() {
+            *last_error = Some(error);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ScriptSource(String);
+
+/// The script gets saved with the project
+impl<'a> PersistentField<'a, ScriptSource> for Arc<DetectionScript> {
+    fn set(&self, new_value: ScriptSource) {
+        // a script that doesn't compile anymore (say, after a rhai update) falls back to the default
+        if self.set_source(&new_value.0).is_err() {
+            let _ = self.set_source(DEFAULT_SCRIPT);
+        }
+    }
+
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&ScriptSource) -> R,
+    {
+        f(&ScriptSource(self.source()))
+    }
+}
@@ -0,0 +1,53 @@
+use nih_plug::prelude::Buffer;
+
+const CROSSFADE_MS: f32 = 10.0;
+
+/// Fades between the untouched input and the processed output, so bypassing doesn't click. The processing keeps
+/// running underneath, so the detector is already settled when the bypass gets turned off again. The processing adds
+/// no delay, so the input lines up with the output as is and the reported latency stays the same either way
+#[derive(Default)]
+pub struct SoftBypass {
+    dry: Vec<Vec<f32>>,
+    /// 0 is fully processed, 1 is fully bypassed
+    mix: f32,
+    step: f32,
+}
+
+impl SoftBypass {
+    pub fn initialize(&mut self, sample_rate: f32, num_channels: usize, max_buffer_size: usize) {
+        self.dry = vec![Vec::with_capacity(max_buffer_size); num_channels];
+        self.step = (CROSSFADE_MS / 1000.0 * sample_rate).recip();
+    }
+
+    /// Jumps straight to the current bypass state, there's nothing to fade from after a reset
+    pub fn reset(&mut self, bypassed: bool) {
+        self.mix = if bypassed { 1.0 } else { 0.0 };
+    }
+
+    /// Keeps a copy of the input. Call this before anything touches the buffer
+    pub fn capture_input(&mut self, buffer: &mut Buffer) {
+        for (dry, channel) in self.dry.iter_mut().zip(buffer.as_slice_immutable()) {
+            dry.clear();
+            dry.extend_from_slice(channel);
+        }
+    }
+
+    pub fn process_output(&mut self, buffer: &mut Buffer, bypassed: bool) {
+        let target = if bypassed { 1.0 } else { 0.0 };
+        if self.mix == 0.0 && target == 0.0 {
+            return;
+        }
+
+        for (sample_idx, mut channel_samples) in buffer.iter_samples().enumerate() {
+            self.mix = if target > self.mix {
+                (self.mix + self.step).min(target)
+            } else {
+                (self.mix - self.step).max(target)
+            };
+
+            for (sample, dry) in channel_samples.iter_mut().zip(&self.dry) {
+                *sample += (dry[sample_idx] - *sample) * self.mix;
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::config_dir;
+
+/// The audio backends nih-plug's standalone wrapper can be asked for on this platform
+pub const BACKENDS: &[&str] = &[
+    "auto",
+    "jack",
+    #[cfg(target_os = "linux")]
+    "alsa",
+    #[cfg(target_os = "macos")]
+    "core-audio",
+    #[cfg(target_os = "windows")]
+    "wasapi",
+    "dummy",
+];
+pub const SAMPLE_RATES: &[u32] = &[44100, 48000, 88200, 96000, 176_400, 192_000];
+pub const PERIOD_SIZES: &[u32] = &[32, 64, 128, 256, 512, 1024, 2048, 4096];
+
+static IS_STANDALONE: AtomicBool = AtomicBool::new(false);
+
+/// Called by the standalone binary before it starts, so the editor knows to show the audio settings
+pub fn mark_standalone() {
+    IS_STANDALONE.store(true, Ordering::Relaxed);
+}
+
+pub fn is_standalone() -> bool {
+    IS_STANDALONE.load(Ordering::Relaxed)
+}
+
+/// Audio settings for the standalone build, saved in the user's config directory. Anything left as `None` uses the
+/// wrapper's default
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct StandaloneConfig {
+    pub backend: Option<String>,
+    pub input_device: Option<String>,
+    pub output_device: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub period_size: Option<u32>,
+    /// Which JACK ports to connect to the plugin's inputs, as a comma separated list. This is how the input channel
+    /// pair gets picked with JACK, the other backends always use the device's first two channels
+    pub jack_inputs: Option<String>,
+}
+
+impl StandaloneConfig {
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("standalone.toml"))
+    }
+
+    /// Loads the saved settings, falling back to the defaults if there aren't any or they can't be read
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|config| toml::from_str(&config).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        let config = toml::to_string_pretty(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, config)
+    }
+
+    /// Appends the settings to the command line arguments for the standalone wrapper. Options that were already
+    /// passed on the command line take precedence over the saved ones
+    pub fn apply_to_args(&self, args: &mut Vec<String>) {
+        let options = [
+            ("--backend", self.backend.clone()),
+            ("--input-device", self.input_device.clone()),
+            ("--output-device", self.output_device.clone()),
+            (
+                "--sample-rate",
+                self.sample_rate.map(|rate| rate.to_string()),
+            ),
+            (
+                "--period-size",
+                self.period_size.map(|size| size.to_string()),
+            ),
+            ("--connect-jack-inputs", self.jack_inputs.clone()),
+        ];
+
+        for (flag, value) in options {
+            let Some(value) = value else {
+                continue;
+            };
+
+            let already_passed = args
+                .iter()
+                .any(|arg| arg == flag || arg.starts_with(&format!("{flag}=")));
+            if !already_passed {
+                args.push(flag.to_string());
+                args.push(value);
+            }
+        }
+    }
+}
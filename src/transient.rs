@@ -0,0 +1,52 @@
+/// How quickly the fast envelope follows the signal
+const FAST_ENVELOPE_MS: f32 = 1.0;
+/// How quickly the slow envelope follows the signal, anything shorter than this can count as a transient
+const SLOW_ENVELOPE_MS: f32 = 50.0;
+/// How far the fast envelope has to jump over the slow one for a frame to count as a transient, 6 dB in energy terms
+const TRANSIENT_RATIO: f32 = 4.0;
+
+/// Finds the transients in the detection window, so hard panned percussion can be left out of the estimate. A frame
+/// counts as a transient while a fast energy envelope sits well above a slow one, which covers the hit itself and its
+/// first few milliseconds of decay
+#[derive(Default)]
+pub struct TransientDetector {
+    fast_weight: f32,
+    slow_weight: f32,
+    /// Whether every frame of the last window was a transient, in the window's own order
+    flags: Vec<bool>,
+}
+
+impl TransientDetector {
+    pub fn initialize(&mut self, sample_rate: f32, max_window_size: usize) {
+        self.fast_weight = (-(FAST_ENVELOPE_MS / 1000.0 * sample_rate).recip()).exp();
+        self.slow_weight = (-(SLOW_ENVELOPE_MS / 1000.0 * sample_rate).recip()).exp();
+        self.flags.reserve(max_window_size);
+    }
+
+    /// Flags the transients in `window`, a ring buffer whose oldest frame is at `start`. The envelopes start out at
+    /// the window's average energy, so its first frames don't all look like onsets
+    pub fn detect(&mut self, window: &[(f32, f32)], start: usize) {
+        self.flags.clear();
+        self.flags.resize(window.len(), false);
+        if window.is_empty() {
+            return;
+        }
+
+        let energy = |(left, right): (f32, f32)| left.mul_add(left, right * right);
+        let mean_energy =
+            window.iter().map(|&frame| energy(frame)).sum::<f32>() / window.len() as f32;
+        let (mut fast, mut slow) = (mean_energy, mean_energy);
+        let start = start % window.len();
+        for idx in (start..window.len()).chain(0..start) {
+            let energy = energy(window[idx]);
+            fast = fast.mul_add(self.fast_weight, energy * (1.0 - self.fast_weight));
+            slow = slow.mul_add(self.slow_weight, energy * (1.0 - self.slow_weight));
+            self.flags[idx] = fast > slow * TRANSIENT_RATIO;
+        }
+    }
+
+    /// Whether the frame at `idx` in the last window was a transient
+    pub fn is_transient(&self, idx: usize) -> bool {
+        self.flags.get(idx).copied().unwrap_or(false)
+    }
+}
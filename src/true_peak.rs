@@ -0,0 +1,82 @@
+use std::f32::consts::PI;
+
+use once_cell::sync::Lazy;
+
+const OVERSAMPLING: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+const NUM_TAPS: usize = OVERSAMPLING * TAPS_PER_PHASE;
+
+// polyphase windowed sinc interpolator, same shape as the one in ITU-R BS.1770-4 annex 2
+static COEFFICIENTS: Lazy<[[f32; TAPS_PER_PHASE]; OVERSAMPLING]> = Lazy::new(|| {
+    let mut coefficients = [[0.0; TAPS_PER_PHASE]; OVERSAMPLING];
+    let center = (NUM_TAPS - 1) as f32 / 2.0;
+
+    for (phase, phase_coefficients) in coefficients.iter_mut().enumerate() {
+        for (tap, coefficient) in phase_coefficients.iter_mut().enumerate() {
+            let n = (tap * OVERSAMPLING + phase) as f32;
+            let x = (n - center) / OVERSAMPLING as f32;
+            let sinc = if x == 0.0 {
+                1.0
+            } else {
+                (PI * x).sin() / (PI * x)
+            };
+            let window = 0.5 - 0.5 * (2.0 * PI * (n + 0.5) / NUM_TAPS as f32).cos();
+
+            *coefficient = sinc * window;
+        }
+
+        // every phase should pass DC at unity, otherwise the meter reads differently depending on where the peak lands
+        let sum: f32 = phase_coefficients.iter().sum();
+        for coefficient in phase_coefficients.iter_mut() {
+            *coefficient /= sum;
+        }
+    }
+
+    coefficients
+});
+
+/// 4x oversampled peak detector for a single channel
+pub struct TruePeakDetector {
+    history: [f32; TAPS_PER_PHASE],
+    history_idx: usize,
+}
+
+impl Default for TruePeakDetector {
+    fn default() -> Self {
+        Lazy::force(&COEFFICIENTS);
+
+        Self {
+            history: [0.0; TAPS_PER_PHASE],
+            history_idx: 0,
+        }
+    }
+}
+
+impl TruePeakDetector {
+    pub fn reset(&mut self) {
+        self.history = [0.0; TAPS_PER_PHASE];
+        self.history_idx = 0;
+    }
+
+    /// Feeds a sample into the interpolator and returns the highest absolute value between it and the previous sample
+    pub fn process(&mut self, sample: f32) -> f32 {
+        self.history[self.history_idx] = sample;
+
+        let mut peak = sample.abs();
+        for phase_coefficients in COEFFICIENTS.iter() {
+            let interpolated =
+                phase_coefficients
+                    .iter()
+                    .enumerate()
+                    .fold(0.0_f32, |acc, (tap, coefficient)| {
+                        let idx = (self.history_idx + TAPS_PER_PHASE - tap) % TAPS_PER_PHASE;
+                        coefficient.mul_add(self.history[idx], acc)
+                    });
+            peak = peak.max(interpolated.abs());
+        }
+
+        self.history_idx = (self.history_idx + 1) % TAPS_PER_PHASE;
+
+        peak
+    }
+}
@@ -0,0 +1,141 @@
+use std::{f32::consts::TAU, sync::Arc};
+
+use nih_plug::util;
+use realfft::{num_complex::Complex, RealFftPlanner, RealToComplex};
+
+use crate::{SPEECH_BAND_HZ, SPEECH_GATE_DB};
+
+/// How much audio every decision looks at, long enough to resolve a voice's harmonics. Rounded up to a power of two
+const FRAME_MS: f32 = 20.0;
+/// Frames have to be this far above the noise floor to count as speech
+const SPEECH_MARGIN_DB: f32 = 6.0;
+/// Voices are harmonic, so their spectrum is far from flat. Anything flatter than this is room tone, a breath, or
+/// some other kind of noise
+const MAX_SPEECH_FLATNESS: f32 = 0.3;
+/// How quickly the noise floor estimate creeps up while everything stays above it. It drops down to a quieter frame
+/// straight away
+const NOISE_FLOOR_RISE_DB_PER_SECOND: f32 = 3.0;
+/// Speech keeps counting for this long after the last voiced frame, so the gaps between words don't close the gate
+const HANGOVER_MS: f32 = 300.0;
+
+/// A simple voice activity detector that goes by energy and spectral flatness. A frame counts as speech when it's
+/// louder than [`SPEECH_GATE_DB`], clearly above the noise floor, and its speech band isn't flat like noise is
+#[derive(Default)]
+pub struct VoiceActivityDetector {
+    fft: Option<Arc<dyn RealToComplex<f32>>>,
+    window: Vec<f32>,
+    /// The last frame of the mono sum, a ring buffer
+    frame: Vec<f32>,
+    frame_idx: usize,
+    /// How many samples got written since the last decision. Frames overlap by half
+    pending_samples: usize,
+    fft_input: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    /// The bins the flatness gets measured over
+    band_bins: (usize, usize),
+    /// As a mean square, like the frame energies
+    noise_floor: f32,
+    noise_floor_rise: f32,
+    hangover_samples: usize,
+    remaining_hangover: usize,
+}
+
+impl VoiceActivityDetector {
+    pub fn initialize(&mut self, sample_rate: f32) {
+        let frame_len = ((FRAME_MS / 1000.0 * sample_rate) as usize).next_power_of_two();
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(frame_len);
+
+        self.window = (0..frame_len)
+            .map(|idx| 0.5 - 0.5 * (TAU * idx as f32 / frame_len as f32).cos())
+            .collect();
+        self.frame = vec![0.0; frame_len];
+        self.fft_input = fft.make_input_vec();
+        self.spectrum = fft.make_output_vec();
+        self.scratch = fft.make_scratch_vec();
+        let bin = |frequency: f32| {
+            ((frequency * frame_len as f32 / sample_rate).round() as usize).min(frame_len / 2)
+        };
+        let low_bin = bin(SPEECH_BAND_HZ.0);
+        self.band_bins = (low_bin, bin(SPEECH_BAND_HZ.1).max(low_bin + 1));
+        let hop_seconds = (frame_len / 2) as f32 / sample_rate;
+        self.noise_floor_rise =
+            util::db_to_gain(NOISE_FLOOR_RISE_DB_PER_SECOND * hop_seconds).powi(2);
+        self.hangover_samples = (HANGOVER_MS / 1000.0 * sample_rate) as usize;
+        self.fft = Some(fft);
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.frame.fill(0.0);
+        self.frame_idx = 0;
+        self.pending_samples = 0;
+        // start at the quietest level that can be speech, and let the estimate work its way up to the room tone
+        self.noise_floor = util::db_to_gain(SPEECH_GATE_DB).powi(2);
+        self.remaining_hangover = 0;
+    }
+
+    /// Listens to a block of `(left, right)` frames, and returns whether someone was talking by the end of it
+    pub fn process(&mut self, frames: &[(f32, f32)]) -> bool {
+        if self.frame.is_empty() {
+            return false;
+        }
+
+        for &(left, right) in frames {
+            self.frame[self.frame_idx] = (left + right) * 0.5;
+            self.frame_idx = (self.frame_idx + 1) % self.frame.len();
+            self.remaining_hangover = self.remaining_hangover.saturating_sub(1);
+
+            self.pending_samples += 1;
+            if self.pending_samples >= self.frame.len() / 2 {
+                self.pending_samples = 0;
+                if self.is_speech() {
+                    self.remaining_hangover = self.hangover_samples;
+                }
+            }
+        }
+
+        self.remaining_hangover > 0
+    }
+
+    /// Classifies the current frame, and follows the noise floor along the way
+    fn is_speech(&mut self) -> bool {
+        let Some(fft) = &self.fft else {
+            return false;
+        };
+
+        let frame_len = self.frame.len();
+        let mut sum_squares = 0.0;
+        for (idx, (input, window)) in self.fft_input.iter_mut().zip(&self.window).enumerate() {
+            let sample = self.frame[(self.frame_idx + idx) % frame_len];
+            sum_squares = sample.mul_add(sample, sum_squares);
+            *input = sample * window;
+        }
+        let energy = sum_squares / frame_len as f32;
+        self.noise_floor = (self.noise_floor * self.noise_floor_rise).min(energy);
+
+        if energy < util::db_to_gain(SPEECH_GATE_DB).powi(2)
+            || energy < self.noise_floor * util::db_to_gain(SPEECH_MARGIN_DB).powi(2)
+        {
+            return false;
+        }
+
+        if fft
+            .process_with_scratch(&mut self.fft_input, &mut self.spectrum, &mut self.scratch)
+            .is_err()
+        {
+            return false;
+        }
+
+        // the flatness is the geometric mean of the band's power over its arithmetic mean
+        let band = &self.spectrum[self.band_bins.0..self.band_bins.1];
+        let (log_sum, sum) = band.iter().fold((0.0, 0.0), |(log_sum, sum), bin| {
+            let power = bin.norm_sqr() + f32::MIN_POSITIVE;
+            (log_sum + power.ln(), sum + power)
+        });
+        let num_bins = band.len() as f32;
+        let flatness = (log_sum / num_bins).exp() / (sum / num_bins);
+
+        flatness < MAX_SPEECH_FLATNESS
+    }
+}